@@ -0,0 +1,370 @@
+use super::{ethertype_numbers, protocol_numbers, ComputedField, Packet};
+use crate::headers::{
+    AnyHeader, ArpHeader, EthernetHeader, IcmpHeader, IpHeader, LlcHeader, LldpHeader, LldpTlv, MplsHeader, PwControlWordHeader, SnapHeader, TcpHeader, UdpHeader, VlanHeader,
+    CHASSIS_ID_MAC_ADDRESS, DONT_FRAGMENT, HARDWARE_ETHERNET, OUI_ETHERTYPE, PORT_ID_INTERFACE_NAME, REQUEST,
+};
+use std::fmt::Write;
+use std::net::IpAddr;
+
+impl Packet {
+    /// Returns Rust source code which rebuilds this packet with the header constructors and setters of this crate,
+    /// so that a captured packet can be pasted straight into a regression test:
+    ///
+    /// ```text
+    /// let mut ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP);
+    /// ip.set_identification(4660);
+    /// let tcp = headers::TcpHeader::new(1234, 80);
+    /// let packet = Packet::from_layers(vec![ip.into(), tcp.into()], Vec::new());
+    /// ```
+    ///
+    /// The code expects `headers`, `ethertype_numbers`, `Packet`, `Protocol` and `ComputedField` to be imported from `packet_crafter`.
+    /// Only the fields which differ from what the constructors set are written out. The length and checksum fields and the FCS
+    /// are left for the packet to calculate, unless this packet has them wrong, in which case they are fixed at the same wrong
+    /// values with `set_override` and `set_fcs_override`, so the packet comes out byte for byte the same
+    pub fn to_rust(&self) -> String {
+        let mut out = String::new();
+        let mut names: Vec<String> = Vec::new();
+        for layer in self.get_layers() {
+            let base = match &layer {
+                AnyHeader::Ethernet(_) => "eth",
+                AnyHeader::Arp(_) => "arp",
+                AnyHeader::Icmp(_) => "icmp",
+                AnyHeader::Ip(_) => "ip",
+                AnyHeader::Tcp(_) => "tcp",
+                AnyHeader::Udp(_) => "udp",
+                AnyHeader::Vlan(_) => "vlan",
+                AnyHeader::Llc(_) => "llc",
+                AnyHeader::Snap(_) => "snap",
+                AnyHeader::Lldp(_) => "lldp",
+                AnyHeader::Mpls(_) => "mpls",
+                AnyHeader::PwControlWord(_) => "cw",
+            };
+            let count = names.iter().filter(|name| name.split('_').next() == Some(base)).count();
+            let name = if count == 0 { String::from(base) } else { format!("{}_{}", base, count + 1) };
+            let (constructor, setters) = match &layer {
+                AnyHeader::Ethernet(h) => ethernet(h),
+                AnyHeader::Arp(h) => arp(h),
+                AnyHeader::Icmp(h) => icmp(h),
+                AnyHeader::Ip(h) => ip(h),
+                AnyHeader::Tcp(h) => tcp(h),
+                AnyHeader::Udp(h) => udp(h),
+                AnyHeader::Vlan(h) => vlan(h),
+                AnyHeader::Llc(h) => llc(h),
+                AnyHeader::Snap(h) => snap(h),
+                AnyHeader::Lldp(h) => lldp(h),
+                AnyHeader::Mpls(h) => mpls(h),
+                AnyHeader::PwControlWord(h) => pw_control_word(h),
+            };
+            let binding = if setters.is_empty() { "let" } else { "let mut" };
+            writeln!(out, "{} {} = headers::{};", binding, name, constructor).unwrap();
+            for (setter, value) in setters {
+                writeln!(out, "{}.{}({});", name, setter, value).unwrap();
+            }
+            names.push(name);
+        }
+
+        let payload = if self.payload.is_empty() {
+            String::from("Vec::new()")
+        } else {
+            writeln!(out, "let payload = vec![").unwrap();
+            for line in self.payload.chunks(12) {
+                let bytes: Vec<String> = line.iter().map(|b| format!("0x{:02x},", b)).collect();
+                writeln!(out, "    {}", bytes.join(" ")).unwrap();
+            }
+            writeln!(out, "];").unwrap();
+            String::from("payload")
+        };
+        let layers: Vec<String> = names.iter().map(|name| format!("{}.into()", name)).collect();
+        let mut trailer = Vec::new();
+        if !self.trailer.padding.is_empty() {
+            trailer.push(format!("packet.set_padding(vec!{});", hex_array(&self.trailer.padding)));
+        }
+        if self.trailer.pad_to_minimum {
+            trailer.push(String::from("packet.set_pad_to_minimum(true);"));
+        }
+        let mut rebuilt = self.clone();
+        rebuilt.clear_overrides();
+        for (index, field, value) in computed_field_overrides(self, &mut rebuilt) {
+            let value = if field == ComputedField::Checksum { format!("0x{:04x}", value) } else { value.to_string() };
+            trailer.push(format!("packet.set_override({}, ComputedField::{:?}, {});", index, field, value));
+        }
+        match self.get_fcs() {
+            Some(fcs) if rebuilt.get_fcs() != Some(fcs) => trailer.push(format!("packet.set_fcs_override(0x{:08x});", fcs)),
+            _ if self.trailer.fcs => trailer.push(String::from("packet.set_fcs(true);")),
+            _ => {}
+        }
+        let binding = if trailer.is_empty() { "let" } else { "let mut" };
+        writeln!(out, "{} packet = Packet::from_layers(vec![{}], {});", binding, layers.join(", "), payload).unwrap();
+        for line in trailer {
+            writeln!(out, "{}", line).unwrap();
+        }
+        out
+    }
+}
+
+type Setters = Vec<(&'static str, String)>;
+
+/// the computed fields `rebuilt` (the same packet without any overrides) has to be fixed at to match `packet`, which it is left with
+fn computed_field_overrides(packet: &Packet, rebuilt: &mut Packet) -> Vec<(usize, ComputedField, u16)> {
+    let mut overrides = Vec::new();
+    // one wrong field can throw off the fields calculated over it, e.g. a wrong IPv4 total length changes the header checksum,
+    // so only the first field which differs, in the order they are calculated, is fixed before comparing again
+    loop {
+        let differing = (0..packet.layers.len())
+            .rev()
+            .flat_map(|index| vec![ComputedField::HeaderLength, ComputedField::Length, ComputedField::Checksum].into_iter().map(move |field| (index, field)))
+            .find_map(|(index, field)| {
+                let value = packet.computed_field_value(index, field)?;
+                (rebuilt.computed_field_value(index, field) != Some(value)).then_some((index, field, value))
+            });
+        match differing {
+            Some((index, field, value)) => {
+                rebuilt.set_override(index, field, value);
+                overrides.push((index, field, value));
+            }
+            None => break,
+        }
+    }
+    overrides.sort_by_key(|(index, _, _)| *index);
+    overrides
+}
+
+fn ethernet(h: &EthernetHeader) -> (String, Setters) {
+    let eth_type = ethertype(*h.get_eth_type());
+    let constructor = format!("EthernetHeader::new({}, {}, {})", hex_array(h.get_src_mac().as_ref()), hex_array(h.get_dst_mac().as_ref()), eth_type);
+    (constructor, Vec::new())
+}
+
+fn vlan(h: &VlanHeader) -> (String, Setters) {
+    let mut setters = Vec::new();
+    if *h.get_priority() != 0 {
+        setters.push(("set_priority", h.get_priority().to_string()));
+    }
+    if *h.get_drop_eligible() {
+        setters.push(("set_drop_eligible", String::from("true")));
+    }
+    setters.push(("set_eth_type", ethertype(*h.get_eth_type())));
+    (format!("VlanHeader::new({})", h.get_vlan_id()), setters)
+}
+
+fn llc(h: &LlcHeader) -> (String, Setters) {
+    (format!("LlcHeader::new(0x{:02x}, 0x{:02x}, 0x{:02x})", h.get_dsap(), h.get_ssap(), h.get_control()), Vec::new())
+}
+
+fn snap(h: &SnapHeader) -> (String, Setters) {
+    let protocol_id = if *h.get_oui() == OUI_ETHERTYPE { ethertype(*h.get_protocol_id()) } else { format!("0x{:04x}", h.get_protocol_id()) };
+    (format!("SnapHeader::new({}, {})", hex_array(h.get_oui()), protocol_id), Vec::new())
+}
+
+fn mpls(h: &MplsHeader) -> (String, Setters) {
+    let mut setters = Vec::new();
+    if *h.get_traffic_class() != 0 {
+        setters.push(("set_traffic_class", h.get_traffic_class().to_string()));
+    }
+    // from_layers leaves the bit as it is, so every label but the last needs it cleared
+    if !*h.get_bottom_of_stack() {
+        setters.push(("set_bottom_of_stack", String::from("false")));
+    }
+    if *h.get_ttl() != 64 {
+        setters.push(("set_ttl", h.get_ttl().to_string()));
+    }
+    (format!("MplsHeader::new({})", h.get_label()), setters)
+}
+
+fn pw_control_word(h: &PwControlWordHeader) -> (String, Setters) {
+    let mut setters = Vec::new();
+    if *h.get_flags() != 0 {
+        setters.push(("set_flags", format!("0x{:x}", h.get_flags())));
+    }
+    if *h.get_fragmentation() != 0 {
+        setters.push(("set_fragmentation", h.get_fragmentation().to_string()));
+    }
+    if *h.get_payload_length() != 0 {
+        setters.push(("set_payload_length", h.get_payload_length().to_string()));
+    }
+    (format!("PwControlWordHeader::new({})", h.get_sequence_number()), setters)
+}
+
+fn lldp(h: &LldpHeader) -> (String, Setters) {
+    let tlvs: Vec<String> = h.get_tlvs().iter().map(|tlv| format!("    headers::LldpTlv::{},\n", lldp_tlv(tlv))).collect();
+    (format!("LldpHeader::from_tlvs(vec![\n{}])", tlvs.concat()), Vec::new())
+}
+
+/// the code for a TLV, after `LldpTlv::`
+fn lldp_tlv(tlv: &LldpTlv) -> String {
+    match tlv {
+        LldpTlv::ChassisId { subtype: CHASSIS_ID_MAC_ADDRESS, id } if id.len() == 6 => format!("chassis_mac({})", hex_array(id)),
+        LldpTlv::PortId { subtype: PORT_ID_INTERFACE_NAME, id } if std::str::from_utf8(id).is_ok() => {
+            format!("port_name({:?})", std::str::from_utf8(id).unwrap())
+        }
+        LldpTlv::ChassisId { subtype, id } => format!("ChassisId {{ subtype: {}, id: vec!{} }}", subtype, hex_array(id)),
+        LldpTlv::PortId { subtype, id } => format!("PortId {{ subtype: {}, id: vec!{} }}", subtype, hex_array(id)),
+        LldpTlv::PortDescription(s) => format!("PortDescription(String::from({:?}))", s),
+        LldpTlv::SystemName(s) => format!("SystemName(String::from({:?}))", s),
+        LldpTlv::SystemDescription(s) => format!("SystemDescription(String::from({:?}))", s),
+        LldpTlv::ManagementAddress { address_subtype, address, interface_subtype, interface_number, oid } => format!(
+            "ManagementAddress {{ address_subtype: {}, address: vec!{}, interface_subtype: {}, interface_number: {}, oid: vec!{} }}",
+            address_subtype,
+            hex_array(address),
+            interface_subtype,
+            interface_number,
+            hex_array(oid)
+        ),
+        LldpTlv::VlanName { vlan_id, name } => format!("VlanName {{ vlan_id: {}, name: String::from({:?}) }}", vlan_id, name),
+        LldpTlv::OrganizationallySpecific { oui, subtype, info } => {
+            format!("OrganizationallySpecific {{ oui: {}, subtype: {}, info: vec!{} }}", hex_array(oui), subtype, hex_array(info))
+        }
+        LldpTlv::Unknown { tlv_type, value } => format!("Unknown {{ tlv_type: {}, value: vec!{} }}", tlv_type, hex_array(value)),
+        // the rest only have numbers in them, which are written the same way in Rust as by Debug
+        _ => format!("{:?}", tlv),
+    }
+}
+
+/// the name of the constant for an ethertype, or the number if there isn't one
+fn ethertype(eth_type: u16) -> String {
+    let name = match eth_type {
+        ethertype_numbers::ETHERTYPE_IPV4 => "ETHERTYPE_IPV4",
+        ethertype_numbers::ETHERTYPE_ARP => "ETHERTYPE_ARP",
+        ethertype_numbers::ETHERTYPE_RARP => "ETHERTYPE_RARP",
+        ethertype_numbers::ETHERTYPE_IPV6 => "ETHERTYPE_IPV6",
+        ethertype_numbers::ETHERTYPE_LLDP => "ETHERTYPE_LLDP",
+        ethertype_numbers::ETHERTYPE_MPLS_UNICAST => "ETHERTYPE_MPLS_UNICAST",
+        ethertype_numbers::ETHERTYPE_MPLS_MULTICAST => "ETHERTYPE_MPLS_MULTICAST",
+        ethertype_numbers::ETHERTYPE_VLAN => "ETHERTYPE_VLAN",
+        ethertype_numbers::ETHERTYPE_QINQ => "ETHERTYPE_QINQ",
+        ethertype_numbers::ETHERTYPE_QINQ_LEGACY => "ETHERTYPE_QINQ_LEGACY",
+        n => return format!("0x{:04x}", n),
+    };
+    format!("ethertype_numbers::{}", name)
+}
+
+fn arp(h: &ArpHeader) -> (String, Setters) {
+    let addresses = (h.get_sender_mac(), h.get_sender_ip(), h.get_destination_mac(), h.get_destination_ip());
+    let constructor = match addresses {
+        (Some(sender_mac), Some(sender_ip), Some(destination_mac), Some(destination_ip))
+            if *h.get_hardware_type() == HARDWARE_ETHERNET && *h.get_protocol_type() == ethertype_numbers::ETHERTYPE_IPV4 =>
+        {
+            format!(
+                "ArpHeader::new({}, {:?}, {}, {:?})",
+                hex_array(sender_mac.as_ref()),
+                sender_ip.octets(),
+                hex_array(destination_mac.as_ref()),
+                destination_ip.octets()
+            )
+        }
+        _ => format!(
+            "ArpHeader::with_addresses({}, {}, vec!{}, vec!{}, vec!{}, vec!{})",
+            h.get_hardware_type(),
+            ethertype(*h.get_protocol_type()),
+            hex_array(h.get_sender_hw_address()),
+            hex_array(h.get_sender_proto_address()),
+            hex_array(h.get_destination_hw_address()),
+            hex_array(h.get_destination_proto_address())
+        ),
+    };
+    let mut setters = Vec::new();
+    if *h.get_opcode() != REQUEST {
+        setters.push(("set_opcode", h.get_opcode().to_string()));
+    }
+    (constructor, setters)
+}
+
+fn icmp(h: &IcmpHeader) -> (String, Setters) {
+    let constructor = format!(
+        "IcmpHeader::new({}, {}, {}, {})",
+        h.get_msg_type(),
+        h.get_code(),
+        h.get_identifier(),
+        h.get_sequence_number()
+    );
+    (constructor, Vec::new())
+}
+
+fn ip(h: &IpHeader) -> (String, Setters) {
+    let number = *h.get_next_protocol();
+    // protocols without a `Protocol` variant are set with `set_next_protocol_number` afterwards
+    let (protocol, has_variant) = match number {
+        protocol_numbers::IPPROTO_ICMP if h.get_src_ip().is_ipv4() => ("ICMP", true),
+        protocol_numbers::IPPROTO_ICMPV6 if h.get_src_ip().is_ipv6() => ("ICMP", true),
+        protocol_numbers::IPPROTO_TCP => ("TCP", true),
+        protocol_numbers::IPPROTO_UDP => ("UDP", true),
+        protocol_numbers::IPPROTO_IPV4 => ("IP", true),
+        protocol_numbers::IPPROTO_ETHERIP => ("ETH", true),
+        _ => ("IP", false),
+    };
+    let constructor = format!("IpHeader::new({}, {}, Protocol::{})", ip_address(h.get_src_ip()), ip_address(h.get_dst_ip()), protocol);
+    let mut setters = Vec::new();
+    if !has_variant {
+        setters.push(("set_next_protocol_number", number.to_string()));
+    }
+    if *h.get_tos() != 0 {
+        setters.push(("set_tos", format!("0x{:02x}", h.get_tos())));
+    }
+    if h.get_src_ip().is_ipv4() {
+        if *h.get_identification() != 0 {
+            setters.push(("set_identification", h.get_identification().to_string()));
+        }
+        if *h.get_flags() != DONT_FRAGMENT {
+            setters.push(("set_flags", format!("0b{:03b}", h.get_flags())));
+        }
+        if *h.get_fragment_offset() != 0 {
+            setters.push(("set_fragment_offset", h.get_fragment_offset().to_string()));
+        }
+        if !h.get_options().is_empty() {
+            setters.push(("set_options", format!("vec!{}", hex_array(h.get_options()))));
+        }
+    } else if *h.get_flow_label() != 0 {
+        setters.push(("set_flow_label", format!("0x{:05x}", h.get_flow_label())));
+    }
+    if *h.get_ttl() != 64 {
+        setters.push(("set_ttl", h.get_ttl().to_string()));
+    }
+    (constructor, setters)
+}
+
+fn tcp(h: &TcpHeader) -> (String, Setters) {
+    let constructor = format!("TcpHeader::new({}, {})", h.get_src_port(), h.get_dst_port());
+    let mut setters = Vec::new();
+    if *h.get_sequence_number() != 0 {
+        setters.push(("set_sequence_number", h.get_sequence_number().to_string()));
+    }
+    if *h.get_acknowledgment_number() != 0 {
+        setters.push(("set_acknowledgment_number", h.get_acknowledgment_number().to_string()));
+    }
+    if *h.get_reserved() != 0 {
+        setters.push(("set_reserved", format!("0x{:x}", h.get_reserved())));
+    }
+    if *h.get_flags() != 0 {
+        setters.push(("set_flags", format!("0x{:02x}", h.get_flags())));
+    }
+    if *h.get_window() != 0xffff {
+        setters.push(("set_window", h.get_window().to_string()));
+    }
+    if *h.get_urgent_pointer() != 0 {
+        setters.push(("set_urgent_pointer", h.get_urgent_pointer().to_string()));
+    }
+    if !h.get_options().is_empty() {
+        setters.push(("set_options", format!("vec!{}", hex_array(h.get_options()))));
+    }
+    (constructor, setters)
+}
+
+fn udp(h: &UdpHeader) -> (String, Setters) {
+    (format!("UdpHeader::new({}, {})", h.get_src_port(), h.get_dst_port()), Vec::new())
+}
+
+/// an array literal which `IpHeader::new` accepts as the given address
+fn ip_address(address: &IpAddr) -> String {
+    match address {
+        IpAddr::V4(v4) => format!("{:?}", v4.octets()),
+        IpAddr::V6(v6) => {
+            let segments: Vec<String> = v6.segments().iter().map(|s| format!("0x{:x}", s)).collect();
+            format!("[{}]", segments.join(", "))
+        }
+    }
+}
+
+fn hex_array(bytes: &[u8]) -> String {
+    let bytes: Vec<String> = bytes.iter().map(|b| format!("0x{:02x}", b)).collect();
+    format!("[{}]", bytes.join(", "))
+}
@@ -0,0 +1,183 @@
+use super::{
+    ethertype_numbers,
+    protocol_numbers,
+    Packet,
+    Protocol,
+};
+use crate::headers::{
+    Header,
+    EthernetHeader,
+    ArpHeader,
+    IcmpHeader,
+    IpHeader,
+    TcpHeader,
+    UdpHeader,
+    VlanHeader,
+    LlcHeader,
+    SnapHeader,
+    LldpHeader,
+    MplsHeader,
+    PwControlWordHeader,
+    REVERSE_REPLY,
+    REVERSE_REQUEST
+};
+use std::ops::Div;
+
+impl Packet {
+    /// Adds the header into the packet like `add_header`, and then fills in the field of the layer below it which says what the next
+    /// layer is (the ethertype of an ethernet header, VLAN tag or SNAP header, or the protocol of an IP header), if that field has been left as 0.
+    /// An MPLS label with another label stacked on top of it has its bottom of stack bit cleared.
+    /// This is what the `/` operator uses to stack headers on top of each other.
+    pub fn push_header(&mut self, buf: impl Header) {
+        self.add_header(buf);
+        let index = self.layers.len() - 1;
+        if index == 0 {
+            return;
+        }
+        let lower = self.layers[index - 1];
+        let upper = self.layers[index];
+        let upper_is_ipv6 = upper.proto == Protocol::IP && self.buffer[upper.offset] >> 4 == 6;
+        let offset = lower.offset;
+        match lower.proto {
+            Protocol::ETH | Protocol::VLAN | Protocol::SNAP => {
+                let eth_type = match upper.proto {
+                    Protocol::IP if upper_is_ipv6 => ethertype_numbers::ETHERTYPE_IPV6,
+                    Protocol::IP => ethertype_numbers::ETHERTYPE_IPV4,
+                    Protocol::ARP if is_rarp(&self.buffer[upper.offset..]) => ethertype_numbers::ETHERTYPE_RARP,
+                    Protocol::ARP => ethertype_numbers::ETHERTYPE_ARP,
+                    Protocol::VLAN => ethertype_numbers::ETHERTYPE_VLAN,
+                    Protocol::LLDP => ethertype_numbers::ETHERTYPE_LLDP,
+                    Protocol::MPLS => ethertype_numbers::ETHERTYPE_MPLS_UNICAST,
+                    _ => return,
+                };
+                // the ethertype is the last field of all three headers. an LLC header's length is filled in by finalize instead
+                let field = offset + lower.length - 2;
+                if self.buffer[field..(field + 2)] == [0, 0] {
+                    self.buffer[field..(field + 2)].copy_from_slice(&eth_type.to_be_bytes());
+                }
+            },
+            Protocol::IP => {
+                let next_protocol = match upper.proto {
+                    Protocol::IP if upper_is_ipv6 => protocol_numbers::IPPROTO_IPV6,
+                    Protocol::ICMP if self.buffer[offset] >> 4 == 6 => protocol_numbers::IPPROTO_ICMPV6,
+                    Protocol::ARP | Protocol::VLAN | Protocol::LLC | Protocol::SNAP | Protocol::LLDP | Protocol::MPLS | Protocol::PWCW => return,
+                    p => p.protocol_number(),
+                };
+                // the protocol field is at a different position in an IPv6 header
+                let field = if self.buffer[offset] >> 4 == 6 { offset + 6 } else { offset + 9 };
+                if self.buffer[field] == 0 {
+                    self.buffer[field] = next_protocol;
+                }
+            },
+            Protocol::MPLS if upper.proto == Protocol::MPLS => {
+                // only the last label is at the bottom of the stack
+                self.buffer[offset + 2] &= 0xfe;
+            },
+            _ => return
+        }
+        // the IP checksum covers the protocol field, and the checksums of any enclosing layers cover both fields, so they need recalculating
+        self.finalize();
+    }
+}
+
+/// whether an ARP header has one of the RARP opcodes, so it goes in a frame with the RARP ethertype
+fn is_rarp(arp: &[u8]) -> bool {
+    let opcode = u16::from_be_bytes([arp[6], arp[7]]);
+    opcode == REVERSE_REQUEST || opcode == REVERSE_REPLY
+}
+
+impl Default for Packet {
+    fn default() -> Self {
+        Self::new_empty()
+    }
+}
+
+impl<T: Header> Div<T> for Packet {
+    type Output = Packet;
+
+    fn div(mut self, rhs: T) -> Packet {
+        self.push_header(rhs);
+        self
+    }
+}
+
+impl Div<Vec<u8>> for Packet {
+    type Output = Packet;
+
+    fn div(mut self, rhs: Vec<u8>) -> Packet {
+        self.extend_payload(rhs);
+        self
+    }
+}
+
+impl Div<&[u8]> for Packet {
+    type Output = Packet;
+
+    fn div(mut self, rhs: &[u8]) -> Packet {
+        self.extend_payload(rhs.iter().copied());
+        self
+    }
+}
+
+impl Div<&str> for Packet {
+    type Output = Packet;
+
+    fn div(mut self, rhs: &str) -> Packet {
+        self.extend_payload(rhs.bytes());
+        self
+    }
+}
+
+// lets any header be the start of a chain, e.g. `EthernetHeader::default() / IpHeader::default()`
+macro_rules! impl_div_for_headers {
+    ( $($header:ty),* ) => (
+        $(
+            impl<T: Header> Div<T> for $header {
+                type Output = Packet;
+
+                fn div(self, rhs: T) -> Packet {
+                    Packet::default() / self / rhs
+                }
+            }
+
+            impl Div<Vec<u8>> for $header {
+                type Output = Packet;
+
+                fn div(self, rhs: Vec<u8>) -> Packet {
+                    Packet::default() / self / rhs
+                }
+            }
+
+            impl Div<&[u8]> for $header {
+                type Output = Packet;
+
+                fn div(self, rhs: &[u8]) -> Packet {
+                    Packet::default() / self / rhs
+                }
+            }
+
+            impl Div<&str> for $header {
+                type Output = Packet;
+
+                fn div(self, rhs: &str) -> Packet {
+                    Packet::default() / self / rhs
+                }
+            }
+        )*
+    )
+}
+
+impl_div_for_headers!(
+    EthernetHeader,
+    ArpHeader,
+    IcmpHeader,
+    IpHeader,
+    TcpHeader,
+    UdpHeader,
+    VlanHeader,
+    LlcHeader,
+    SnapHeader,
+    LldpHeader,
+    MplsHeader,
+    PwControlWordHeader
+);
@@ -0,0 +1,194 @@
+//! Field by field comparison of packets, for tests which need to know more than the index of the first byte that differs.
+//!
+//! Packets are compared layer by layer using their dissections, so each difference names the layer and the wireshark style field
+//! (`ip.ttl`, `tcp.flags.syn`, ...) which differs. Fields which are expected to change between runs can be left out of the comparison
+//! with `DiffOptions`, and `assert_packet_eq!` panics with a readable list of the differences.
+
+use super::Packet;
+use crate::dissect::{Field, LayerDissection};
+use std::fmt;
+
+/// The fields which `DiffOptions::ignore_volatile` leaves out: identifiers, sequence numbers, timestamps and the checksums which depend on them
+pub const VOLATILE_FIELDS: [&str; 10] = [
+    "ip.id",
+    "ip.checksum",
+    "ipv6.flow",
+    "tcp.seq",
+    "tcp.ack",
+    "tcp.checksum",
+    "tcp.options.timestamp.tsval",
+    "tcp.options.timestamp.tsecr",
+    "udp.checksum",
+    "icmp.checksum",
+];
+
+/// Which fields to leave out when comparing packets
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    ignored: Vec<String>,
+}
+
+impl DiffOptions {
+    /// compares every field
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// leaves out the field with the given name, along with any fields within it
+    pub fn ignore(mut self, field: &str) -> Self {
+        self.ignored.push(String::from(field));
+        self
+    }
+
+    /// leaves out all of the `VOLATILE_FIELDS`
+    pub fn ignore_volatile(mut self) -> Self {
+        self.ignored.extend(VOLATILE_FIELDS.iter().map(|f| String::from(*f)));
+        self
+    }
+
+    /// whether the field with the given name is left out of the comparison
+    pub fn is_ignored(&self, field: &str) -> bool {
+        self.ignored.iter().any(|ignored| {
+            field == ignored || (field.starts_with(ignored.as_str()) && field[ignored.len()..].starts_with('.'))
+        })
+    }
+}
+
+/// A single difference between two packets. The left packet is the one `diff` is called on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// the layers at position `index` are different protocols, or one of the packets has no layer there.
+    /// the names are the short layer names used by `dissect`, e.g. `ip` or `data` for the payload
+    Layer {
+        index: usize,
+        left: Option<&'static str>,
+        right: Option<&'static str>,
+    },
+    /// the field `field` of the layer at position `index` has a different value, or is only in one of the packets
+    Field {
+        index: usize,
+        layer: &'static str,
+        field: &'static str,
+        left: Option<String>,
+        right: Option<String>,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let or_missing = |value: &Option<String>| value.clone().unwrap_or_else(|| String::from("<missing>"));
+        match self {
+            Difference::Layer { index, left, right } => write!(
+                f,
+                "layer {}: {} != {}",
+                index,
+                left.unwrap_or("<missing>"),
+                right.unwrap_or("<missing>")
+            ),
+            Difference::Field { index, layer, field, left, right } => {
+                write!(f, "layer {} ({}), {}: {} != {}", index, layer, field, or_missing(left), or_missing(right))
+            }
+        }
+    }
+}
+
+impl Packet {
+    /// Compares every field of this packet with `other`, returning the differences in the order they appear in the packet.
+    /// The payload is compared as a final `data` layer
+    pub fn diff(&self, other: &Packet) -> Vec<Difference> {
+        self.diff_with(other, &DiffOptions::new())
+    }
+
+    /// The same as `diff`, but leaving out the fields `options` ignores
+    pub fn diff_with(&self, other: &Packet, options: &DiffOptions) -> Vec<Difference> {
+        let (left, right) = (self.dissect(), other.dissect());
+        let mut differences = Vec::new();
+        for index in 0..left.len().max(right.len()) {
+            match (left.get(index), right.get(index)) {
+                (Some(l), Some(r)) if l.name == r.name => diff_layer(index, l, r, options, &mut differences),
+                (l, r) => differences.push(Difference::Layer {
+                    index,
+                    left: l.map(|l| l.name),
+                    right: r.map(|r| r.name),
+                }),
+            }
+        }
+        differences
+    }
+}
+
+/// Formats a list of differences one per line, as `assert_packet_eq!` prints them
+pub fn format_differences(differences: &[Difference]) -> String {
+    differences.iter().map(|d| format!("    {}\n", d)).collect()
+}
+
+/// Asserts that two packets are equal field by field, panicking with the list of differences if they're not.
+/// A `DiffOptions` can be passed as the third argument to leave fields out of the comparison:
+///
+/// ```text
+/// assert_packet_eq!(sent, expected);
+/// assert_packet_eq!(sent, expected, DiffOptions::new().ignore_volatile().ignore("ip.ttl"));
+/// ```
+#[macro_export]
+macro_rules! assert_packet_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_packet_eq!($left, $right, $crate::diff::DiffOptions::new())
+    };
+    ($left:expr, $right:expr, $options:expr $(,)?) => {{
+        let (left, right): (&$crate::Packet, &$crate::Packet) = (&$left, &$right);
+        let differences = left.diff_with(right, &$options);
+        if !differences.is_empty() {
+            panic!(
+                "assertion failed: packets are not equal\n  left: {}\n right: {}\n{}",
+                left,
+                right,
+                $crate::diff::format_differences(&differences)
+            );
+        }
+    }};
+}
+
+fn diff_layer(index: usize, left: &LayerDissection, right: &LayerDissection, options: &DiffOptions, out: &mut Vec<Difference>) {
+    let (left_fields, right_fields) = (leaf_fields(&left.fields, options), leaf_fields(&right.fields, options));
+    let mut matched = vec![false; right_fields.len()];
+    for l in left_fields.iter() {
+        // fields which repeat within a layer are paired up in order
+        let found = right_fields.iter().enumerate().position(|(i, r)| !matched[i] && r.name == l.name);
+        match found {
+            Some(i) => {
+                matched[i] = true;
+                let r = right_fields[i];
+                if l.raw != r.raw || l.show != r.show {
+                    out.push(field_difference(index, left.name, l.name, Some(l), Some(r)));
+                }
+            }
+            None => out.push(field_difference(index, left.name, l.name, Some(l), None)),
+        }
+    }
+    for (r, _) in right_fields.iter().zip(matched).filter(|(_, matched)| !matched) {
+        out.push(field_difference(index, left.name, r.name, None, Some(r)));
+    }
+}
+
+fn field_difference(index: usize, layer: &'static str, field: &'static str, left: Option<&Field>, right: Option<&Field>) -> Difference {
+    Difference::Field {
+        index,
+        layer,
+        field,
+        left: left.map(|f| f.show.clone()),
+        right: right.map(|f| f.show.clone()),
+    }
+}
+
+/// the fields which aren't ignored and have no fields within them, in order. fields with children are compared through their children
+fn leaf_fields<'a>(fields: &'a [Field], options: &DiffOptions) -> Vec<&'a Field> {
+    let mut leaves = Vec::new();
+    for field in fields.iter().filter(|f| !options.is_ignored(f.name)) {
+        if field.children.is_empty() {
+            leaves.push(field);
+        } else {
+            leaves.extend(leaf_fields(&field.children, options));
+        }
+    }
+    leaves
+}
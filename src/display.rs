@@ -0,0 +1,275 @@
+use super::{hexdump, Packet, Protocol};
+use crate::dissect::{Field, LayerDissection, TCP_FLAGS};
+use std::fmt::{self, Write};
+
+impl Packet {
+    /// Returns a multi-line description of every layer and field in the packet, laid out like the packet details pane in wireshark:
+    ///
+    /// ```text
+    /// Internet Protocol Version 4, Src: 10.0.0.1, Dst: 10.0.0.2
+    ///     Version: 4
+    ///     Header Length: 20 bytes (5)
+    ///     ...
+    ///     Flags: 0x2 (Don't fragment)
+    ///         Reserved bit: Not set
+    /// ```
+    pub fn show(&self) -> String {
+        let mut out = String::new();
+        for layer in self.dissect() {
+            writeln!(out, "{}", layer_title(&layer)).unwrap();
+            write_fields(&mut out, &layer.fields, 1);
+        }
+        out
+    }
+
+    /// Returns a one line summary of the packet, e.g. `IP 10.0.0.1:1234 > 10.0.0.2:80 TCP [S] len=0`.
+    /// The summary describes the innermost network and transport layers, with `len` being the length of the payload
+    pub fn summary(&self) -> String {
+        let layers = self.dissect();
+        let field = |layer: &LayerDissection, name: &str| layer.get_field(name).map(|f| f.show.clone()).unwrap_or_default();
+        let value = |layer: &LayerDissection, name: &str| layer.get_field(name).and_then(|f| f.value()).unwrap_or(0);
+        let payload_len = self.payload.len();
+
+        let ip = layers.iter().rev().find(|l| l.proto == Some(Protocol::IP));
+        let transport = layers.iter().rev().find(|l| matches!(l.proto, Some(Protocol::TCP) | Some(Protocol::UDP) | Some(Protocol::ICMP)));
+        if let Some(ip) = ip {
+            let (label, src, dst) = if ip.name == "ipv6" {
+                ("IP6", format!("[{}]", field(ip, "ipv6.src")), format!("[{}]", field(ip, "ipv6.dst")))
+            } else {
+                ("IP", field(ip, "ip.src"), field(ip, "ip.dst"))
+            };
+            return match transport {
+                Some(t) if t.proto == Some(Protocol::TCP) => format!(
+                    "{} {}:{} > {}:{} TCP [{}] len={}",
+                    label, src, value(t, "tcp.srcport"), dst, value(t, "tcp.dstport"), tcp_flag_letters(value(t, "tcp.flags")), payload_len
+                ),
+                Some(t) if t.proto == Some(Protocol::UDP) => format!(
+                    "{} {}:{} > {}:{} UDP len={}",
+                    label, src, value(t, "udp.srcport"), dst, value(t, "udp.dstport"), payload_len
+                ),
+                Some(t) => format!(
+                    "{} {} > {} ICMP type={} code={} id={} seq={} len={}",
+                    label, src, dst, value(t, "icmp.type"), value(t, "icmp.code"), value(t, "icmp.ident"), value(t, "icmp.seq"), payload_len
+                ),
+                None => format!("{} {} > {} proto={} len={}", label, src, dst, ip_protocol(ip), payload_len),
+            };
+        }
+        if let Some(arp) = layers.iter().find(|l| l.proto == Some(Protocol::ARP)) {
+            // the addresses only have the _mac and _ipv4 names when they are that long
+            let address = |name: &str, kind: &str| {
+                let named = field(arp, &format!("{}_{}", name, kind));
+                if named.is_empty() { field(arp, name) } else { named }
+            };
+            let (src_hw, dst_hw) = (address("arp.src.hw", "mac"), address("arp.dst.hw", "mac"));
+            let (src_proto, dst_proto) = (address("arp.src.proto", "ipv4"), address("arp.dst.proto", "ipv4"));
+            return match value(arp, "arp.opcode") {
+                1 => format!("ARP who-has {} tell {}", dst_proto, src_proto),
+                2 => format!("ARP {} is-at {}", src_proto, src_hw),
+                3 => format!("RARP who-is {} tell {}", dst_hw, src_hw),
+                4 => format!("RARP {} at {}", dst_hw, dst_proto),
+                n => format!("ARP opcode={} {} > {}", n, src_proto, dst_proto),
+            };
+        }
+        if let Some(lldp) = layers.iter().find(|l| l.proto == Some(Protocol::LLDP)) {
+            return format!(
+                "LLDP chassis {} port {} ttl={}",
+                field(lldp, "lldp.chassis.id"), field(lldp, "lldp.port.id"), value(lldp, "lldp.time_to_live")
+            );
+        }
+        let labels: Vec<String> = layers.iter().filter(|l| l.proto == Some(Protocol::MPLS)).map(|l| value(l, "mpls.label").to_string()).collect();
+        if !labels.is_empty() {
+            return format!("MPLS labels={} len={}", labels.join(","), payload_len);
+        }
+        if let Some(eth) = layers.iter().find(|l| l.proto == Some(Protocol::ETH)) {
+            if eth.get_field("eth.len").is_some() {
+                return format!("802.3 {} > {} len={}", field(eth, "eth.src"), field(eth, "eth.dst"), payload_len);
+            }
+            return format!("Ether {} > {} type=0x{:04x} len={}", field(eth, "eth.src"), field(eth, "eth.dst"), value(eth, "eth.type"), payload_len);
+        }
+        format!("Raw len={}", payload_len)
+    }
+
+    /// Returns a canonical hex dump of the whole packet, in the same format as `hexdump -C`
+    pub fn hexdump(&self) -> String {
+        let mut data = Vec::with_capacity(self.len());
+        self.write_into(&mut data);
+        hexdump(&data)
+    }
+}
+
+/// the same as `summary()`
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.summary())
+    }
+}
+
+impl fmt::Debug for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Packet")
+            .field("layers", &self.get_layers())
+            .field("payload", &self.payload)
+            .field("trailer", &self.trailer.bytes)
+            .finish()
+    }
+}
+
+fn ip_protocol(ip: &LayerDissection) -> u64 {
+    ip.get_field("ip.proto").or_else(|| ip.get_field("ipv6.nxt")).and_then(|f| f.value()).unwrap_or(0)
+}
+
+/// the tcpdump style letters for the set tcp flags, with ACK as `.`
+fn tcp_flag_letters(flags: u64) -> String {
+    let letters = [(0x002, 'S'), (0x001, 'F'), (0x004, 'R'), (0x008, 'P'), (0x020, 'U'), (0x040, 'E'), (0x080, 'W'), (0x010, '.')];
+    letters.iter().filter(|(mask, _)| flags & mask != 0).map(|(_, c)| *c).collect()
+}
+
+fn layer_title(layer: &LayerDissection) -> String {
+    let field = |name: &str| layer.get_field(name).map(|f| f.show.as_str()).unwrap_or("?");
+    match layer.name {
+        "eth" if layer.get_field("eth.len").is_some() => format!("IEEE 802.3 Ethernet, Src: {}, Dst: {}", field("eth.src"), field("eth.dst")),
+        "eth" => format!("Ethernet II, Src: {}, Dst: {}", field("eth.src"), field("eth.dst")),
+        "vlan" => format!("802.1Q Virtual LAN, PRI: {}, DEI: {}, ID: {}", field("vlan.priority"), field("vlan.dei"), field("vlan.id")),
+        "llc" => String::from("Logical-Link Control"),
+        "snap" => String::from("SubNetwork Access Protocol"),
+        "arp" if matches!(layer.get_field("arp.opcode").and_then(|f| f.value()), Some(3) | Some(4)) => {
+            format!("Reverse Address Resolution Protocol ({})", field("arp.opcode"))
+        }
+        "arp" => format!("Address Resolution Protocol ({})", field("arp.opcode")),
+        "lldp" => String::from("Link Layer Discovery Protocol"),
+        "mpls" => format!(
+            "MultiProtocol Label Switching Header, Label: {}, Exp: {}, S: {}, TTL: {}",
+            field("mpls.label"), field("mpls.exp"), field("mpls.bottom"), field("mpls.ttl")
+        ),
+        "pwmcw" => String::from("PW MPLS Control Word"),
+        "ip" => format!("Internet Protocol Version 4, Src: {}, Dst: {}", field("ip.src"), field("ip.dst")),
+        "ipv6" => format!("Internet Protocol Version 6, Src: {}, Dst: {}", field("ipv6.src"), field("ipv6.dst")),
+        "tcp" => format!("Transmission Control Protocol, Src Port: {}, Dst Port: {}", field("tcp.srcport"), field("tcp.dstport")),
+        "udp" => format!("User Datagram Protocol, Src Port: {}, Dst Port: {}", field("udp.srcport"), field("udp.dstport")),
+        "icmp" => String::from("Internet Control Message Protocol"),
+        "data" => format!("Data ({} bytes)", layer.length),
+        name => String::from(name),
+    }
+}
+
+fn write_fields(out: &mut String, fields: &[Field], depth: usize) {
+    for field in fields {
+        writeln!(out, "{}{}: {}", "    ".repeat(depth), field_label(field.name), field.show).unwrap();
+        write_fields(out, &field.children, depth + 1);
+    }
+}
+
+/// the human readable name of a dissected field
+fn field_label(name: &str) -> &str {
+    if let Some((_, _, short)) = TCP_FLAGS.iter().find(|(flag, _, _)| *flag == name) {
+        return short;
+    }
+    match name {
+        "eth.dst" | "ip.dst" | "ipv6.dst" => "Destination",
+        "eth.src" | "ip.src" | "ipv6.src" => "Source",
+        "eth.type" | "vlan.etype" => "Type",
+        "eth.len" | "vlan.len" => "Length",
+        "eth.padding" => "Padding",
+        "eth.fcs" => "Frame check sequence",
+        "vlan.priority" => "Priority",
+        "vlan.dei" => "DEI",
+        "vlan.id" => "ID",
+        "llc.dsap" => "DSAP",
+        "llc.ssap" => "SSAP",
+        "llc.control" => "Control field",
+        "llc.oui" => "Organization Code",
+        "llc.pid" => "PID",
+        "arp.hw.type" => "Hardware type",
+        "arp.proto.type" => "Protocol type",
+        "arp.hw.size" => "Hardware size",
+        "arp.proto.size" => "Protocol size",
+        "arp.opcode" => "Opcode",
+        "arp.src.hw_mac" => "Sender MAC address",
+        "arp.src.proto_ipv4" => "Sender IP address",
+        "arp.dst.hw_mac" => "Target MAC address",
+        "arp.dst.proto_ipv4" => "Target IP address",
+        "arp.src.hw" => "Sender hardware address",
+        "arp.src.proto" => "Sender protocol address",
+        "arp.dst.hw" => "Target hardware address",
+        "arp.dst.proto" => "Target protocol address",
+        "lldp.tlv" => "TLV",
+        "lldp.tlv.type" => "TLV Type",
+        "lldp.tlv.len" => "TLV Length",
+        "lldp.chassis.subtype" => "Chassis Id Subtype",
+        "lldp.chassis.id" => "Chassis Id",
+        "lldp.port.subtype" => "Port Id Subtype",
+        "lldp.port.id" => "Port Id",
+        "lldp.time_to_live" => "Seconds",
+        "lldp.port.desc" => "Port Description",
+        "lldp.tlv.system.name" => "System Name",
+        "lldp.tlv.system.desc" => "System Description",
+        "lldp.tlv.system_cap" => "Capabilities",
+        "lldp.tlv.enable_system_cap" => "Enabled Capabilities",
+        "lldp.mgn.addr.subtype" => "Address Subtype",
+        "lldp.mgn.addr" => "Management Address",
+        "lldp.mgn.if.number" => "Interface Number",
+        "lldp.orgtlv.oui" => "Organization Unique Code",
+        "lldp.ieee.802_1.subtype" | "lldp.ieee.802_3.subtype" | "lldp.orgtlv.subtype" => "Subtype",
+        "lldp.ieee.802_1.port_vlan.id" => "Port VLAN Identifier",
+        "lldp.ieee.802_1.vid" => "VLAN Identifier",
+        "lldp.ieee.802_1.vlan.name" => "VLAN Name",
+        "lldp.ieee.802_3.autoneg" => "Auto-Negotiation Support/Status",
+        "lldp.ieee.802_3.advertised_caps" => "PMD Auto-Negotiation Advertised Capability",
+        "lldp.ieee.802_3.mau_type" => "Operational MAU Type",
+        "lldp.ieee.802_3.aggregation_status" => "Aggregation Status",
+        "lldp.ieee.802_3.aggregated_port_id" => "Aggregated Port Id",
+        "lldp.ieee.802_3.max_frame_size" => "Maximum Frame Size",
+        "mpls.label" => "MPLS Label",
+        "mpls.exp" => "MPLS Experimental Bits",
+        "mpls.bottom" => "MPLS Bottom Of Label Stack",
+        "mpls.ttl" => "MPLS TTL",
+        "pwmcw.flags" => "Flags",
+        "pwmcw.fragmentation" => "Fragmentation",
+        "pwmcw.length" => "Length",
+        "pwmcw.sequence_number" => "Sequence Number",
+        "ip.version" | "ipv6.version" => "Version",
+        "ip.hdr_len" | "tcp.hdr_len" => "Header Length",
+        "ip.dsfield" => "Differentiated Services Field",
+        "ip.len" => "Total Length",
+        "ip.id" => "Identification",
+        "ip.flags" | "tcp.flags" => "Flags",
+        "ip.flags.rb" => "Reserved bit",
+        "ip.flags.df" => "Don't fragment",
+        "ip.flags.mf" => "More fragments",
+        "ip.frag_offset" => "Fragment Offset",
+        "ip.ttl" => "Time to Live",
+        "ip.proto" => "Protocol",
+        "ip.checksum" | "tcp.checksum" | "udp.checksum" | "icmp.checksum" => "Checksum",
+        "ip.options" | "tcp.options" => "Options",
+        "ipv6.tclass" => "Traffic Class",
+        "ipv6.flow" => "Flow Label",
+        "ipv6.plen" => "Payload Length",
+        "ipv6.nxt" => "Next Header",
+        "ipv6.hlim" => "Hop Limit",
+        "tcp.srcport" | "udp.srcport" => "Source Port",
+        "tcp.dstport" | "udp.dstport" => "Destination Port",
+        "tcp.seq" => "Sequence Number",
+        "tcp.ack" => "Acknowledgment Number",
+        "tcp.flags.res" => "Reserved",
+        "tcp.options.eol" | "tcp.options.nop" | "tcp.options.mss" | "tcp.options.wscale" | "tcp.options.sack_perm" | "tcp.options.sack"
+        | "tcp.options.timestamp" | "tcp.options.unknown" => "TCP Option",
+        "tcp.option_kind" => "Kind",
+        "tcp.option_len" => "Length",
+        "tcp.options.mss_val" => "MSS Value",
+        "tcp.options.wscale.shift" => "Shift count",
+        "tcp.options.sack_le" => "left edge",
+        "tcp.options.sack_re" => "right edge",
+        "tcp.options.timestamp.tsval" => "Timestamp value",
+        "tcp.options.timestamp.tsecr" => "Timestamp echo reply",
+        "tcp.options.data" => "Data",
+        "tcp.window_size_value" => "Window",
+        "tcp.urgent_pointer" => "Urgent Pointer",
+        "udp.length" => "Length",
+        "icmp.type" => "Type",
+        "icmp.code" => "Code",
+        "icmp.ident" => "Identifier",
+        "icmp.seq" => "Sequence Number",
+        "data.data" => "Data",
+        name => name,
+    }
+}
@@ -0,0 +1,821 @@
+//! Dissection of packets into their individual fields, named after wireshark's display filter fields (`ip.ttl`, `tcp.flags.syn`, ...),
+//! with each field's position in the packet, its raw bytes and its interpreted value.
+
+use super::{ethertype_numbers, protocol_numbers, MacAddr, Packet, Protocol};
+use crate::headers::{hw_address_string, proto_address_string, HARDWARE_ETHERNET, HARDWARE_IEEE802, HARDWARE_INFINIBAND};
+use crate::headers::{lldp_address_string, lldp_id_string, LldpTlv, CHASSIS_ID_MAC_ADDRESS, OUI_IEEE_8021, OUI_IEEE_8023, PORT_ID_MAC_ADDRESS};
+use crate::headers::{
+    TLV_CAPABILITIES, TLV_CHASSIS_ID, TLV_END, TLV_MANAGEMENT_ADDRESS, TLV_ORGANIZATIONALLY_SPECIFIC, TLV_PORT_DESCRIPTION, TLV_PORT_ID,
+    TLV_SYSTEM_DESCRIPTION, TLV_SYSTEM_NAME, TLV_TTL,
+};
+use crate::headers::{OUI_CISCO, OUI_ETHERTYPE, SAP_IPX, SAP_NETBIOS, SAP_SNAP, SAP_STP};
+use crate::headers::{LABEL_IMPLICIT_NULL, LABEL_IPV4_EXPLICIT_NULL, LABEL_IPV6_EXPLICIT_NULL, LABEL_ROUTER_ALERT};
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// A single field of a dissected header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// the name of the field, e.g. `ip.ttl`
+    pub name: &'static str,
+    /// the position of the first byte of the field, from the start of the packet
+    pub offset: usize,
+    /// the number of bytes the field covers
+    pub length: usize,
+    /// the bits of the covered bytes which belong to this field, for fields which aren't a whole number of bytes. 0 for whole byte fields
+    pub bitmask: u64,
+    /// the bytes the field covers, as they are in the packet
+    pub raw: Vec<u8>,
+    /// the interpreted value of the field, e.g. `TCP (6)` for the protocol field of an IP header
+    pub show: String,
+    /// the fields within this one, e.g. the individual flags of a flags field
+    pub children: Vec<Field>,
+}
+
+impl Field {
+    /// Returns the numeric value of the field (with the bitmask applied), or `None` if the field is too long to fit in a u64
+    pub fn value(&self) -> Option<u64> {
+        if self.raw.len() > 8 {
+            return None;
+        }
+        let value = self.raw.iter().fold(0u64, |v, b| (v << 8) | *b as u64);
+        if self.bitmask == 0 {
+            Some(value)
+        } else {
+            Some((value & self.bitmask) >> self.bitmask.trailing_zeros())
+        }
+    }
+}
+
+/// A dissected header, or the payload of the packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerDissection {
+    /// the short name of the layer, e.g. `eth`, `ip`, `ipv6` or `tcp`. the payload is called `data`
+    pub name: &'static str,
+    /// the protocol of the header, or `None` for the payload
+    pub proto: Option<Protocol>,
+    /// the position of the first byte of the layer, from the start of the packet
+    pub offset: usize,
+    /// the length of the layer in bytes
+    pub length: usize,
+    pub fields: Vec<Field>,
+}
+
+impl LayerDissection {
+    /// Finds the field with the given name in this layer, including fields within other fields
+    pub fn get_field(&self, name: &str) -> Option<&Field> {
+        fn find<'a>(fields: &'a [Field], name: &str) -> Option<&'a Field> {
+            fields.iter().find_map(|f| if f.name == name { Some(f) } else { find(&f.children, name) })
+        }
+        find(&self.fields, name)
+    }
+}
+
+impl Packet {
+    /// Breaks the packet down into its individual fields, one `LayerDissection` per header, followed by one for the payload if there is one
+    pub fn dissect(&self) -> Vec<LayerDissection> {
+        let mut layers: Vec<LayerDissection> = self.layers.iter().enumerate().map(|(index, layer)| {
+            let data = &self.buffer[layer.offset..(layer.offset + layer.length)];
+            let in_ipv6 = super::enclosing_ip_layer(&self.layers, index).is_some_and(|ip| super::is_ipv6(ip, &self.buffer));
+            dissect_header(layer.proto, data, layer.offset, in_ipv6)
+        }).collect();
+        if !self.payload.is_empty() {
+            let mut d = Dissector::new(&self.payload, self.buffer.len());
+            d.add("data.data", 0, self.payload.len(), 0, hex(&self.payload));
+            layers.push(d.finish("data", None));
+        }
+        // the padding and FCS belong to the ethernet header, as they do in wireshark
+        if !self.trailer.bytes.is_empty() {
+            let padding = self.get_padding();
+            let mut d = Dissector::new(&self.trailer.bytes, self.buffer.len() + self.payload.len());
+            if !padding.is_empty() {
+                d.add("eth.padding", 0, padding.len(), 0, hex(padding));
+            }
+            if let Some(fcs) = self.get_fcs() {
+                d.add("eth.fcs", padding.len(), 4, 0, format!("0x{:08x}", fcs));
+            }
+            layers[0].fields.append(&mut d.fields);
+        }
+        layers
+    }
+
+    /// Returns the dissection of this packet as JSON, in the same layout as `tshark -T json -x`:
+    /// each field's interpreted value is under its name, and its raw value is under `<name>_raw` as `[hex bytes, offset, length, bitmask]`.
+    /// Fields within other fields are under `<name>_tree`, and when there is more than one layer with the same name (e.g. IP in IP),
+    /// their dissections are put in an array rather than repeating the key
+    pub fn to_json(&self) -> String {
+        let layers = self.dissect();
+        let mut out = String::from("{\"_source\":{\"layers\":{\"frame\":{");
+        let protocols: Vec<&str> = layers.iter().map(|l| l.name).collect();
+        write!(out, "\"frame.len\":\"{}\",\"frame.protocols\":\"{}\"}}", self.len(), protocols.join(":")).unwrap();
+        let mut written: Vec<&str> = Vec::new();
+        for layer in layers.iter() {
+            if written.contains(&layer.name) {
+                continue;
+            }
+            written.push(layer.name);
+            let same: Vec<&LayerDissection> = layers.iter().filter(|l| l.name == layer.name).collect();
+            write!(out, ",\"{}\":", layer.name).unwrap();
+            if same.len() == 1 {
+                write_json_fields(&mut out, &layer.fields);
+            } else {
+                out.push('[');
+                for (i, l) in same.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_json_fields(&mut out, &l.fields);
+                }
+                out.push(']');
+            }
+        }
+        out.push_str("}}}");
+        out
+    }
+}
+
+fn write_json_fields(out: &mut String, fields: &[Field]) {
+    out.push('{');
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write!(out, "\"{}\":\"{}\"", field.name, json_escape(&field.show)).unwrap();
+        if !field.raw.is_empty() {
+            write!(out, ",\"{}_raw\":[\"{}\",{},{},{}]", field.name, hex(&field.raw), field.offset, field.length, field.bitmask).unwrap();
+        }
+        if !field.children.is_empty() {
+            write!(out, ",\"{}_tree\":", field.name).unwrap();
+            write_json_fields(out, &field.children);
+        }
+    }
+    out.push('}');
+}
+
+fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// dissects the bytes of a single header of the given protocol, which starts `offset` bytes into the packet.
+/// `in_ipv6` is whether the closest IP header before it is IPv6, which makes an ICMP header ICMPv6
+pub(crate) fn dissect_header(proto: Protocol, data: &[u8], offset: usize, in_ipv6: bool) -> LayerDissection {
+    let mut d = Dissector::new(data, offset);
+    let name = match proto {
+        Protocol::ETH => {
+            dissect_ethernet(&mut d);
+            "eth"
+        },
+        Protocol::ARP => {
+            dissect_arp(&mut d);
+            "arp"
+        },
+        Protocol::VLAN => {
+            dissect_vlan(&mut d);
+            "vlan"
+        },
+        Protocol::LLC => {
+            dissect_llc(&mut d);
+            "llc"
+        },
+        Protocol::SNAP => {
+            dissect_snap(&mut d);
+            "snap"
+        },
+        Protocol::LLDP => {
+            dissect_lldp(&mut d);
+            "lldp"
+        },
+        Protocol::MPLS => {
+            dissect_mpls(&mut d);
+            "mpls"
+        },
+        Protocol::PWCW => {
+            dissect_pw_control_word(&mut d);
+            "pwmcw"
+        },
+        Protocol::IP if data.first().is_some_and(|b| b >> 4 == 6) => {
+            dissect_ipv6(&mut d);
+            "ipv6"
+        },
+        Protocol::IP => {
+            dissect_ipv4(&mut d);
+            "ip"
+        },
+        Protocol::TCP => {
+            dissect_tcp(&mut d);
+            "tcp"
+        },
+        Protocol::UDP => {
+            dissect_udp(&mut d);
+            "udp"
+        },
+        Protocol::ICMP => {
+            dissect_icmp(&mut d, in_ipv6);
+            "icmp"
+        },
+    };
+    d.finish(name, Some(proto))
+}
+
+/// collects the fields of one header. positions passed to its methods are relative to the start of the header
+struct Dissector<'a> {
+    data: &'a [u8],
+    base: usize,
+    fields: Vec<Field>,
+}
+
+impl<'a> Dissector<'a> {
+    fn new(data: &'a [u8], base: usize) -> Self {
+        Self { data, base, fields: Vec::new() }
+    }
+
+    /// builds a field without adding it, so that it can be made a child of another field.
+    /// returns `None` if the header is too short to contain the field
+    fn field(&self, name: &'static str, start: usize, length: usize, bitmask: u64, show: String) -> Option<Field> {
+        let raw = self.data.get(start..(start + length))?.to_vec();
+        Some(Field {
+            name,
+            offset: self.base + start,
+            length,
+            bitmask,
+            raw,
+            show,
+            children: Vec::new(),
+        })
+    }
+
+    fn add(&mut self, name: &'static str, start: usize, length: usize, bitmask: u64, show: String) {
+        if let Some(f) = self.field(name, start, length, bitmask, show) {
+            self.fields.push(f);
+        }
+    }
+
+    fn add_with_children(&mut self, name: &'static str, start: usize, length: usize, bitmask: u64, show: String, children: Vec<Field>) {
+        if let Some(mut f) = self.field(name, start, length, bitmask, show) {
+            f.children = children;
+            self.fields.push(f);
+        }
+    }
+
+    /// reads the (masked and shifted) value of up to 8 bytes. bytes past the end of the header read as 0
+    fn value(&self, start: usize, length: usize, bitmask: u64) -> u64 {
+        let value = (start..(start + length)).fold(0u64, |v, i| (v << 8) | *self.data.get(i).unwrap_or(&0) as u64);
+        if bitmask == 0 {
+            value
+        } else {
+            (value & bitmask) >> bitmask.trailing_zeros()
+        }
+    }
+
+    fn has(&self, start: usize, length: usize) -> bool {
+        self.data.len() >= start + length
+    }
+
+    fn finish(self, name: &'static str, proto: Option<Protocol>) -> LayerDissection {
+        LayerDissection {
+            name,
+            proto,
+            offset: self.base,
+            length: self.data.len(),
+            fields: self.fields,
+        }
+    }
+}
+
+fn dissect_ethernet(d: &mut Dissector) {
+    if d.has(0, 14) {
+        let mac = MacAddr::from(<[u8; 6]>::try_from(&d.data[0..6]).unwrap());
+        d.add("eth.dst", 0, 6, 0, mac.to_string());
+        let mac = MacAddr::from(<[u8; 6]>::try_from(&d.data[6..12]).unwrap());
+        d.add("eth.src", 6, 6, 0, mac.to_string());
+    }
+    let eth_type = d.value(12, 2, 0) as u16;
+    if ethertype_numbers::is_length(eth_type) {
+        d.add("eth.len", 12, 2, 0, eth_type.to_string());
+    } else {
+        d.add("eth.type", 12, 2, 0, ethertype_string(eth_type));
+    }
+}
+
+fn dissect_vlan(d: &mut Dissector) {
+    d.add("vlan.priority", 0, 2, 0xe000, d.value(0, 2, 0xe000).to_string());
+    d.add("vlan.dei", 0, 2, 0x1000, set_string(d.value(0, 2, 0x1000)));
+    d.add("vlan.id", 0, 2, 0x0fff, d.value(0, 2, 0x0fff).to_string());
+    let eth_type = d.value(2, 2, 0) as u16;
+    if ethertype_numbers::is_length(eth_type) {
+        d.add("vlan.len", 2, 2, 0, eth_type.to_string());
+    } else {
+        d.add("vlan.etype", 2, 2, 0, ethertype_string(eth_type));
+    }
+}
+
+fn dissect_mpls(d: &mut Dissector) {
+    let label = d.value(0, 4, 0xffff_f000) as u32;
+    let label_name = match label {
+        LABEL_IPV4_EXPLICIT_NULL => "IPv4 Explicit-Null",
+        LABEL_ROUTER_ALERT => "Router Alert",
+        LABEL_IPV6_EXPLICIT_NULL => "IPv6 Explicit-Null",
+        LABEL_IMPLICIT_NULL => "Implicit-Null",
+        _ => "",
+    };
+    let label_string = if label_name.is_empty() { label.to_string() } else { format!("{} ({})", label_name, label) };
+    d.add("mpls.label", 0, 4, 0xffff_f000, label_string);
+    d.add("mpls.exp", 0, 4, 0x0000_0e00, d.value(0, 4, 0x0000_0e00).to_string());
+    d.add("mpls.bottom", 0, 4, 0x0000_0100, d.value(0, 4, 0x0000_0100).to_string());
+    d.add("mpls.ttl", 0, 4, 0x0000_00ff, d.value(0, 4, 0x0000_00ff).to_string());
+}
+
+fn dissect_pw_control_word(d: &mut Dissector) {
+    d.add("pwmcw.flags", 0, 1, 0x0f, format!("0x{:x}", d.value(0, 1, 0x0f)));
+    d.add("pwmcw.fragmentation", 1, 1, 0xc0, d.value(1, 1, 0xc0).to_string());
+    d.add("pwmcw.length", 1, 1, 0x3f, d.value(1, 1, 0x3f).to_string());
+    d.add("pwmcw.sequence_number", 2, 2, 0, d.value(2, 2, 0).to_string());
+}
+
+fn dissect_llc(d: &mut Dissector) {
+    d.add("llc.dsap", 0, 1, 0, sap_string(d.value(0, 1, 0) as u8));
+    d.add("llc.ssap", 1, 1, 0, sap_string(d.value(1, 1, 0) as u8));
+    if d.value(2, 1, 0x03) == 0x03 {
+        d.add("llc.control", 2, 1, 0, format!("0x{:02x}", d.value(2, 1, 0)));
+    } else {
+        d.add("llc.control", 2, 2, 0, format!("0x{:04x}", (d.value(2, 2, 0) as u16).swap_bytes()));
+    }
+}
+
+fn dissect_snap(d: &mut Dissector) {
+    let oui = [d.value(0, 1, 0) as u8, d.value(1, 1, 0) as u8, d.value(2, 1, 0) as u8];
+    let show = match oui {
+        OUI_ETHERTYPE => String::from("Encapsulated Ethernet (0x000000)"),
+        OUI_CISCO => String::from("Cisco (0x00000c)"),
+        _ => format!("0x{}", hex(&oui)),
+    };
+    d.add("llc.oui", 0, 3, 0, show);
+    let pid = d.value(3, 2, 0) as u16;
+    let show = if oui == OUI_ETHERTYPE { ethertype_string(pid) } else { format!("0x{:04x}", pid) };
+    d.add("llc.pid", 3, 2, 0, show);
+}
+
+/// one field per TLV, with the type, length and the parts of the value within it
+fn dissect_lldp(d: &mut Dissector) {
+    let mut start = 0;
+    while let Ok((tlv, length)) = LldpTlv::parse(&d.data[start..]) {
+        let tlv_type = tlv.tlv_type();
+        let mut children = vec![
+            d.field("lldp.tlv.type", start, 2, 0xfe00, format!("{} ({})", lldp_tlv_type_name(tlv_type), tlv_type)),
+            d.field("lldp.tlv.len", start, 2, 0x01ff, (length - 2).to_string()),
+        ];
+        children.extend(lldp_value_fields(d, &tlv, start + 2, length - 2));
+        d.add_with_children("lldp.tlv", start, length, 0, tlv.to_string(), children.into_iter().flatten().collect());
+        start += length;
+        if tlv == LldpTlv::End {
+            break;
+        }
+    }
+}
+
+/// the fields within the value of an LLDP TLV, which starts at `v` and is `length` bytes long
+fn lldp_value_fields(d: &Dissector, tlv: &LldpTlv, v: usize, length: usize) -> Vec<Option<Field>> {
+    let number = |start: usize, size: usize| d.value(start, size, 0).to_string();
+    let hex_number = |start: usize, size: usize| format!("0x{:0width$x}", d.value(start, size, 0), width = size * 2);
+    let org_subtype = match tlv {
+        LldpTlv::PortVlanId(_) | LldpTlv::VlanName { .. } => "lldp.ieee.802_1.subtype",
+        LldpTlv::MacPhyConfig { .. } | LldpTlv::LinkAggregation { .. } | LldpTlv::MaxFrameSize(_) => "lldp.ieee.802_3.subtype",
+        _ => "lldp.orgtlv.subtype",
+    };
+    let org = |fields: Vec<Option<Field>>| {
+        let oui = [d.value(v, 1, 0) as u8, d.value(v + 1, 1, 0) as u8, d.value(v + 2, 1, 0) as u8];
+        let show = match oui {
+            OUI_IEEE_8021 => String::from("IEEE 802.1 (0x0080c2)"),
+            OUI_IEEE_8023 => String::from("IEEE 802.3 (0x00120f)"),
+            _ => format!("0x{}", hex(&oui)),
+        };
+        let mut all = vec![d.field("lldp.orgtlv.oui", v, 3, 0, show), d.field(org_subtype, v + 3, 1, 0, number(v + 3, 1))];
+        all.extend(fields);
+        all
+    };
+    match tlv {
+        LldpTlv::ChassisId { subtype, id } => vec![
+            d.field("lldp.chassis.subtype", v, 1, 0, number(v, 1)),
+            d.field("lldp.chassis.id", v + 1, id.len(), 0, lldp_id_string(*subtype == CHASSIS_ID_MAC_ADDRESS, id)),
+        ],
+        LldpTlv::PortId { subtype, id } => vec![
+            d.field("lldp.port.subtype", v, 1, 0, number(v, 1)),
+            d.field("lldp.port.id", v + 1, id.len(), 0, lldp_id_string(*subtype == PORT_ID_MAC_ADDRESS, id)),
+        ],
+        LldpTlv::Ttl(ttl) => vec![d.field("lldp.time_to_live", v, 2, 0, format!("{} sec", ttl))],
+        LldpTlv::PortDescription(s) => vec![d.field("lldp.port.desc", v, length, 0, s.clone())],
+        LldpTlv::SystemName(s) => vec![d.field("lldp.tlv.system.name", v, length, 0, s.clone())],
+        LldpTlv::SystemDescription(s) => vec![d.field("lldp.tlv.system.desc", v, length, 0, s.clone())],
+        LldpTlv::Capabilities { .. } => vec![
+            d.field("lldp.tlv.system_cap", v, 2, 0, hex_number(v, 2)),
+            d.field("lldp.tlv.enable_system_cap", v + 2, 2, 0, hex_number(v + 2, 2)),
+        ],
+        LldpTlv::ManagementAddress { address, interface_number, .. } => vec![
+            d.field("lldp.mgn.addr.subtype", v + 1, 1, 0, number(v + 1, 1)),
+            d.field("lldp.mgn.addr", v + 2, address.len(), 0, lldp_address_string(address)),
+            d.field("lldp.mgn.if.number", v + 3 + address.len(), 4, 0, interface_number.to_string()),
+        ],
+        LldpTlv::PortVlanId(vlan_id) => org(vec![d.field("lldp.ieee.802_1.port_vlan.id", v + 4, 2, 0, vlan_id.to_string())]),
+        LldpTlv::VlanName { vlan_id, name } => org(vec![
+            d.field("lldp.ieee.802_1.vid", v + 4, 2, 0, vlan_id.to_string()),
+            d.field("lldp.ieee.802_1.vlan.name", v + 7, name.len(), 0, name.clone()),
+        ]),
+        LldpTlv::MacPhyConfig { .. } => org(vec![
+            d.field("lldp.ieee.802_3.autoneg", v + 4, 1, 0, hex_number(v + 4, 1)),
+            d.field("lldp.ieee.802_3.advertised_caps", v + 5, 2, 0, hex_number(v + 5, 2)),
+            d.field("lldp.ieee.802_3.mau_type", v + 7, 2, 0, number(v + 7, 2)),
+        ]),
+        LldpTlv::LinkAggregation { .. } => org(vec![
+            d.field("lldp.ieee.802_3.aggregation_status", v + 4, 1, 0, hex_number(v + 4, 1)),
+            d.field("lldp.ieee.802_3.aggregated_port_id", v + 5, 4, 0, number(v + 5, 4)),
+        ]),
+        LldpTlv::MaxFrameSize(size) => org(vec![d.field("lldp.ieee.802_3.max_frame_size", v + 4, 2, 0, size.to_string())]),
+        LldpTlv::OrganizationallySpecific { .. } => org(Vec::new()),
+        LldpTlv::End | LldpTlv::Unknown { .. } => Vec::new(),
+    }
+}
+
+fn lldp_tlv_type_name(tlv_type: u8) -> &'static str {
+    match tlv_type {
+        TLV_END => "End of LLDPDU",
+        TLV_CHASSIS_ID => "Chassis Id",
+        TLV_PORT_ID => "Port Id",
+        TLV_TTL => "Time to Live",
+        TLV_PORT_DESCRIPTION => "Port Description",
+        TLV_SYSTEM_NAME => "System Name",
+        TLV_SYSTEM_DESCRIPTION => "System Description",
+        TLV_CAPABILITIES => "System Capabilities",
+        TLV_MANAGEMENT_ADDRESS => "Management Address",
+        TLV_ORGANIZATIONALLY_SPECIFIC => "Organization Specific",
+        _ => "Unknown",
+    }
+}
+
+fn sap_string(sap: u8) -> String {
+    let name = match sap {
+        SAP_SNAP => "SNAP",
+        SAP_STP => "Spanning Tree BPDU",
+        SAP_IPX => "NetWare",
+        SAP_NETBIOS => "NetBIOS",
+        _ => return format!("0x{:02x}", sap),
+    };
+    format!("{} (0x{:02x})", name, sap)
+}
+
+fn dissect_arp(d: &mut Dissector) {
+    let hardware_type = d.value(0, 2, 0);
+    let show = match hardware_type as u16 {
+        HARDWARE_ETHERNET => String::from("Ethernet (1)"),
+        HARDWARE_IEEE802 => String::from("IEEE 802 Networks (6)"),
+        HARDWARE_INFINIBAND => String::from("InfiniBand (32)"),
+        n => n.to_string(),
+    };
+    d.add("arp.hw.type", 0, 2, 0, show);
+    d.add("arp.proto.type", 2, 2, 0, ethertype_string(d.value(2, 2, 0) as u16));
+    let hardware_size = d.value(4, 1, 0) as usize;
+    let protocol_size = d.value(5, 1, 0) as usize;
+    d.add("arp.hw.size", 4, 1, 0, hardware_size.to_string());
+    d.add("arp.proto.size", 5, 1, 0, protocol_size.to_string());
+    let opcode = d.value(6, 2, 0);
+    let show = match opcode {
+        1 => String::from("request (1)"),
+        2 => String::from("reply (2)"),
+        3 => String::from("reverse request (3)"),
+        4 => String::from("reverse reply (4)"),
+        n => n.to_string(),
+    };
+    d.add("arp.opcode", 6, 2, 0, show);
+    // like wireshark, the addresses only get the _mac and _ipv4 names when they are that long
+    let names = match (hardware_size, protocol_size) {
+        (6, 4) => [("arp.src.hw_mac", "arp.src.proto_ipv4"), ("arp.dst.hw_mac", "arp.dst.proto_ipv4")],
+        (6, _) => [("arp.src.hw_mac", "arp.src.proto"), ("arp.dst.hw_mac", "arp.dst.proto")],
+        (_, 4) => [("arp.src.hw", "arp.src.proto_ipv4"), ("arp.dst.hw", "arp.dst.proto_ipv4")],
+        _ => [("arp.src.hw", "arp.src.proto"), ("arp.dst.hw", "arp.dst.proto")],
+    };
+    let mut start = 8;
+    for (hw_name, proto_name) in names.iter() {
+        if d.has(start, hardware_size) {
+            let show = hw_address_string(&d.data[start..(start + hardware_size)]);
+            d.add(hw_name, start, hardware_size, 0, show);
+        }
+        start += hardware_size;
+        if d.has(start, protocol_size) {
+            let show = proto_address_string(&d.data[start..(start + protocol_size)]);
+            d.add(proto_name, start, protocol_size, 0, show);
+        }
+        start += protocol_size;
+    }
+}
+
+fn dissect_ipv4(d: &mut Dissector) {
+    d.add("ip.version", 0, 1, 0xf0, d.value(0, 1, 0xf0).to_string());
+    let header_len = d.value(0, 1, 0x0f) as usize * 4;
+    d.add("ip.hdr_len", 0, 1, 0x0f, format!("{} bytes ({})", header_len, header_len / 4));
+    d.add("ip.dsfield", 1, 1, 0, format!("0x{:02x}", d.value(1, 1, 0)));
+    d.add("ip.len", 2, 2, 0, d.value(2, 2, 0).to_string());
+    let id = d.value(4, 2, 0);
+    d.add("ip.id", 4, 2, 0, format!("0x{:04x} ({})", id, id));
+    let flags = vec![
+        d.field("ip.flags.rb", 6, 1, 0x80, set_string(d.value(6, 1, 0x80))),
+        d.field("ip.flags.df", 6, 1, 0x40, set_string(d.value(6, 1, 0x40))),
+        d.field("ip.flags.mf", 6, 1, 0x20, set_string(d.value(6, 1, 0x20))),
+    ].into_iter().flatten().collect();
+    let flag_bits = d.value(6, 1, 0xe0);
+    let show = match flag_bits {
+        0b010 => String::from("0x2 (Don't fragment)"),
+        0b001 => String::from("0x1 (More fragments)"),
+        n => format!("0x{:x}", n),
+    };
+    d.add_with_children("ip.flags", 6, 1, 0xe0, show, flags);
+    d.add("ip.frag_offset", 6, 2, 0x1fff, (d.value(6, 2, 0x1fff) * 8).to_string());
+    d.add("ip.ttl", 8, 1, 0, d.value(8, 1, 0).to_string());
+    d.add("ip.proto", 9, 1, 0, protocol_string(d.value(9, 1, 0) as u8));
+    d.add("ip.checksum", 10, 2, 0, format!("0x{:04x}", d.value(10, 2, 0)));
+    if d.has(12, 8) {
+        let src = Ipv4Addr::new(d.data[12], d.data[13], d.data[14], d.data[15]).to_string();
+        d.add("ip.src", 12, 4, 0, src);
+        let dst = Ipv4Addr::new(d.data[16], d.data[17], d.data[18], d.data[19]).to_string();
+        d.add("ip.dst", 16, 4, 0, dst);
+    }
+    if header_len > 20 && d.has(20, header_len - 20) {
+        let show = hex(&d.data[20..header_len]);
+        d.add("ip.options", 20, header_len - 20, 0, show);
+    }
+}
+
+fn dissect_ipv6(d: &mut Dissector) {
+    d.add("ipv6.version", 0, 1, 0xf0, d.value(0, 1, 0xf0).to_string());
+    d.add("ipv6.tclass", 0, 4, 0x0ff0_0000, format!("0x{:02x}", d.value(0, 4, 0x0ff0_0000)));
+    d.add("ipv6.flow", 0, 4, 0x000f_ffff, format!("0x{:05x}", d.value(0, 4, 0x000f_ffff)));
+    d.add("ipv6.plen", 4, 2, 0, d.value(4, 2, 0).to_string());
+    d.add("ipv6.nxt", 6, 1, 0, protocol_string(d.value(6, 1, 0) as u8));
+    d.add("ipv6.hlim", 7, 1, 0, d.value(7, 1, 0).to_string());
+    if d.has(8, 32) {
+        let mut src = [0u8; 16];
+        src.copy_from_slice(&d.data[8..24]);
+        d.add("ipv6.src", 8, 16, 0, Ipv6Addr::from(src).to_string());
+        let mut dst = [0u8; 16];
+        dst.copy_from_slice(&d.data[24..40]);
+        d.add("ipv6.dst", 24, 16, 0, Ipv6Addr::from(dst).to_string());
+    }
+}
+
+/// the names of the tcp flags, from the most significant bit of the 12 bit flags field down, as (field name, mask, short name)
+pub(crate) const TCP_FLAGS: [(&str, u64, &str); 9] = [
+    ("tcp.flags.ns", 0x100, "NS"),
+    ("tcp.flags.cwr", 0x080, "CWR"),
+    ("tcp.flags.ece", 0x040, "ECE"),
+    ("tcp.flags.urg", 0x020, "URG"),
+    ("tcp.flags.ack", 0x010, "ACK"),
+    ("tcp.flags.push", 0x008, "PSH"),
+    ("tcp.flags.reset", 0x004, "RST"),
+    ("tcp.flags.syn", 0x002, "SYN"),
+    ("tcp.flags.fin", 0x001, "FIN"),
+];
+
+fn dissect_tcp(d: &mut Dissector) {
+    d.add("tcp.srcport", 0, 2, 0, port_string(d.value(0, 2, 0) as u16));
+    d.add("tcp.dstport", 2, 2, 0, port_string(d.value(2, 2, 0) as u16));
+    d.add("tcp.seq", 4, 4, 0, d.value(4, 4, 0).to_string());
+    d.add("tcp.ack", 8, 4, 0, d.value(8, 4, 0).to_string());
+    let header_len = d.value(12, 1, 0xf0) as usize * 4;
+    d.add("tcp.hdr_len", 12, 1, 0xf0, format!("{} bytes ({})", header_len, header_len / 4));
+    let flags = d.value(12, 2, 0x0fff);
+    let mut children: Vec<Field> = d.field("tcp.flags.res", 12, 2, 0x0e00, set_string(d.value(12, 2, 0x0e00))).into_iter().collect();
+    let mut names = Vec::new();
+    for (name, mask, short) in TCP_FLAGS.iter() {
+        if let Some(f) = d.field(name, 12, 2, *mask, set_string(flags & mask)) {
+            children.push(f);
+        }
+        if flags & mask != 0 {
+            names.push(*short);
+        }
+    }
+    let show = if names.is_empty() {
+        format!("0x{:03x}", flags)
+    } else {
+        format!("0x{:03x} ({})", flags, names.join(", "))
+    };
+    d.add_with_children("tcp.flags", 12, 2, 0x0fff, show, children);
+    d.add("tcp.window_size_value", 14, 2, 0, d.value(14, 2, 0).to_string());
+    d.add("tcp.checksum", 16, 2, 0, format!("0x{:04x}", d.value(16, 2, 0)));
+    d.add("tcp.urgent_pointer", 18, 2, 0, d.value(18, 2, 0).to_string());
+    if header_len > 20 && d.has(20, header_len - 20) {
+        let show = hex(&d.data[20..header_len]);
+        let options = tcp_option_fields(d, header_len);
+        d.add_with_children("tcp.options", 20, header_len - 20, 0, show, options);
+    }
+}
+
+/// the individual options of a TCP header whose options end at `end`
+fn tcp_option_fields(d: &Dissector, end: usize) -> Vec<Field> {
+    let mut options = Vec::new();
+    let mut start = 20;
+    while start < end {
+        let kind = d.value(start, 1, 0);
+        if kind == 0 {
+            options.extend(d.field("tcp.options.eol", start, 1, 0, String::from("End of Option List (EOL)")));
+            break;
+        }
+        if kind == 1 {
+            options.extend(d.field("tcp.options.nop", start, 1, 0, String::from("No-Operation (NOP)")));
+            start += 1;
+            continue;
+        }
+        let length = d.value(start + 1, 1, 0) as usize;
+        // an option too short to hold its own kind and length, or running past the end of the options, ends them
+        if length < 2 || start + length > end {
+            break;
+        }
+        let v = start + 2;
+        let number = |offset: usize, size: usize| d.value(v + offset, size, 0).to_string();
+        let (name, show, values) = match (kind, length) {
+            (2, 4) => (
+                "tcp.options.mss",
+                format!("Maximum segment size: {} bytes", number(0, 2)),
+                vec![d.field("tcp.options.mss_val", v, 2, 0, number(0, 2))],
+            ),
+            (3, 3) => (
+                "tcp.options.wscale",
+                format!("Window scale: {} (multiply by {})", number(0, 1), 1u32 << d.value(v, 1, 0).min(31)),
+                vec![d.field("tcp.options.wscale.shift", v, 1, 0, number(0, 1))],
+            ),
+            (4, 2) => ("tcp.options.sack_perm", String::from("SACK permitted"), Vec::new()),
+            (5, _) => {
+                let edges = (0..((length - 2) / 8)).flat_map(|i| {
+                    vec![
+                        d.field("tcp.options.sack_le", v + i * 8, 4, 0, number(i * 8, 4)),
+                        d.field("tcp.options.sack_re", v + i * 8 + 4, 4, 0, number(i * 8 + 4, 4)),
+                    ]
+                });
+                ("tcp.options.sack", String::from("SACK"), edges.collect())
+            }
+            (8, 10) => (
+                "tcp.options.timestamp",
+                format!("Timestamps: TSval {}, TSecr {}", number(0, 4), number(4, 4)),
+                vec![
+                    d.field("tcp.options.timestamp.tsval", v, 4, 0, number(0, 4)),
+                    d.field("tcp.options.timestamp.tsecr", v + 4, 4, 0, number(4, 4)),
+                ],
+            ),
+            _ => {
+                let data = match length {
+                    2 => Vec::new(),
+                    _ => vec![d.field("tcp.options.data", v, length - 2, 0, hex(&d.data[v..(start + length)]))],
+                };
+                ("tcp.options.unknown", format!("Unknown ({})", kind), data)
+            }
+        };
+        let mut children = vec![
+            d.field("tcp.option_kind", start, 1, 0, kind.to_string()),
+            d.field("tcp.option_len", start + 1, 1, 0, length.to_string()),
+        ];
+        children.extend(values);
+        options.extend(d.field(name, start, length, 0, show).map(|mut option| {
+            option.children = children.into_iter().flatten().collect();
+            option
+        }));
+        start += length;
+    }
+    options
+}
+
+fn dissect_udp(d: &mut Dissector) {
+    d.add("udp.srcport", 0, 2, 0, port_string(d.value(0, 2, 0) as u16));
+    d.add("udp.dstport", 2, 2, 0, port_string(d.value(2, 2, 0) as u16));
+    d.add("udp.length", 4, 2, 0, d.value(4, 2, 0).to_string());
+    d.add("udp.checksum", 6, 2, 0, format!("0x{:04x}", d.value(6, 2, 0)));
+}
+
+fn dissect_icmp(d: &mut Dissector, icmpv6: bool) {
+    let msg_type = d.value(0, 1, 0) as u8;
+    let name = if icmpv6 { icmpv6_type_name(msg_type) } else { icmp_type_name(msg_type) };
+    let show = match name {
+        Some(name) => format!("{} ({})", msg_type, name),
+        None => msg_type.to_string(),
+    };
+    d.add("icmp.type", 0, 1, 0, show);
+    d.add("icmp.code", 1, 1, 0, d.value(1, 1, 0).to_string());
+    d.add("icmp.checksum", 2, 2, 0, format!("0x{:04x}", d.value(2, 2, 0)));
+    let ident = d.value(4, 2, 0);
+    d.add("icmp.ident", 4, 2, 0, format!("{} (0x{:04x})", ident, ident));
+    let seq = d.value(6, 2, 0);
+    d.add("icmp.seq", 6, 2, 0, format!("{} (0x{:04x})", seq, seq));
+}
+
+/// Returns the name of the ICMP message type, for the more common types
+pub fn icmp_type_name(msg_type: u8) -> Option<&'static str> {
+    Some(match msg_type {
+        0 => "Echo (ping) reply",
+        3 => "Destination unreachable",
+        4 => "Source quench",
+        5 => "Redirect",
+        8 => "Echo (ping) request",
+        9 => "Router advertisement",
+        10 => "Router solicitation",
+        11 => "Time-to-live exceeded",
+        12 => "Parameter problem",
+        13 => "Timestamp request",
+        14 => "Timestamp reply",
+        _ => return None,
+    })
+}
+
+/// Returns the name of the ICMPv6 message type, for the more common types, including the neighbor discovery messages
+pub fn icmpv6_type_name(msg_type: u8) -> Option<&'static str> {
+    Some(match msg_type {
+        1 => "Destination Unreachable",
+        2 => "Packet Too Big",
+        3 => "Time Exceeded",
+        4 => "Parameter Problem",
+        128 => "Echo (ping) request",
+        129 => "Echo (ping) reply",
+        133 => "Router Solicitation",
+        134 => "Router Advertisement",
+        135 => "Neighbor Solicitation",
+        136 => "Neighbor Advertisement",
+        137 => "Redirect",
+        143 => "Multicast Listener Report Message v2",
+        _ => return None,
+    })
+}
+
+/// Returns the IANA service name of a well known TCP / UDP port
+pub fn service_name(port: u16) -> Option<&'static str> {
+    Some(match port {
+        7 => "echo",
+        20 => "ftp-data",
+        21 => "ftp",
+        22 => "ssh",
+        23 => "telnet",
+        25 => "smtp",
+        53 => "domain",
+        67 => "bootps",
+        68 => "bootpc",
+        69 => "tftp",
+        80 => "http",
+        110 => "pop3",
+        123 => "ntp",
+        137 => "netbios-ns",
+        138 => "netbios-dgm",
+        139 => "netbios-ssn",
+        143 => "imap",
+        161 => "snmp",
+        162 => "snmptrap",
+        179 => "bgp",
+        389 => "ldap",
+        443 => "https",
+        445 => "microsoft-ds",
+        514 => "syslog",
+        546 => "dhcpv6-client",
+        547 => "dhcpv6-server",
+        993 => "imaps",
+        995 => "pop3s",
+        1812 => "radius",
+        3306 => "mysql",
+        3389 => "ms-wbt-server",
+        5060 => "sip",
+        5432 => "postgresql",
+        8080 => "http-alt",
+        _ => return None,
+    })
+}
+
+fn port_string(port: u16) -> String {
+    match service_name(port) {
+        Some(name) => format!("{} ({})", port, name),
+        None => port.to_string(),
+    }
+}
+
+fn protocol_string(protocol: u8) -> String {
+    match protocol_numbers::protocol_name(protocol) {
+        Some(name) => format!("{} ({})", name, protocol),
+        None => protocol.to_string(),
+    }
+}
+
+fn ethertype_string(eth_type: u16) -> String {
+    match ethertype_numbers::ethertype_name(eth_type) {
+        Some(name) => format!("{} (0x{:04x})", name, eth_type),
+        None => format!("Unknown (0x{:04x})", eth_type),
+    }
+}
+
+fn set_string(bit: u64) -> String {
+    String::from(if bit != 0 { "Set" } else { "Not set" })
+}
+
+pub(crate) fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
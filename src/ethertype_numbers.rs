@@ -9,4 +9,44 @@ pub const ETHERTYPE_RARP: u16          = 0x8035;
 /// IPv6
 pub const ETHERTYPE_IPV6: u16          = 0x86DD;
 /// Link Layer Discovery Protocol
-pub const ETHERTYPE_LLDP: u16          = 0x88CC;
\ No newline at end of file
+pub const ETHERTYPE_LLDP: u16          = 0x88CC;
+/// MPLS unicast
+pub const ETHERTYPE_MPLS_UNICAST: u16  = 0x8847;
+/// MPLS multicast
+pub const ETHERTYPE_MPLS_MULTICAST: u16 = 0x8848;
+/// 802.1Q VLAN tag
+pub const ETHERTYPE_VLAN: u16          = 0x8100;
+/// 802.1ad service VLAN tag, the outer tag of QinQ
+pub const ETHERTYPE_QINQ: u16          = 0x88A8;
+/// the QinQ outer tag used before 802.1ad was standardised
+pub const ETHERTYPE_QINQ_LEGACY: u16   = 0x9100;
+
+/// the largest value of the type field which is the length of an IEEE 802.3 frame rather than an ethertype
+pub const MAX_8023_LENGTH: u16         = 1500;
+
+/// whether the type field is the length of an IEEE 802.3 frame, which is followed by an LLC header, rather than an ethertype
+pub fn is_length(eth_type: u16) -> bool {
+    eth_type <= MAX_8023_LENGTH
+}
+
+/// whether the ethertype says a VLAN tag comes next
+pub fn is_vlan_tag(eth_type: u16) -> bool {
+    matches!(eth_type, ETHERTYPE_VLAN | ETHERTYPE_QINQ | ETHERTYPE_QINQ_LEGACY)
+}
+
+/// Returns the name of the protocol with the given ethertype, as shown by packet analysers
+pub fn ethertype_name(eth_type: u16) -> Option<&'static str> {
+    Some(match eth_type {
+        ETHERTYPE_IPV4 => "IPv4",
+        ETHERTYPE_ARP => "ARP",
+        ETHERTYPE_RARP => "RARP",
+        ETHERTYPE_IPV6 => "IPv6",
+        ETHERTYPE_LLDP => "LLDP",
+        ETHERTYPE_MPLS_UNICAST => "MPLS label switched packet",
+        ETHERTYPE_MPLS_MULTICAST => "MPLS multicast label switched packet",
+        ETHERTYPE_VLAN => "802.1Q Virtual LAN",
+        ETHERTYPE_QINQ => "802.1ad Provider Bridge (Q-in-Q)",
+        ETHERTYPE_QINQ_LEGACY => "QinQ (old)",
+        _ => return None,
+    })
+}
@@ -0,0 +1,358 @@
+//! A mutation fuzzer which takes a valid packet and produces malformed versions of it, for testing network stacks against bad input.
+//!
+//! The fuzzer knows the layers and fields of the packet through its dissection, so rather than flipping random bits it makes the kinds
+//! of mistakes a parser has to cope with: boundary values in numeric fields, wrong lengths, bad checksums, truncated packets,
+//! duplicated or removed options and layers which are missing, repeated or in the wrong order.
+//! Every mutant is worked out from the seed and its index alone, so any one of them can be reproduced on its own.
+//!
+//! ```text
+//! let fuzzer = Fuzzer::new(packet, 42);
+//! for mutant in fuzzer.take(10_000) {
+//!     your_function_to_send_packet_down_socket(&mutant.data);
+//! }
+//! ```
+
+use super::{random_u64, ComputedField, Layer, Packet, Protocol, Rng};
+use crate::dissect::Field;
+use crate::template::{write_field, Location};
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Range;
+
+/// The length fields which `WrongLength` mutations are made to
+pub const LENGTH_FIELDS: [&str; 7] = ["arp.hw.size", "arp.proto.size", "ip.hdr_len", "ip.len", "ipv6.plen", "tcp.hdr_len", "udp.length"];
+
+/// A single change made to a packet. Layers are numbered from the outermost header inwards, starting at 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mutation {
+    /// a numeric field set to one of the values at the edges of its range: 0, 1, the middle two values or the top two
+    BoundaryValue { layer: usize, field: &'static str, value: u64 },
+    /// a length field set to 0, one less or one more than it should be, or its largest value
+    WrongLength { layer: usize, field: &'static str, value: u64 },
+    /// a checksum field with one of its bits flipped
+    BadChecksum { layer: usize, field: &'static str, value: u64 },
+    /// the packet cut short after `length` bytes
+    Truncate { length: usize },
+    /// the option at position `option` in the options of an IPv4 or TCP header repeated straight after itself
+    DuplicateOption { layer: usize, option: usize },
+    /// the option at position `option` in the options of an IPv4 or TCP header taken out
+    RemoveOption { layer: usize, option: usize },
+    /// a layer repeated straight after itself
+    DuplicateLayer { layer: usize },
+    /// a layer taken out
+    RemoveLayer { layer: usize },
+    /// two layers swapped round
+    SwapLayers { first: usize, second: usize },
+}
+
+impl Mutation {
+    /// Makes this change to `packet`, returning the bytes of the malformed packet.
+    /// Apart from the field being changed, the length and checksum fields are calculated as normal,
+    /// so that each mutant only has one thing wrong with it. Changes to computed fields are made with `Packet::set_override`
+    pub fn apply(&self, packet: &Packet) -> Vec<u8> {
+        let mut packet = packet.clone();
+        match *self {
+            Mutation::BoundaryValue { layer, field, value }
+            | Mutation::WrongLength { layer, field, value }
+            | Mutation::BadChecksum { layer, field, value } => match ComputedField::from_field_name(field) {
+                Some(computed) if packet.has_computed_field(layer, computed) => packet.set_override(layer, computed, value as u16),
+                _ => {
+                    let location = packet.dissect().get(layer).and_then(|l| l.get_field(field)).map(Location::from);
+                    if let Some(location) = location {
+                        write_field(&mut packet, &location, value);
+                        packet.finalize();
+                    }
+                }
+            },
+            Mutation::Truncate { length } => {
+                let mut data = packet.into_vec();
+                data.truncate(length);
+                return data;
+            }
+            Mutation::DuplicateOption { layer, option } => edit_options(&mut packet, layer, option, true),
+            Mutation::RemoveOption { layer, option } => edit_options(&mut packet, layer, option, false),
+            Mutation::DuplicateLayer { layer } => {
+                let mut order: Vec<usize> = (0..packet.layer_count()).collect();
+                order.insert(layer, layer);
+                reorder_layers(&mut packet, &order);
+            }
+            Mutation::RemoveLayer { layer } => {
+                packet.remove_header(layer);
+            }
+            Mutation::SwapLayers { first, second } => {
+                let mut order: Vec<usize> = (0..packet.layer_count()).collect();
+                order.swap(first, second);
+                reorder_layers(&mut packet, &order);
+            }
+        }
+        packet.into_vec()
+    }
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mutation::BoundaryValue { layer, field, value } => write!(f, "boundary value: {} of layer {} set to {}", field, layer, value),
+            Mutation::WrongLength { layer, field, value } => write!(f, "wrong length: {} of layer {} set to {}", field, layer, value),
+            Mutation::BadChecksum { layer, field, value } => write!(f, "bad checksum: {} of layer {} set to 0x{:04x}", field, layer, value),
+            Mutation::Truncate { length } => write!(f, "truncated to {} bytes", length),
+            Mutation::DuplicateOption { layer, option } => write!(f, "option {} of layer {} duplicated", option, layer),
+            Mutation::RemoveOption { layer, option } => write!(f, "option {} of layer {} removed", option, layer),
+            Mutation::DuplicateLayer { layer } => write!(f, "layer {} duplicated", layer),
+            Mutation::RemoveLayer { layer } => write!(f, "layer {} removed", layer),
+            Mutation::SwapLayers { first, second } => write!(f, "layers {} and {} swapped", first, second),
+        }
+    }
+}
+
+/// A malformed packet, along with what was done to make it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mutant {
+    /// the position of this mutant in the fuzzer's sequence, which together with the seed is enough to make it again
+    pub index: u64,
+    pub mutation: Mutation,
+    pub data: Vec<u8>,
+}
+
+impl AsRef<[u8]> for Mutant {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Produces an endless, reproducible sequence of mutants of a packet. Use `take` to get as many as are needed
+#[derive(Debug, Clone)]
+pub struct Fuzzer {
+    base: Packet,
+    seed: u64,
+    next: u64,
+}
+
+impl Fuzzer {
+    /// Creates a fuzzer for `base`. The same seed always gives the same mutants
+    pub fn new(base: Packet, seed: u64) -> Self {
+        Self { base, seed, next: 0 }
+    }
+
+    /// Returns the mutant at position `index` in the sequence, without making any of the ones before it
+    pub fn mutant(&self, index: u64) -> Mutant {
+        let mut rng = Rng::new(random_u64(self.seed, index));
+        let mutation = self.choose(&mut rng);
+        let data = mutation.apply(&self.base);
+        Mutant { index, mutation, data }
+    }
+
+    fn choose(&self, rng: &mut Rng) -> Mutation {
+        let layers = self.base.dissect();
+        let fields: Vec<(usize, &Field)> = layers
+            .iter()
+            .enumerate()
+            .filter(|(_, layer)| layer.proto.is_some())
+            .flat_map(|(index, layer)| leaf_fields(&layer.fields).into_iter().map(move |field| (index, field)))
+            .filter(|(_, field)| field.length <= 8 && !Location::from(*field).is_in_trailer(&self.base))
+            .collect();
+        let lengths: Vec<&(usize, &Field)> = fields.iter().filter(|(_, f)| LENGTH_FIELDS.contains(&f.name)).collect();
+        let checksums: Vec<&(usize, &Field)> = fields.iter().filter(|(_, f)| f.name.ends_with(".checksum")).collect();
+        let options: Vec<(usize, usize)> = (0..self.base.layer_count())
+            .flat_map(|layer| (0..options(&self.base, layer).len()).map(move |option| (layer, option)))
+            .collect();
+        let layer_count = self.base.layer_count();
+
+        let mut kinds = Vec::new();
+        if !fields.is_empty() {
+            kinds.push(0);
+        }
+        if !lengths.is_empty() {
+            kinds.push(1);
+        }
+        if !checksums.is_empty() {
+            kinds.push(2);
+        }
+        if !self.base.is_empty() {
+            kinds.push(3);
+        }
+        if !options.is_empty() {
+            kinds.extend([4, 5]);
+        }
+        if layer_count > 0 {
+            kinds.extend([6, 7]);
+        }
+        if layer_count > 1 {
+            kinds.push(8);
+        }
+
+        match rng.pick(&kinds) {
+            0 => {
+                let (layer, field) = rng.pick(&fields);
+                let max = max_value(field);
+                let values = [0, 1, max >> 1, (max >> 1) + 1, max - 1, max];
+                Mutation::BoundaryValue { layer: *layer, field: field.name, value: *rng.pick(&values) }
+            }
+            1 => {
+                let (layer, field) = rng.pick(&lengths);
+                let (max, actual) = (max_value(field), field.value().unwrap_or(0));
+                let values = [0, actual.wrapping_sub(1) & max, actual.wrapping_add(1) & max, max];
+                Mutation::WrongLength { layer: *layer, field: field.name, value: *rng.pick(&values) }
+            }
+            2 => {
+                let (layer, field) = rng.pick(&checksums);
+                let value = field.value().unwrap_or(0) ^ (1 << rng.below(16));
+                Mutation::BadChecksum { layer: *layer, field: field.name, value }
+            }
+            3 => Mutation::Truncate { length: rng.below(self.base.len()) },
+            4 => {
+                let (layer, option) = *rng.pick(&options);
+                Mutation::DuplicateOption { layer, option }
+            }
+            5 => {
+                let (layer, option) = *rng.pick(&options);
+                Mutation::RemoveOption { layer, option }
+            }
+            6 => Mutation::DuplicateLayer { layer: rng.below(layer_count) },
+            7 => Mutation::RemoveLayer { layer: rng.below(layer_count) },
+            _ => {
+                let first = rng.below(layer_count);
+                let second = (first + 1 + rng.below(layer_count - 1)) % layer_count;
+                Mutation::SwapLayers { first: first.min(second), second: first.max(second) }
+            }
+        }
+    }
+}
+
+impl Iterator for Fuzzer {
+    type Item = Mutant;
+
+    fn next(&mut self) -> Option<Mutant> {
+        self.next += 1;
+        Some(self.mutant(self.next - 1))
+    }
+}
+
+/// Shrinks a corpus down to the smallest inputs which between them still cover everything the whole corpus covered, like `afl-cmin`.
+/// `features` returns whatever the system under test did with an input (code paths hit, error codes, log lines, ...).
+/// Inputs are tried from the shortest up, and each one is kept only if it does something none of the inputs kept before it did
+pub fn minimize_corpus<T, K, F>(corpus: Vec<T>, mut features: F) -> Vec<T>
+where
+    T: AsRef<[u8]>,
+    K: Eq + Hash,
+    F: FnMut(&[u8]) -> Vec<K>,
+{
+    let mut corpus = corpus;
+    corpus.sort_by_key(|input| input.as_ref().len());
+    let mut seen = HashSet::new();
+    let mut kept = Vec::new();
+    for input in corpus {
+        let mut new = false;
+        for feature in features(input.as_ref()) {
+            new |= seen.insert(feature);
+        }
+        if new {
+            kept.push(input);
+        }
+    }
+    kept
+}
+
+/// the largest value that fits in the field
+fn max_value(field: &Field) -> u64 {
+    let bits = if field.bitmask == 0 { field.length as u32 * 8 } else { field.bitmask.count_ones() };
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+/// the fields which have no fields within them, in order
+fn leaf_fields(fields: &[Field]) -> Vec<&Field> {
+    let mut leaves = Vec::new();
+    for field in fields {
+        if field.children.is_empty() {
+            leaves.push(field);
+        } else {
+            leaves.extend(leaf_fields(&field.children));
+        }
+    }
+    leaves
+}
+
+/// the byte ranges of the options of the IPv4 or TCP header at position `index`, relative to the start of the header
+fn options(packet: &Packet, index: usize) -> Vec<Range<usize>> {
+    let layer = packet.layers[index];
+    let data = &packet.buffer[layer.offset..(layer.offset + layer.length)];
+    let has_options = match layer.proto {
+        Protocol::IP => data[0] >> 4 == 4,
+        Protocol::TCP => true,
+        _ => false,
+    };
+    let mut options = Vec::new();
+    if !has_options {
+        return options;
+    }
+    let mut i = 20;
+    while i < data.len() {
+        match data[i] {
+            0 => break, // end of options list
+            1 => {
+                options.push(i..(i + 1)); // no-op
+                i += 1;
+            }
+            _ => {
+                let length = data.get(i + 1).map(|l| *l as usize).unwrap_or(0);
+                if length < 2 || i + length > data.len() {
+                    break;
+                }
+                options.push(i..(i + length));
+                i += length;
+            }
+        }
+    }
+    options
+}
+
+/// duplicates or removes one of the options of the header at position `index`, padding the options back out to a multiple
+/// of 4 bytes and updating the header length field. duplicating an option which would take the header over 60 bytes does nothing
+fn edit_options(packet: &mut Packet, index: usize, option: usize, duplicate: bool) {
+    let ranges = options(packet, index);
+    if option >= ranges.len() {
+        return;
+    }
+    let layer = packet.layers[index];
+    let data = &packet.buffer[layer.offset..(layer.offset + layer.length)];
+    let mut header = data[..20].to_vec();
+    for (i, range) in ranges.iter().enumerate() {
+        let copies = if i != option { 1 } else if duplicate { 2 } else { 0 };
+        for _ in 0..copies {
+            header.extend_from_slice(&data[range.clone()]);
+        }
+    }
+    while !header.len().is_multiple_of(4) {
+        header.push(0);
+    }
+    if header.len() > 60 {
+        return;
+    }
+    let words = (header.len() / 4) as u8;
+    match layer.proto {
+        Protocol::IP => header[0] = (header[0] & 0xf0) | words,
+        _ => header[12] = (header[12] & 0x0f) | (words << 4),
+    }
+    let length = header.len();
+    packet.splice_layer_data(layer.offset, layer.length, header);
+    packet.shift_offsets(index + 1, length as isize - layer.length as isize);
+    packet.layers[index].length = length;
+    packet.finalize();
+}
+
+/// rebuilds the headers of the packet with its layers in the order given by `order`, which holds the old position of each layer
+fn reorder_layers(packet: &mut Packet, order: &[usize]) {
+    let (old_layers, old_buffer) = (std::mem::take(&mut packet.layers), std::mem::take(&mut packet.buffer));
+    for &i in order {
+        let layer = old_layers[i];
+        let offset = packet.buffer.len();
+        packet.buffer.extend_from_slice(&old_buffer[layer.offset..(layer.offset + layer.length)]);
+        packet.layers.push(Layer { offset, ..layer });
+    }
+    packet.finalize();
+}
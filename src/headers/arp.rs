@@ -1,92 +1,308 @@
-use super::{Header, PacketData, Protocol, ParseError};
-use crate::AsBeBytes;
+use super::{Header, Protocol, ParseError};
+use crate::MacAddr;
+use bytes::BufMut;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
 
 pub const REQUEST: u16 = 1;
 pub const REPLY: u16 = 2;
+/// the RARP opcodes, which are sent with `ETHERTYPE_RARP` rather than `ETHERTYPE_ARP`
+pub const REVERSE_REQUEST: u16 = 3;
+pub const REVERSE_REPLY: u16 = 4;
 
-#[derive(AddGetter, AddSetter)]
+pub const HARDWARE_ETHERNET: u16 = 1;
+pub const HARDWARE_IEEE802: u16 = 6;
+pub const HARDWARE_INFINIBAND: u16 = 32;
+
+/// An ARP (or RARP) header, with addresses of any length.
+///
+/// The sender and target hardware addresses are always the same length as each other, as are the protocol addresses,
+/// and the size fields are the lengths of them. `new` makes the usual IPv4 over ethernet header, and `with_addresses` any other kind.
+/// Deserialising checks this too
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(try_from = "UncheckedArpHeader"))]
 #[get]
 pub struct ArpHeader {
+    #[set] hardware_type: u16,
+    #[set] protocol_type: u16,
+    hardware_size: u8,
+    protocol_size: u8,
+    #[set] opcode: u16,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::hardware_address"))]
+    sender_hw_address: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::protocol_address"))]
+    sender_proto_address: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::hardware_address"))]
+    destination_hw_address: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::protocol_address"))]
+    destination_proto_address: Vec<u8>,
+}
+
+/// the fields of an `ArpHeader` as they are deserialised, before they are checked
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedArpHeader {
     hardware_type: u16,
     protocol_type: u16,
     hardware_size: u8,
     protocol_size: u8,
-    #[set] opcode: u16,
-    #[set] sender_mac: [u8; 6],
-    #[set] sender_ip: [u8; 4],
-    #[set] destination_mac: [u8; 6],
-    #[set] destination_ip: [u8; 4],
+    opcode: u16,
+    #[serde(alias = "sender_mac", with = "crate::serde_support::hardware_address")]
+    sender_hw_address: Vec<u8>,
+    #[serde(alias = "sender_ip", with = "crate::serde_support::protocol_address")]
+    sender_proto_address: Vec<u8>,
+    #[serde(alias = "destination_mac", with = "crate::serde_support::hardware_address")]
+    destination_hw_address: Vec<u8>,
+    #[serde(alias = "destination_ip", with = "crate::serde_support::protocol_address")]
+    destination_proto_address: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedArpHeader> for ArpHeader {
+    type Error = String;
+
+    fn try_from(h: UncheckedArpHeader) -> Result<Self, String> {
+        let sizes = [
+            ("sender hardware", &h.sender_hw_address, h.hardware_size),
+            ("destination hardware", &h.destination_hw_address, h.hardware_size),
+            ("sender protocol", &h.sender_proto_address, h.protocol_size),
+            ("destination protocol", &h.destination_proto_address, h.protocol_size),
+        ];
+        for (kind, address, size) in sizes.iter() {
+            if address.len() != *size as usize {
+                return Err(format!("the {} address is {} bytes long, but the size is {}", kind, address.len(), size));
+            }
+        }
+        Ok(ArpHeader {
+            hardware_type: h.hardware_type,
+            protocol_type: h.protocol_type,
+            hardware_size: h.hardware_size,
+            protocol_size: h.protocol_size,
+            opcode: h.opcode,
+            sender_hw_address: h.sender_hw_address,
+            sender_proto_address: h.sender_proto_address,
+            destination_hw_address: h.destination_hw_address,
+            destination_proto_address: h.destination_proto_address,
+        })
+    }
 }
 
 impl ArpHeader {
-    pub fn new<T: Into<[u8; 4]>>(sender_mac: [u8; 6], sender_ip: T, destination_mac: [u8; 6], destination_ip: T) -> Self {
-        ArpHeader {
-            hardware_type: 1,
-            protocol_type: 0x0800,
-            hardware_size: 6,
-            protocol_size: 4,
+    /// an IPv4 over ethernet request
+    pub fn new<M: Into<MacAddr>, T: Into<[u8; 4]>>(sender_mac: M, sender_ip: T, destination_mac: M, destination_ip: T) -> Self {
+        Self::with_addresses(
+            HARDWARE_ETHERNET,
+            0x0800,
+            sender_mac.into().as_ref().to_vec(),
+            sender_ip.into().to_vec(),
+            destination_mac.into().as_ref().to_vec(),
+            destination_ip.into().to_vec(),
+        )
+    }
+
+    /// a request with any kind of addresses, e.g. the 20 byte hardware addresses of IPv4 over InfiniBand.
+    ///
+    /// Panics if the sender and destination addresses of the same kind are different lengths, or longer than 255 bytes
+    pub fn with_addresses(
+        hardware_type: u16,
+        protocol_type: u16,
+        sender_hw_address: Vec<u8>,
+        sender_proto_address: Vec<u8>,
+        destination_hw_address: Vec<u8>,
+        destination_proto_address: Vec<u8>,
+    ) -> Self {
+        let mut header = ArpHeader {
+            hardware_type,
+            protocol_type,
+            hardware_size: 0,
+            protocol_size: 0,
             opcode: REQUEST,
-            sender_mac: sender_mac,
-            sender_ip: sender_ip.into(),
-            destination_mac: destination_mac,
-            destination_ip: destination_ip.into(),
-        }
+            sender_hw_address: Vec::new(),
+            sender_proto_address: Vec::new(),
+            destination_hw_address: Vec::new(),
+            destination_proto_address: Vec::new(),
+        };
+        header.set_hw_addresses(sender_hw_address, destination_hw_address);
+        header.set_proto_addresses(sender_proto_address, destination_proto_address);
+        header
+    }
+
+    /// Sets both hardware addresses and the hardware size.
+    ///
+    /// Panics if they are different lengths, or longer than 255 bytes
+    pub fn set_hw_addresses(&mut self, sender: Vec<u8>, destination: Vec<u8>) {
+        self.hardware_size = address_size("hardware", &sender, &destination);
+        self.sender_hw_address = sender;
+        self.destination_hw_address = destination;
+    }
+
+    /// Sets both protocol addresses and the protocol size.
+    ///
+    /// Panics if they are different lengths, or longer than 255 bytes
+    pub fn set_proto_addresses(&mut self, sender: Vec<u8>, destination: Vec<u8>) {
+        self.protocol_size = address_size("protocol", &sender, &destination);
+        self.sender_proto_address = sender;
+        self.destination_proto_address = destination;
+    }
+
+    /// the sender hardware address as a MAC address, or `None` if it isn't 6 bytes long
+    pub fn get_sender_mac(&self) -> Option<MacAddr> {
+        as_mac(&self.sender_hw_address)
+    }
+
+    /// the target hardware address as a MAC address, or `None` if it isn't 6 bytes long
+    pub fn get_destination_mac(&self) -> Option<MacAddr> {
+        as_mac(&self.destination_hw_address)
+    }
+
+    /// the sender protocol address as an IPv4 address, or `None` if it isn't 4 bytes long
+    pub fn get_sender_ip(&self) -> Option<Ipv4Addr> {
+        as_ipv4(&self.sender_proto_address)
+    }
+
+    /// the target protocol address as an IPv4 address, or `None` if it isn't 4 bytes long
+    pub fn get_destination_ip(&self) -> Option<Ipv4Addr> {
+        as_ipv4(&self.destination_proto_address)
+    }
+
+    /// Panics if the hardware addresses aren't 6 bytes long
+    pub fn set_sender_mac<M: Into<MacAddr>>(&mut self, mac: M) {
+        let destination = self.destination_hw_address.clone();
+        self.set_hw_addresses(mac.into().as_ref().to_vec(), destination);
+    }
+
+    /// Panics if the hardware addresses aren't 6 bytes long
+    pub fn set_destination_mac<M: Into<MacAddr>>(&mut self, mac: M) {
+        let sender = self.sender_hw_address.clone();
+        self.set_hw_addresses(sender, mac.into().as_ref().to_vec());
+    }
+
+    /// Panics if the protocol addresses aren't 4 bytes long
+    pub fn set_sender_ip<T: Into<[u8; 4]>>(&mut self, ip: T) {
+        let destination = self.destination_proto_address.clone();
+        self.set_proto_addresses(ip.into().to_vec(), destination);
+    }
+
+    /// Panics if the protocol addresses aren't 4 bytes long
+    pub fn set_destination_ip<T: Into<[u8; 4]>>(&mut self, ip: T) {
+        let sender = self.sender_proto_address.clone();
+        self.set_proto_addresses(sender, ip.into().to_vec());
+    }
+
+    /// whether this is a RARP header rather than an ARP one, going by the opcode
+    pub fn is_reverse(&self) -> bool {
+        self.opcode == REVERSE_REQUEST || self.opcode == REVERSE_REPLY
+    }
+}
+
+fn address_size(kind: &str, sender: &[u8], destination: &[u8]) -> u8 {
+    assert_eq!(sender.len(), destination.len(), "the sender and destination {} addresses must be the same length", kind);
+    assert!(sender.len() <= 255, "{} addresses can't be longer than 255 bytes", kind);
+    sender.len() as u8
+}
+
+fn as_mac(address: &[u8]) -> Option<MacAddr> {
+    match *address {
+        [a, b, c, d, e, f] => Some(MacAddr::new(a, b, c, d, e, f)),
+        _ => None,
+    }
+}
+
+fn as_ipv4(address: &[u8]) -> Option<Ipv4Addr> {
+    match *address {
+        [a, b, c, d] => Some(Ipv4Addr::new(a, b, c, d)),
+        _ => None,
+    }
+}
+
+pub(crate) fn hw_address_string(address: &[u8]) -> String {
+    match as_mac(address) {
+        Some(mac) => mac.to_string(),
+        None => hex_string(address),
+    }
+}
+
+pub(crate) fn proto_address_string(address: &[u8]) -> String {
+    if let Some(ip) = as_ipv4(address) {
+        return ip.to_string();
+    }
+    if address.len() == 16 {
+        let mut octets = [0u8; 16];
+        octets.copy_from_slice(address);
+        return Ipv6Addr::from(octets).to_string();
+    }
+    hex_string(address)
+}
+
+fn hex_string(address: &[u8]) -> String {
+    address.iter().map(|b| format!("{:02x}", b)).collect::<Vec<String>>().join(":")
+}
+
+/// an IPv4 over ethernet request, with all addresses set to zero
+impl Default for ArpHeader {
+    fn default() -> Self {
+        Self::new(MacAddr::ZERO, [0; 4], MacAddr::ZERO, [0; 4])
+    }
+}
+
+impl fmt::Display for ArpHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.opcode {
+            REQUEST => String::from("request"),
+            REPLY => String::from("reply"),
+            REVERSE_REQUEST => String::from("reverse request"),
+            REVERSE_REPLY => String::from("reverse reply"),
+            n => n.to_string(),
+        };
+        write!(
+            f,
+            "{}Address Resolution Protocol ({}), Sender: {} ({}), Target: {} ({})",
+            if self.is_reverse() { "Reverse " } else { "" },
+            op,
+            hw_address_string(&self.sender_hw_address),
+            proto_address_string(&self.sender_proto_address),
+            hw_address_string(&self.destination_hw_address),
+            proto_address_string(&self.destination_proto_address)
+        )
     }
 }
 
 impl Header for ArpHeader {
-    fn make(self) -> PacketData {
-        let hwt_b = self.hardware_type.split_to_bytes();
-        let p_b = self.protocol_type.split_to_bytes();
-        let opcode_b = self.opcode.split_to_bytes();
-        let sender_mac: [u8; 6] = self.sender_mac.into();
-        let destination_mac: [u8; 6] = self.destination_mac.into();
-        vec![
-            hwt_b[0],
-            hwt_b[1],
-            p_b[0],
-            p_b[1],
-            self.hardware_size,
-            self.protocol_size,
-            opcode_b[0],
-            opcode_b[1],
-            sender_mac[0],
-            sender_mac[1],
-            sender_mac[2],
-            sender_mac[3],
-            sender_mac[4],
-            sender_mac[5],
-            self.sender_ip[0],
-            self.sender_ip[1],
-            self.sender_ip[2],
-            self.sender_ip[3],
-            destination_mac[0],
-            destination_mac[1],
-            destination_mac[2],
-            destination_mac[3],
-            destination_mac[4],
-            destination_mac[5],
-            self.destination_ip[0],
-            self.destination_ip[1],
-            self.destination_ip[2],
-            self.destination_ip[3],
-        ]
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u16(self.hardware_type);
+        buf.put_u16(self.protocol_type);
+        buf.put_u8(self.hardware_size);
+        buf.put_u8(self.protocol_size);
+        buf.put_u16(self.opcode);
+        buf.put_slice(&self.sender_hw_address);
+        buf.put_slice(&self.sender_proto_address);
+        buf.put_slice(&self.destination_hw_address);
+        buf.put_slice(&self.destination_proto_address);
     }
 
     fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
-        if raw_data.len() < Self::get_min_length().into() {
+        if raw_data.len() < 8 {
+            return Err(ParseError::InvalidLength);
+        }
+        let hardware_size = raw_data[4] as usize;
+        let protocol_size = raw_data[5] as usize;
+        if raw_data.len() < 8 + (hardware_size * 2) + (protocol_size * 2) {
             return Err(ParseError::InvalidLength);
         }
+        let address = |start: usize, size: usize| raw_data[start..(start + size)].to_vec();
+        let destination = 8 + hardware_size + protocol_size;
         Ok(Box::new(Self {
             hardware_type: ((raw_data[0] as u16) << 8) + raw_data[1] as u16,
             protocol_type: ((raw_data[2] as u16) << 8) + raw_data[3] as u16,
-            hardware_size: raw_data[4],
-            protocol_size: raw_data[5],
+            hardware_size: hardware_size as u8,
+            protocol_size: protocol_size as u8,
             opcode: ((raw_data[6] as u16) << 8) + raw_data[7] as u16,
-            sender_mac: [raw_data[8], raw_data[9], raw_data[10], raw_data[11], raw_data[12], raw_data[13]],
-            sender_ip: [raw_data[14], raw_data[15], raw_data[16], raw_data[17]],
-            destination_mac: [raw_data[18], raw_data[19], raw_data[20], raw_data[21], raw_data[22], raw_data[23]],
-            destination_ip: [raw_data[24], raw_data[25], raw_data[26], raw_data[27]],
+            sender_hw_address: address(8, hardware_size),
+            sender_proto_address: address(8 + hardware_size, protocol_size),
+            destination_hw_address: address(destination, hardware_size),
+            destination_proto_address: address(destination + hardware_size, protocol_size),
         }))
     }
 
@@ -94,15 +310,12 @@ impl Header for ArpHeader {
         Protocol::ARP
     }
 
-    fn get_length(&self) -> u8 {
-        8 + (self.hardware_size*2) + (self.protocol_size*2)
+    fn get_length(&self) -> usize {
+        8 + self.sender_hw_address.len() + self.sender_proto_address.len() + self.destination_hw_address.len() + self.destination_proto_address.len()
     }
 
-    fn get_min_length() -> u8 {
-        /* no one really uses anything other than the typical arp over Ipv4,
-         * using hardware length 6 (mac addr) and protocol length 4 (ipv4 addr),
-         * making the total length 18, so we just always return that here
-         */
-        28
+    fn get_min_length() -> usize {
+        // just the fixed fields, as the addresses can be any length. the usual IPv4 over ethernet header is 28 bytes
+        8
     }
 }
@@ -1,13 +1,18 @@
-use super::{Header, PacketData, Protocol, ParseError};
-use crate::AsBeBytes;
+use super::{Header, Protocol, ParseError};
+use crate::MacAddr;
+use bytes::BufMut;
+use std::fmt;
 
-#[derive(AddGetter, AddSetter)]
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EthernetHeader {
     #[get]
-    dst_mac: [u8; 6],
+    #[set]
+    dst_mac: MacAddr,
 
     #[get]
-    src_mac: [u8; 6],
+    #[set]
+    src_mac: MacAddr,
 
     #[get]
     #[set]
@@ -16,46 +21,43 @@ pub struct EthernetHeader {
 }
 
 impl EthernetHeader {
-    pub fn new(src_mac: [u8; 6], dst_mac: [u8; 6], eth_type: u16) -> Self {
+    pub fn new<M: Into<MacAddr>>(src_mac: M, dst_mac: M, eth_type: u16) -> Self {
         EthernetHeader {
-            dst_mac: dst_mac,
-            src_mac: src_mac,
-            eth_type: eth_type,
+            dst_mac: dst_mac.into(),
+            src_mac: src_mac.into(),
+            eth_type,
         }
     }
 }
 
+/// all zero source mac, broadcast destination mac and an ethertype of 0,
+/// which is filled in when another header is stacked on top of it with `/`
+impl Default for EthernetHeader {
+    fn default() -> Self {
+        Self::new(MacAddr::ZERO, MacAddr::BROADCAST, 0)
+    }
+}
+
+impl fmt::Display for EthernetHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ethernet II, Src: {}, Dst: {}, Type: 0x{:04x}", self.src_mac, self.dst_mac, self.eth_type)
+    }
+}
+
 impl Header for EthernetHeader {
-    #[allow(unused_variables)]
-    fn make(self) -> PacketData {
-        let tyb = self.eth_type.split_to_bytes();
-        let dst_mac: [u8; 6] = self.dst_mac.into();
-        let src_mac: [u8; 6] = self.src_mac.into();
-        vec![
-            dst_mac[0],
-            dst_mac[1],
-            dst_mac[2],
-            dst_mac[3],
-            dst_mac[4],
-            dst_mac[5],
-            src_mac[0],
-            src_mac[1],
-            src_mac[2],
-            src_mac[3],
-            src_mac[4],
-            src_mac[5],
-            tyb[0],
-            tyb[1],
-        ]
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
+        buf.put_slice(self.dst_mac.as_ref());
+        buf.put_slice(self.src_mac.as_ref());
+        buf.put_u16(self.eth_type);
     }
 
     fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
-        if raw_data.len() < Self::get_min_length().into() {
+        if raw_data.len() < Self::get_min_length() {
             return Err(ParseError::InvalidLength);
         }
         Ok(Box::new(Self {
-            dst_mac: [raw_data[0], raw_data[1], raw_data[2], raw_data[3], raw_data[4], raw_data[5]],
-            src_mac: [raw_data[6], raw_data[7], raw_data[8], raw_data[9], raw_data[10], raw_data[11]],
+            dst_mac: MacAddr::new(raw_data[0], raw_data[1], raw_data[2], raw_data[3], raw_data[4], raw_data[5]),
+            src_mac: MacAddr::new(raw_data[6], raw_data[7], raw_data[8], raw_data[9], raw_data[10], raw_data[11]),
             eth_type: ((raw_data[12] as u16) << 8) + raw_data[13] as u16,
         }))
     }
@@ -64,11 +66,11 @@ impl Header for EthernetHeader {
         Protocol::ETH
     }
 
-    fn get_length(&self) -> u8 {
+    fn get_length(&self) -> usize {
         14
     }
 
-    fn get_min_length() -> u8 {
+    fn get_min_length() -> usize {
         14
     }
 }
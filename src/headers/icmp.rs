@@ -1,7 +1,10 @@
 use crate::{AsBeBytes, checksum};
-use super::{Header, PacketData, Protocol, ParseError};
+use super::{Header, Protocol, ParseError};
+use bytes::BufMut;
+use std::fmt;
 
-#[derive(AddGetter, AddSetter)]
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[get]
 #[set]
 pub struct IcmpHeader {
@@ -22,29 +25,46 @@ impl IcmpHeader {
     }
 }
 
+/// an echo request with an identifier and sequence number of 0
+impl Default for IcmpHeader {
+    fn default() -> Self {
+        Self::new(8, 0, 0, 0)
+    }
+}
+
+impl fmt::Display for IcmpHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Internet Control Message Protocol, Type: {}, Code: {}, Id: {}, Seq: {}",
+            self.msg_type, self.code, self.identifier, self.sequence_number
+        )
+    }
+}
+
 impl Header for IcmpHeader {
-    fn make(self) -> PacketData {
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
         // some hosts dont seem to reply when sequence number is 0, even though in RFC 792 on page 14 it says it can be zero
         let ident_bytes = self.identifier.split_to_bytes();
         let sn_bytes = self.sequence_number.split_to_bytes();
-        let mut packet: Vec<u8> = vec![
+        let mut packet: [u8; 8] = [
             self.msg_type,
             self.code,
             0,
             0,
             ident_bytes[0],
             ident_bytes[1],
-            sn_bytes[1],
             sn_bytes[0],
+            sn_bytes[1],
         ];
         let checksum = checksum(&packet, 1).split_to_bytes();
         packet[2] = checksum[0];
         packet[3] = checksum[1];
-        packet
+        buf.put_slice(&packet);
     }
 
     fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
-        if raw_data.len() < Self::get_min_length().into() {
+        if raw_data.len() < Self::get_min_length() {
             return Err(ParseError::InvalidLength);
         }
         Ok(Box::new(Self {
@@ -60,11 +80,11 @@ impl Header for IcmpHeader {
     }
 
 
-    fn get_length(&self) -> u8 {
+    fn get_length(&self) -> usize {
         8
     }
 
-    fn get_min_length() -> u8 {
+    fn get_min_length() -> usize {
         8
     }
 }
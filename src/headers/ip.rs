@@ -1,8 +1,27 @@
-use super::{Header, PacketData, ParseError, Protocol};
-use crate::{checksum, protocol_numbers, AsBeBytes};
+use super::{Header, ParseError, Protocol};
+use bytes::BufMut;
+use crate::{finalize_checksum, protocol_numbers, sum_be_words, AsBeBytes};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::fmt;
 use std::net::IpAddr;
 
-#[derive(AddGetter, AddSetter)]
+/// the IPv4 "don't fragment" flag
+pub const DONT_FRAGMENT: u8 = 0b010;
+/// the IPv4 "more fragments" flag
+pub const MORE_FRAGMENTS: u8 = 0b001;
+
+#[cfg(feature = "serde")]
+fn default_flags() -> u8 {
+    DONT_FRAGMENT
+}
+
+/// An IPv4 or IPv6 header, going by the version of its addresses.
+///
+/// Deserialising checks that both addresses are the same IP version and that the flow label fits in 20 bits,
+/// as a header which breaks either can't be written
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(try_from = "UncheckedIpHeader"))]
 pub struct IpHeader {
     #[get]
     #[set]
@@ -13,6 +32,14 @@ pub struct IpHeader {
     #[get]
     #[set]
     identification: u16,
+    /// the 3 IPv4 flag bits (reserved, don't fragment, more fragments), in the low bits of the byte
+    #[get]
+    #[set]
+    flags: u8,
+    /// the IPv4 fragment offset, in units of 8 bytes
+    #[get]
+    #[set]
+    fragment_offset: u16,
     #[get]
     #[set]
     ttl: u8,
@@ -23,6 +50,62 @@ pub struct IpHeader {
     #[get]
     #[set]
     dst_ip: IpAddr,
+    /// the IPv4 options, which are padded with zeros to a multiple of 4 bytes when the header is written
+    #[get]
+    #[set]
+    options: Vec<u8>,
+    /// the 20 bit IPv6 flow label
+    #[get]
+    #[set]
+    flow_label: u32,
+}
+
+/// the fields of an `IpHeader` as they are deserialised, before they are checked
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedIpHeader {
+    tos: u8,
+    packet_len: u16,
+    identification: u16,
+    #[serde(default = "default_flags")]
+    flags: u8,
+    #[serde(default)]
+    fragment_offset: u16,
+    ttl: u8,
+    next_protocol: u8,
+    src_ip: IpAddr,
+    dst_ip: IpAddr,
+    #[serde(default)]
+    options: Vec<u8>,
+    #[serde(default)]
+    flow_label: u32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedIpHeader> for IpHeader {
+    type Error = String;
+
+    fn try_from(h: UncheckedIpHeader) -> Result<Self, String> {
+        if h.src_ip.is_ipv4() != h.dst_ip.is_ipv4() {
+            return Err(format!("the addresses {} and {} are different IP versions", h.src_ip, h.dst_ip));
+        }
+        if h.flow_label >= 1 << 20 {
+            return Err(format!("the flow label {} doesn't fit in 20 bits", h.flow_label));
+        }
+        Ok(IpHeader {
+            tos: h.tos,
+            packet_len: h.packet_len,
+            identification: h.identification,
+            flags: h.flags,
+            fragment_offset: h.fragment_offset,
+            ttl: h.ttl,
+            next_protocol: h.next_protocol,
+            src_ip: h.src_ip,
+            dst_ip: h.dst_ip,
+            options: h.options,
+            flow_label: h.flow_label,
+        })
+    }
 }
 
 impl IpHeader {
@@ -37,19 +120,29 @@ impl IpHeader {
             (IpAddr::V6(_), IpAddr::V6(_)) => { /* OK */ }
             _ => panic!("Invalid IP versions, must not mix IPv4 and IPv6"),
         };
+        // ICMP carried by IPv6 is ICMPv6, which has its own protocol number
+        let next_protocol = match next_proto {
+            Protocol::ICMP if src_ip.is_ipv6() => protocol_numbers::IPPROTO_ICMPV6,
+            p => p.protocol_number(),
+        };
         IpHeader {
             tos: 0,
             packet_len: 0,
             identification: 0,
+            flags: DONT_FRAGMENT,
+            fragment_offset: 0,
             ttl: 64,
-            next_protocol: next_proto.protocol_number(),
-            src_ip: src_ip,
-            dst_ip: dst_ip,
+            next_protocol,
+            src_ip,
+            dst_ip,
+            options: Vec::new(),
+            flow_label: 0,
         }
     }
 
     pub fn set_next_protocol(&mut self, proto: Protocol) -> &mut Self {
         self.next_protocol = match proto {
+            Protocol::ICMP if self.src_ip.is_ipv6() => protocol_numbers::IPPROTO_ICMPV6,
             Protocol::ICMP => protocol_numbers::IPPROTO_ICMP,
             Protocol::TCP => protocol_numbers::IPPROTO_TCP,
             Protocol::UDP => protocol_numbers::IPPROTO_UDP,
@@ -57,12 +150,49 @@ impl IpHeader {
         };
         self
     }
+
+    /// sets the protocol number of the next header directly, for protocols which don't have a `Protocol` variant
+    pub fn set_next_protocol_number(&mut self, number: u8) -> &mut Self {
+        self.next_protocol = number;
+        self
+    }
+}
+
+/// an IPv4 header from and to 127.0.0.1, with a protocol of 0,
+/// which is filled in when another header is stacked on top of it with `/`
+impl Default for IpHeader {
+    fn default() -> Self {
+        Self {
+            tos: 0,
+            packet_len: 0,
+            identification: 0,
+            flags: DONT_FRAGMENT,
+            fragment_offset: 0,
+            ttl: 64,
+            next_protocol: 0,
+            src_ip: [127, 0, 0, 1].into(),
+            dst_ip: [127, 0, 0, 1].into(),
+            options: Vec::new(),
+            flow_label: 0,
+        }
+    }
+}
+
+impl fmt::Display for IpHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = if self.src_ip.is_ipv4() { 4 } else { 6 };
+        write!(
+            f,
+            "Internet Protocol Version {}, Src: {}, Dst: {}, Protocol: {}, TTL: {}",
+            version, self.src_ip, self.dst_ip, self.next_protocol, self.ttl
+        )
+    }
 }
 
 impl Header for IpHeader {
     #[cfg(target_endian = "little")]
     /// needs testing on a big endian machine
-    fn make(self) -> PacketData {
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
         use IpAddr::{V4, V6};
 
         match (&self.src_ip, &self.dst_ip) {
@@ -71,16 +201,17 @@ impl Header for IpHeader {
 
                 let length_bytes = self.packet_len.split_to_bytes();
                 let ident_bytes = self.identification.split_to_bytes();
+                let fragment_bytes = (((self.flags as u16) << 13) | (self.fragment_offset & 0x1fff)).split_to_bytes();
 
-                let mut packet = vec![
-                    0b0100_0101,      // set version to 4 and header length to 5 ("20 bytes")
+                let mut packet: [u8; 20] = [
+                    0x40 | (self.get_length() / 4) as u8, // set version to 4 and the header length in 32 bit words
                     self.tos,        // service type is just left as routine (0)
                     length_bytes[0], //total length of the packet in bytes
                     length_bytes[1], //total length of the packet in bytes
                     ident_bytes[0],  // Identification
                     ident_bytes[1],  // Identification
-                    0b0100_0000,
-                    0,                  // flags and fragment offset
+                    fragment_bytes[0],
+                    fragment_bytes[1],  // flags and fragment offset
                     self.ttl,           // ttl
                     self.next_protocol, // next level protocol
                     0,                  // checksum
@@ -94,18 +225,21 @@ impl Header for IpHeader {
                     dst_ip[2],
                     dst_ip[3],
                 ];
-                let checksum = checksum(&packet, 5).split_to_bytes();
+                // the padding is all zeros, so it doesn't change the checksum
+                let checksum = finalize_checksum(sum_be_words(&packet, 5) + sum_be_words(&self.options, 0)).split_to_bytes();
                 packet[10] = checksum[0];
                 packet[11] = checksum[1];
-                packet
+                buf.put_slice(&packet);
+                buf.put_slice(&self.options);
+                buf.put_bytes(0, self.get_length() - 20 - self.options.len());
             }
             (&V6(src_ip), &V6(dst_ip)) => {
                 let (src_ip, dst_ip) = (src_ip.octets(), dst_ip.octets());
 
                 // based on [RFC8200](https://tools.ietf.org/html/rfc8200#page-6)
-                let traffic_class: u8 = 0;
+                let traffic_class: u8 = self.tos;
                 // 20bit
-                let flow_label: u32 = 0;
+                let flow_label: u32 = self.flow_label;
                 assert!(
                     flow_label < 2u32.pow(20),
                     "flow label must not exceed 20bit, was {:?}",
@@ -115,40 +249,66 @@ impl Header for IpHeader {
                 // Lenght of payload + IPv6 extension headers (todo)
                 let payload_len: u16 = self.packet_len;
 
-                let mut packet = vec![
+                buf.put_slice(&[
                     (6u8 << 4/* version */) + (traffic_class >> 4),
-                    (traffic_class << 4) + (flow_label >> (32 - 20)) as u8,
+                    (traffic_class << 4) + (flow_label >> 16) as u8,
                     (flow_label >> 8) as u8,
                     flow_label as u8,
                     (payload_len >> 8) as u8,
                     payload_len as u8,
                     self.next_protocol,
                     self.ttl, // hop limit
-                ];
-
-                packet.extend_from_slice(&src_ip);
-                packet.extend_from_slice(&dst_ip);
-
-                packet
+                ]);
+                buf.put_slice(&src_ip);
+                buf.put_slice(&dst_ip);
             }
             _ => unreachable!(),
         }
     }
 
     fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
-        if raw_data.len() < Self::get_min_length().into() {
+        if raw_data.len() < Self::get_min_length() {
+            return Err(ParseError::InvalidLength);
+        }
+        if raw_data[0] >> 4 == 6 {
+            if raw_data.len() < 40 {
+                return Err(ParseError::InvalidLength);
+            }
+            let mut src_ip = [0u8; 16];
+            let mut dst_ip = [0u8; 16];
+            src_ip.copy_from_slice(&raw_data[8..24]);
+            dst_ip.copy_from_slice(&raw_data[24..40]);
+            return Ok(Box::new(Self {
+                tos: (raw_data[0] << 4) | (raw_data[1] >> 4),
+                packet_len: ((raw_data[4] as u16) << 8) + raw_data[5] as u16,
+                identification: 0,
+                flags: DONT_FRAGMENT,
+                fragment_offset: 0,
+                ttl: raw_data[7],
+                next_protocol: raw_data[6],
+                src_ip: src_ip.into(),
+                dst_ip: dst_ip.into(),
+                options: Vec::new(),
+                flow_label: u32::from_be_bytes([0, raw_data[1] & 0x0f, raw_data[2], raw_data[3]]),
+            }));
+        }
+        // a header length too short to be valid is treated as a header without options
+        let header_len = ((raw_data[0] & 0x0f) as usize * 4).max(20);
+        if raw_data.len() < header_len {
             return Err(ParseError::InvalidLength);
         }
         Ok(Box::new(Self {
             tos: raw_data[1],
             packet_len: ((raw_data[2] as u16) << 8) + raw_data[3] as u16,
             identification: ((raw_data[4] as u16) << 8) + raw_data[5] as u16,
+            flags: raw_data[6] >> 5,
+            fragment_offset: (((raw_data[6] & 0x1f) as u16) << 8) + raw_data[7] as u16,
             ttl: raw_data[8],
             next_protocol: raw_data[9],
-            // TODO handle v6
             src_ip: [raw_data[12], raw_data[13], raw_data[14], raw_data[15]].into(),
-            // TODO handle v6
             dst_ip: [raw_data[16], raw_data[17], raw_data[18], raw_data[19]].into(),
+            options: raw_data[20..header_len].to_vec(),
+            flow_label: 0,
         }))
     }
 
@@ -156,15 +316,14 @@ impl Header for IpHeader {
         Protocol::IP
     }
 
-    fn get_length(&self) -> u8 {
-        // TODO this should reflect the actual packet, not the min length
+    fn get_length(&self) -> usize {
         match self.src_ip {
-            IpAddr::V4(_) => 20, 
-            IpAddr::V6(_) => 40, 
+            IpAddr::V4(_) => 20 + self.options.len().div_ceil(4) * 4,
+            IpAddr::V6(_) => 40,
         }
     }
 
-    fn get_min_length() -> u8 {
+    fn get_min_length() -> usize {
         20
     }
 }
@@ -0,0 +1,172 @@
+use super::{Header, Protocol, ParseError};
+use bytes::BufMut;
+use std::fmt;
+
+/// the SAP of SNAP, which is followed by a `SnapHeader`
+pub const SAP_SNAP: u8 = 0xaa;
+/// the SAP of the spanning tree protocol
+pub const SAP_STP: u8 = 0x42;
+/// the SAP of IPX
+pub const SAP_IPX: u8 = 0xe0;
+/// the SAP of NetBIOS
+pub const SAP_NETBIOS: u8 = 0xf0;
+/// the control field of an unnumbered information frame, which is what almost everything uses
+pub const LLC_UI: u16 = 0x03;
+
+/// the SNAP OUI which says the protocol id is an ethertype (RFC 1042)
+pub const OUI_ETHERTYPE: [u8; 3] = [0x00, 0x00, 0x00];
+/// the SNAP OUI of Cisco, used by CDP (protocol id 0x2000) among others
+pub const OUI_CISCO: [u8; 3] = [0x00, 0x00, 0x0c];
+
+/// An IEEE 802.2 logical link control header, which follows an ethernet header whose type field is a length
+/// (see `ethertype_numbers::is_length`) rather than an ethertype.
+///
+/// The control field is 1 byte long for unnumbered frames, whose low 2 bits are both set, and 2 bytes long otherwise.
+/// The first byte on the wire is the low byte of `control`
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LlcHeader {
+    #[get]
+    #[set]
+    dsap: u8,
+
+    #[get]
+    #[set]
+    ssap: u8,
+
+    #[get]
+    #[set]
+    control: u16,
+}
+
+impl LlcHeader {
+    pub fn new(dsap: u8, ssap: u8, control: u16) -> Self {
+        LlcHeader { dsap, ssap, control }
+    }
+
+    /// whether the control field is the 1 byte form
+    pub fn is_unnumbered(&self) -> bool {
+        self.control & 0x03 == 0x03
+    }
+}
+
+/// the header of a SNAP frame, which is followed by a `SnapHeader`
+impl Default for LlcHeader {
+    fn default() -> Self {
+        Self::new(SAP_SNAP, SAP_SNAP, LLC_UI)
+    }
+}
+
+impl fmt::Display for LlcHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Logical-Link Control, DSAP: 0x{:02x}, SSAP: 0x{:02x}, Control: 0x{:02x}", self.dsap, self.ssap, self.control)
+    }
+}
+
+impl Header for LlcHeader {
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u8(self.dsap);
+        buf.put_u8(self.ssap);
+        if self.is_unnumbered() {
+            buf.put_u8(self.control as u8);
+        } else {
+            buf.put_u16_le(self.control);
+        }
+    }
+
+    fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
+        if raw_data.len() < Self::get_min_length() {
+            return Err(ParseError::InvalidLength);
+        }
+        let control = if raw_data[2] & 0x03 == 0x03 {
+            raw_data[2] as u16
+        } else if raw_data.len() < 4 {
+            return Err(ParseError::InvalidLength);
+        } else {
+            u16::from_le_bytes([raw_data[2], raw_data[3]])
+        };
+        Ok(Box::new(Self {
+            dsap: raw_data[0],
+            ssap: raw_data[1],
+            control,
+        }))
+    }
+
+    fn get_proto(&self) -> Protocol {
+        Protocol::LLC
+    }
+
+    fn get_length(&self) -> usize {
+        if self.is_unnumbered() { 3 } else { 4 }
+    }
+
+    fn get_min_length() -> usize {
+        3
+    }
+}
+
+/// A SNAP header, which follows an `LlcHeader` with both SAPs set to `SAP_SNAP`. With `OUI_ETHERTYPE` the protocol id is an ethertype,
+/// otherwise it's a protocol of the organisation the OUI belongs to
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapHeader {
+    #[get]
+    #[set]
+    oui: [u8; 3],
+
+    #[get]
+    #[set]
+    protocol_id: u16,
+}
+
+impl SnapHeader {
+    pub fn new(oui: [u8; 3], protocol_id: u16) -> Self {
+        SnapHeader { oui, protocol_id }
+    }
+}
+
+/// an ethertype SNAP header with a protocol id of 0, which is filled in when another header is stacked on top of it with `/`
+impl Default for SnapHeader {
+    fn default() -> Self {
+        Self::new(OUI_ETHERTYPE, 0)
+    }
+}
+
+impl fmt::Display for SnapHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SubNetwork Access Protocol, OUI: {:02x}:{:02x}:{:02x}, PID: 0x{:04x}",
+            self.oui[0], self.oui[1], self.oui[2], self.protocol_id
+        )
+    }
+}
+
+impl Header for SnapHeader {
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
+        buf.put_slice(&self.oui);
+        buf.put_u16(self.protocol_id);
+    }
+
+    fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
+        if raw_data.len() < Self::get_min_length() {
+            return Err(ParseError::InvalidLength);
+        }
+        Ok(Box::new(Self {
+            oui: [raw_data[0], raw_data[1], raw_data[2]],
+            protocol_id: ((raw_data[3] as u16) << 8) + raw_data[4] as u16,
+        }))
+    }
+
+    fn get_proto(&self) -> Protocol {
+        Protocol::SNAP
+    }
+
+    fn get_length(&self) -> usize {
+        5
+    }
+
+    fn get_min_length() -> usize {
+        5
+    }
+}
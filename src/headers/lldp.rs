@@ -0,0 +1,422 @@
+use super::{Header, Protocol, ParseError};
+use crate::MacAddr;
+use bytes::BufMut;
+use std::convert::TryFrom;
+use std::fmt;
+use std::net::IpAddr;
+
+/// the nearest bridge group address, which LLDP frames are sent to and which bridges don't forward
+pub const LLDP_MULTICAST: MacAddr = MacAddr::new(0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e);
+
+pub const TLV_END: u8 = 0;
+pub const TLV_CHASSIS_ID: u8 = 1;
+pub const TLV_PORT_ID: u8 = 2;
+pub const TLV_TTL: u8 = 3;
+pub const TLV_PORT_DESCRIPTION: u8 = 4;
+pub const TLV_SYSTEM_NAME: u8 = 5;
+pub const TLV_SYSTEM_DESCRIPTION: u8 = 6;
+pub const TLV_CAPABILITIES: u8 = 7;
+pub const TLV_MANAGEMENT_ADDRESS: u8 = 8;
+pub const TLV_ORGANIZATIONALLY_SPECIFIC: u8 = 127;
+
+pub const CHASSIS_ID_CHASSIS_COMPONENT: u8 = 1;
+pub const CHASSIS_ID_INTERFACE_ALIAS: u8 = 2;
+pub const CHASSIS_ID_PORT_COMPONENT: u8 = 3;
+pub const CHASSIS_ID_MAC_ADDRESS: u8 = 4;
+pub const CHASSIS_ID_NETWORK_ADDRESS: u8 = 5;
+pub const CHASSIS_ID_INTERFACE_NAME: u8 = 6;
+pub const CHASSIS_ID_LOCAL: u8 = 7;
+
+pub const PORT_ID_INTERFACE_ALIAS: u8 = 1;
+pub const PORT_ID_PORT_COMPONENT: u8 = 2;
+pub const PORT_ID_MAC_ADDRESS: u8 = 3;
+pub const PORT_ID_NETWORK_ADDRESS: u8 = 4;
+pub const PORT_ID_INTERFACE_NAME: u8 = 5;
+pub const PORT_ID_AGENT_CIRCUIT_ID: u8 = 6;
+pub const PORT_ID_LOCAL: u8 = 7;
+
+/// the system capabilities bits, for both the supported and enabled capabilities
+pub const CAPABILITY_OTHER: u16 = 0x0001;
+pub const CAPABILITY_REPEATER: u16 = 0x0002;
+pub const CAPABILITY_BRIDGE: u16 = 0x0004;
+pub const CAPABILITY_WLAN_ACCESS_POINT: u16 = 0x0008;
+pub const CAPABILITY_ROUTER: u16 = 0x0010;
+pub const CAPABILITY_TELEPHONE: u16 = 0x0020;
+pub const CAPABILITY_DOCSIS: u16 = 0x0040;
+pub const CAPABILITY_STATION: u16 = 0x0080;
+
+/// the IANA address families used as management address subtypes
+pub const ADDRESS_FAMILY_IPV4: u8 = 1;
+pub const ADDRESS_FAMILY_IPV6: u8 = 2;
+pub const ADDRESS_FAMILY_802: u8 = 6;
+
+/// the management address interface numbering subtypes
+pub const INTERFACE_NUMBERING_UNKNOWN: u8 = 1;
+pub const INTERFACE_NUMBERING_IFINDEX: u8 = 2;
+pub const INTERFACE_NUMBERING_SYSTEM_PORT: u8 = 3;
+
+/// the OUIs of the IEEE 802.1 and 802.3 organizationally specific TLVs
+pub const OUI_IEEE_8021: [u8; 3] = [0x00, 0x80, 0xc2];
+pub const OUI_IEEE_8023: [u8; 3] = [0x00, 0x12, 0x0f];
+
+pub const IEEE_8021_PORT_VLAN_ID: u8 = 1;
+pub const IEEE_8021_VLAN_NAME: u8 = 3;
+pub const IEEE_8023_MAC_PHY_CONFIG: u8 = 1;
+pub const IEEE_8023_LINK_AGGREGATION: u8 = 3;
+pub const IEEE_8023_MAX_FRAME_SIZE: u8 = 4;
+
+/// One LLDP TLV.
+///
+/// Parsing only gives a typed variant when the value is laid out the way that TLV should be, and any text is valid UTF-8;
+/// anything else is kept byte for byte as `OrganizationallySpecific` or `Unknown`, so a parsed header always writes out as it was captured.
+/// A TLV's length field is 9 bits, so values longer than 511 bytes don't fit
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LldpTlv {
+    /// the end of the LLDPDU
+    End,
+    /// the id of the sending device, e.g. a MAC address with `CHASSIS_ID_MAC_ADDRESS`
+    ChassisId { subtype: u8, id: Vec<u8> },
+    /// the id of the sending port, e.g. an interface name with `PORT_ID_INTERFACE_NAME`
+    PortId { subtype: u8, id: Vec<u8> },
+    /// how many seconds the information is valid for. 0 tells the receivers to forget it
+    Ttl(u16),
+    PortDescription(String),
+    SystemName(String),
+    SystemDescription(String),
+    /// the `CAPABILITY_` bits the system supports, and which of them are enabled
+    Capabilities { system: u16, enabled: u16 },
+    ManagementAddress { address_subtype: u8, address: Vec<u8>, interface_subtype: u8, interface_number: u32, oid: Vec<u8> },
+    /// 802.1: the VLAN id of untagged frames on the port
+    PortVlanId(u16),
+    /// 802.1: the name of a VLAN the port is in
+    VlanName { vlan_id: u16, name: String },
+    /// 802.3: the autonegotiation support and status bits, the advertised capabilities and the operational MAU type
+    MacPhyConfig { autonegotiation: u8, advertised: u16, mau_type: u16 },
+    /// 802.3: the link aggregation status bits, and the id of the aggregated port
+    LinkAggregation { status: u8, port_id: u32 },
+    /// 802.3: the largest frame the port can send and receive
+    MaxFrameSize(u16),
+    /// any other organizationally specific TLV
+    OrganizationallySpecific { oui: [u8; 3], subtype: u8, info: Vec<u8> },
+    /// a TLV of any other type
+    Unknown { tlv_type: u8, value: Vec<u8> },
+}
+
+impl LldpTlv {
+    /// a chassis id which is a MAC address
+    pub fn chassis_mac<M: Into<MacAddr>>(mac: M) -> Self {
+        LldpTlv::ChassisId { subtype: CHASSIS_ID_MAC_ADDRESS, id: mac.into().as_ref().to_vec() }
+    }
+
+    /// a port id which is an interface name
+    pub fn port_name(name: &str) -> Self {
+        LldpTlv::PortId { subtype: PORT_ID_INTERFACE_NAME, id: name.as_bytes().to_vec() }
+    }
+
+    /// an IPv4 or IPv6 management address on the interface with the given ifIndex
+    pub fn management_ip<A: Into<IpAddr>>(address: A, if_index: u32) -> Self {
+        let (address_subtype, address) = match address.into() {
+            IpAddr::V4(ip) => (ADDRESS_FAMILY_IPV4, ip.octets().to_vec()),
+            IpAddr::V6(ip) => (ADDRESS_FAMILY_IPV6, ip.octets().to_vec()),
+        };
+        LldpTlv::ManagementAddress { address_subtype, address, interface_subtype: INTERFACE_NUMBERING_IFINDEX, interface_number: if_index, oid: Vec::new() }
+    }
+
+    /// the type field of the TLV
+    pub fn tlv_type(&self) -> u8 {
+        match self {
+            LldpTlv::End => TLV_END,
+            LldpTlv::ChassisId { .. } => TLV_CHASSIS_ID,
+            LldpTlv::PortId { .. } => TLV_PORT_ID,
+            LldpTlv::Ttl(_) => TLV_TTL,
+            LldpTlv::PortDescription(_) => TLV_PORT_DESCRIPTION,
+            LldpTlv::SystemName(_) => TLV_SYSTEM_NAME,
+            LldpTlv::SystemDescription(_) => TLV_SYSTEM_DESCRIPTION,
+            LldpTlv::Capabilities { .. } => TLV_CAPABILITIES,
+            LldpTlv::ManagementAddress { .. } => TLV_MANAGEMENT_ADDRESS,
+            LldpTlv::PortVlanId(_)
+            | LldpTlv::VlanName { .. }
+            | LldpTlv::MacPhyConfig { .. }
+            | LldpTlv::LinkAggregation { .. }
+            | LldpTlv::MaxFrameSize(_)
+            | LldpTlv::OrganizationallySpecific { .. } => TLV_ORGANIZATIONALLY_SPECIFIC,
+            LldpTlv::Unknown { tlv_type, .. } => *tlv_type,
+        }
+    }
+
+    /// the value of the TLV as it is written, after the 2 byte type and length
+    pub fn value(&self) -> Vec<u8> {
+        let org = |oui: [u8; 3], subtype: u8, info: &[u8]| [&oui[..], &[subtype], info].concat();
+        match self {
+            LldpTlv::End => Vec::new(),
+            LldpTlv::ChassisId { subtype, id } | LldpTlv::PortId { subtype, id } => [&[*subtype][..], id].concat(),
+            LldpTlv::Ttl(ttl) => ttl.to_be_bytes().to_vec(),
+            LldpTlv::PortDescription(s) | LldpTlv::SystemName(s) | LldpTlv::SystemDescription(s) => s.as_bytes().to_vec(),
+            LldpTlv::Capabilities { system, enabled } => [system.to_be_bytes(), enabled.to_be_bytes()].concat(),
+            LldpTlv::ManagementAddress { address_subtype, address, interface_subtype, interface_number, oid } => {
+                let mut value = vec![address.len() as u8 + 1, *address_subtype];
+                value.extend_from_slice(address);
+                value.push(*interface_subtype);
+                value.extend_from_slice(&interface_number.to_be_bytes());
+                value.push(oid.len() as u8);
+                value.extend_from_slice(oid);
+                value
+            }
+            LldpTlv::PortVlanId(vlan_id) => org(OUI_IEEE_8021, IEEE_8021_PORT_VLAN_ID, &vlan_id.to_be_bytes()),
+            LldpTlv::VlanName { vlan_id, name } => {
+                let info = [&vlan_id.to_be_bytes()[..], &[name.len() as u8], name.as_bytes()].concat();
+                org(OUI_IEEE_8021, IEEE_8021_VLAN_NAME, &info)
+            }
+            LldpTlv::MacPhyConfig { autonegotiation, advertised, mau_type } => {
+                let info = [&[*autonegotiation][..], &advertised.to_be_bytes(), &mau_type.to_be_bytes()].concat();
+                org(OUI_IEEE_8023, IEEE_8023_MAC_PHY_CONFIG, &info)
+            }
+            LldpTlv::LinkAggregation { status, port_id } => {
+                org(OUI_IEEE_8023, IEEE_8023_LINK_AGGREGATION, &[&[*status][..], &port_id.to_be_bytes()].concat())
+            }
+            LldpTlv::MaxFrameSize(size) => org(OUI_IEEE_8023, IEEE_8023_MAX_FRAME_SIZE, &size.to_be_bytes()),
+            LldpTlv::OrganizationallySpecific { oui, subtype, info } => org(*oui, *subtype, info),
+            LldpTlv::Unknown { value, .. } => value.clone(),
+        }
+    }
+
+    /// the length of the TLV on the wire, including the type and length
+    pub fn get_length(&self) -> usize {
+        2 + self.value().len()
+    }
+
+    /// parses the TLV at the start of `raw_data`, returning it and its length on the wire
+    pub(crate) fn parse(raw_data: &[u8]) -> Result<(Self, usize), ParseError> {
+        if raw_data.len() < 2 {
+            return Err(ParseError::InvalidLength);
+        }
+        let tlv_type = raw_data[0] >> 1;
+        let length = (((raw_data[0] & 0x01) as usize) << 8) | raw_data[1] as usize;
+        let value = raw_data.get(2..(2 + length)).ok_or(ParseError::InvalidLength)?;
+        let u16_at = |position: usize| u16::from_be_bytes([value[position], value[position + 1]]);
+        let text = || String::from_utf8(value.to_vec()).ok();
+        let typed = match (tlv_type, length) {
+            (TLV_END, 0) => Some(LldpTlv::End),
+            (TLV_CHASSIS_ID, 2..=256) => Some(LldpTlv::ChassisId { subtype: value[0], id: value[1..].to_vec() }),
+            (TLV_PORT_ID, 2..=256) => Some(LldpTlv::PortId { subtype: value[0], id: value[1..].to_vec() }),
+            (TLV_TTL, 2) => Some(LldpTlv::Ttl(u16_at(0))),
+            (TLV_PORT_DESCRIPTION, _) => text().map(LldpTlv::PortDescription),
+            (TLV_SYSTEM_NAME, _) => text().map(LldpTlv::SystemName),
+            (TLV_SYSTEM_DESCRIPTION, _) => text().map(LldpTlv::SystemDescription),
+            (TLV_CAPABILITIES, 4) => Some(LldpTlv::Capabilities { system: u16_at(0), enabled: u16_at(2) }),
+            (TLV_MANAGEMENT_ADDRESS, _) => parse_management_address(value),
+            (TLV_ORGANIZATIONALLY_SPECIFIC, 4..=511) => Some(parse_organizationally_specific(value)),
+            _ => None,
+        };
+        let tlv = typed.unwrap_or_else(|| LldpTlv::Unknown { tlv_type, value: value.to_vec() });
+        Ok((tlv, 2 + length))
+    }
+}
+
+fn parse_management_address(value: &[u8]) -> Option<LldpTlv> {
+    let address_len = (*value.first()? as usize).checked_sub(1)?;
+    let address = value.get(2..(2 + address_len))?;
+    let rest = &value[(2 + address_len)..];
+    let oid_len = *rest.get(5)? as usize;
+    if rest.len() != 6 + oid_len {
+        return None;
+    }
+    Some(LldpTlv::ManagementAddress {
+        address_subtype: value[1],
+        address: address.to_vec(),
+        interface_subtype: rest[0],
+        interface_number: u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]),
+        oid: rest[6..].to_vec(),
+    })
+}
+
+fn parse_organizationally_specific(value: &[u8]) -> LldpTlv {
+    let (oui, subtype, info) = ([value[0], value[1], value[2]], value[3], &value[4..]);
+    let u16_at = |position: usize| u16::from_be_bytes([info[position], info[position + 1]]);
+    let typed = match (oui, subtype, info.len()) {
+        (OUI_IEEE_8021, IEEE_8021_PORT_VLAN_ID, 2) => Some(LldpTlv::PortVlanId(u16_at(0))),
+        (OUI_IEEE_8021, IEEE_8021_VLAN_NAME, n) if n >= 3 && info[2] as usize == n - 3 => {
+            String::from_utf8(info[3..].to_vec()).ok().map(|name| LldpTlv::VlanName { vlan_id: u16_at(0), name })
+        }
+        (OUI_IEEE_8023, IEEE_8023_MAC_PHY_CONFIG, 5) => {
+            Some(LldpTlv::MacPhyConfig { autonegotiation: info[0], advertised: u16_at(1), mau_type: u16_at(3) })
+        }
+        (OUI_IEEE_8023, IEEE_8023_LINK_AGGREGATION, 5) => {
+            Some(LldpTlv::LinkAggregation { status: info[0], port_id: u32::from_be_bytes([info[1], info[2], info[3], info[4]]) })
+        }
+        (OUI_IEEE_8023, IEEE_8023_MAX_FRAME_SIZE, 2) => Some(LldpTlv::MaxFrameSize(u16_at(0))),
+        _ => None,
+    };
+    typed.unwrap_or_else(|| LldpTlv::OrganizationallySpecific { oui, subtype, info: info.to_vec() })
+}
+
+impl fmt::Display for LldpTlv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LldpTlv::End => write!(f, "End of LLDPDU"),
+            LldpTlv::ChassisId { subtype, id } => write!(f, "Chassis Id: {}", lldp_id_string(*subtype == CHASSIS_ID_MAC_ADDRESS, id)),
+            LldpTlv::PortId { subtype, id } => write!(f, "Port Id: {}", lldp_id_string(*subtype == PORT_ID_MAC_ADDRESS, id)),
+            LldpTlv::Ttl(ttl) => write!(f, "Time To Live: {}", ttl),
+            LldpTlv::PortDescription(s) => write!(f, "Port Description: {}", s),
+            LldpTlv::SystemName(s) => write!(f, "System Name: {}", s),
+            LldpTlv::SystemDescription(s) => write!(f, "System Description: {}", s),
+            LldpTlv::Capabilities { system, enabled } => write!(f, "Capabilities: 0x{:04x}, Enabled: 0x{:04x}", system, enabled),
+            LldpTlv::ManagementAddress { address, .. } => write!(f, "Management Address: {}", lldp_address_string(address)),
+            LldpTlv::PortVlanId(vlan_id) => write!(f, "Port VLAN Identifier: {}", vlan_id),
+            LldpTlv::VlanName { vlan_id, name } => write!(f, "VLAN Name: {} ({})", name, vlan_id),
+            LldpTlv::MacPhyConfig { mau_type, .. } => write!(f, "MAC/PHY Configuration/Status, MAU Type: {}", mau_type),
+            LldpTlv::LinkAggregation { status, port_id } => write!(f, "Link Aggregation, Status: 0x{:02x}, Port Id: {}", status, port_id),
+            LldpTlv::MaxFrameSize(size) => write!(f, "Maximum Frame Size: {}", size),
+            LldpTlv::OrganizationallySpecific { oui, subtype, .. } => {
+                write!(f, "Organization Specific, OUI: {:02x}:{:02x}:{:02x}, Subtype: {}", oui[0], oui[1], oui[2], subtype)
+            }
+            LldpTlv::Unknown { tlv_type, value } => write!(f, "Unknown TLV {} ({} bytes)", tlv_type, value.len()),
+        }
+    }
+}
+
+/// shows a chassis or port id as a MAC address if it is one, as text if it's printable, and as hex otherwise
+pub(crate) fn lldp_id_string(is_mac: bool, id: &[u8]) -> String {
+    match std::str::from_utf8(id) {
+        _ if is_mac && id.len() == 6 => MacAddr::new(id[0], id[1], id[2], id[3], id[4], id[5]).to_string(),
+        Ok(s) if !s.chars().any(char::is_control) => s.to_string(),
+        _ => id.iter().map(|b| format!("{:02x}", b)).collect(),
+    }
+}
+
+pub(crate) fn lldp_address_string(address: &[u8]) -> String {
+    if let Ok(octets) = <[u8; 4]>::try_from(address) {
+        return IpAddr::from(octets).to_string();
+    }
+    if let Ok(octets) = <[u8; 16]>::try_from(address) {
+        return IpAddr::from(octets).to_string();
+    }
+    lldp_id_string(address.len() == 6, address)
+}
+
+/// An LLDP data unit, which is a list of TLVs: the chassis id, port id and TTL, any optional TLVs, and an end TLV.
+///
+/// The TLVs are written in the order they are in, with nothing added or checked, so that malformed LLDPDUs can be built too
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LldpHeader {
+    #[get]
+    #[set]
+    tlvs: Vec<LldpTlv>,
+}
+
+impl LldpHeader {
+    /// the mandatory TLVs, with the chassis id being a MAC address and the port id an interface name, and an end TLV
+    pub fn new<M: Into<MacAddr>>(chassis_mac: M, port_name: &str, ttl: u16) -> Self {
+        Self::from_tlvs(vec![LldpTlv::chassis_mac(chassis_mac), LldpTlv::port_name(port_name), LldpTlv::Ttl(ttl), LldpTlv::End])
+    }
+
+    pub fn from_tlvs(tlvs: Vec<LldpTlv>) -> Self {
+        LldpHeader { tlvs }
+    }
+
+    /// adds a TLV before the end TLV, or at the end if there isn't one
+    pub fn add_tlv(&mut self, tlv: LldpTlv) -> &mut Self {
+        let position = match self.tlvs.last() {
+            Some(LldpTlv::End) => self.tlvs.len() - 1,
+            _ => self.tlvs.len(),
+        };
+        self.tlvs.insert(position, tlv);
+        self
+    }
+
+    /// the first TLV of the given type
+    pub fn get_tlv(&self, tlv_type: u8) -> Option<&LldpTlv> {
+        self.tlvs.iter().find(|tlv| tlv.tlv_type() == tlv_type)
+    }
+
+    /// the subtype and id of the chassis id TLV
+    pub fn get_chassis_id(&self) -> Option<(u8, &[u8])> {
+        self.tlvs.iter().find_map(|tlv| match tlv {
+            LldpTlv::ChassisId { subtype, id } => Some((*subtype, id.as_slice())),
+            _ => None,
+        })
+    }
+
+    /// the subtype and id of the port id TLV
+    pub fn get_port_id(&self) -> Option<(u8, &[u8])> {
+        self.tlvs.iter().find_map(|tlv| match tlv {
+            LldpTlv::PortId { subtype, id } => Some((*subtype, id.as_slice())),
+            _ => None,
+        })
+    }
+
+    pub fn get_ttl(&self) -> Option<u16> {
+        self.tlvs.iter().find_map(|tlv| match tlv {
+            LldpTlv::Ttl(ttl) => Some(*ttl),
+            _ => None,
+        })
+    }
+
+    pub fn get_system_name(&self) -> Option<&str> {
+        self.tlvs.iter().find_map(|tlv| match tlv {
+            LldpTlv::SystemName(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// the mandatory TLVs for a zero chassis MAC address and an unnamed port, with the usual TTL of 120 seconds
+impl Default for LldpHeader {
+    fn default() -> Self {
+        Self::new(MacAddr::ZERO, "", 120)
+    }
+}
+
+impl fmt::Display for LldpHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Link Layer Discovery Protocol")?;
+        for tlv in self.tlvs.iter().filter(|tlv| **tlv != LldpTlv::End) {
+            write!(f, ", {}", tlv)?;
+        }
+        Ok(())
+    }
+}
+
+impl Header for LldpHeader {
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
+        for tlv in &self.tlvs {
+            let value = tlv.value();
+            buf.put_u16(((tlv.tlv_type() as u16) << 9) | (value.len() as u16 & 0x01ff));
+            buf.put_slice(&value);
+        }
+    }
+
+    /// parses TLVs up to and including the end TLV, or to the end of `raw_data` if there isn't one
+    fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
+        if raw_data.len() < Self::get_min_length() {
+            return Err(ParseError::InvalidLength);
+        }
+        let mut tlvs = Vec::new();
+        let mut position = 0;
+        while position < raw_data.len() {
+            let (tlv, length) = LldpTlv::parse(&raw_data[position..])?;
+            position += length;
+            let end = tlv == LldpTlv::End;
+            tlvs.push(tlv);
+            if end {
+                break;
+            }
+        }
+        Ok(Box::new(Self { tlvs }))
+    }
+
+    fn get_proto(&self) -> Protocol {
+        Protocol::LLDP
+    }
+
+    fn get_length(&self) -> usize {
+        self.tlvs.iter().map(LldpTlv::get_length).sum()
+    }
+
+    fn get_min_length() -> usize {
+        // an end TLV on its own
+        2
+    }
+}
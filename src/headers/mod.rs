@@ -1,4 +1,6 @@
-use super::{Protocol, ParseError};
+use super::{Protocol, ParseError, WriteError};
+use bytes::BufMut;
+use std::fmt;
 
 mod ethernet;
 mod arp;
@@ -6,6 +8,10 @@ mod icmp;
 mod ip;
 mod tcp;
 mod udp;
+mod vlan;
+mod llc;
+mod lldp;
+mod mpls;
 // de-glob the modules, doing this rather than having "pub mod x;" allows for shorter use paths
 pub use ethernet::*;
 pub use arp::*;
@@ -13,6 +19,10 @@ pub use icmp::*;
 pub use ip::*;
 pub use tcp::*;
 pub use udp::*;
+pub use vlan::*;
+pub use llc::*;
+pub use lldp::*;
+pub use mpls::*;
 
 pub type PacketData = Vec<u8>;
 
@@ -21,7 +31,27 @@ pub trait Header {
     /// this function will calculate checksums, even though they will be over-written by the OS if the packet is sent 'down the wire', likewise with a lot of `length` fields and such.
     /// 
     /// The reason i decided to still calculate checksums is incase someone uses the packet for some other purpose, or if they are building/using an experimntal OS which doesn calculate checksums (or they havent built that in yet).
-    fn make(self) -> PacketData;
+    fn make(self) -> PacketData where Self: Sized {
+        let mut data = Vec::with_capacity(self.get_length());
+        self.write_into(&mut data);
+        data
+    }
+
+    /// Writes the cooked header onto the end of `buf` without consuming the header, so it can be written again later.
+    /// This is what `make` and `write_to` use to serialise the header.
+    fn write_into<B: BufMut>(&self, buf: &mut B);
+
+    /// Writes the cooked header to the start of `buf` without consuming the header or allocating, returning the number of bytes written.
+    /// Returns an error, and leaves `buf` untouched, if `buf` is shorter than `get_length()`
+    fn write_to(&self, buf: &mut [u8]) -> Result<usize, WriteError> {
+        let length = self.get_length();
+        if buf.len() < length {
+            return Err(WriteError::BufferTooSmall { needed: length, available: buf.len() });
+        }
+        let mut cursor = &mut buf[..length];
+        self.write_into(&mut cursor);
+        Ok(length)
+    }
 
     /// parse() should never be run from a box<Header>, is hould only ever br un as <Header>::parse().
     /// this is just here to complete the implementation of Header onto boxed structs that implement header
@@ -30,14 +60,16 @@ pub trait Header {
     fn get_proto(&self) -> Protocol;
 
     /// get the current length of the header in bytes. this usually just returns a fixed value as most headers dont have variable length,
-    /// only really IP does and even then its rare for it to be > 20 bytes
-    fn get_length(&self) -> u8; // these are done as functions rather than constants in order to enforce all modules to have them if they want to implement this trait
+    /// only really IP does and even then its rare for it to be > 20 bytes.
+    /// this is a `usize` so that headers longer than 255 bytes (extension header chains, application messages) can still be layers
+    fn get_length(&self) -> usize; // these are done as functions rather than constants in order to enforce all modules to have them if they want to implement this trait
 
     /// get the minimum length (in bytes) that this type of header can be
-    fn get_min_length() -> u8; // these are done as functions rather than constants in order to enforce all modules to have them if they want to implement this trait
+    fn get_min_length() -> usize; // these are done as functions rather than constants in order to enforce all modules to have them if they want to implement this trait
 
     /// attempts to coerce the header (a type which implements the Header trait) into a &mut dyn TransportHeader.
     /// Only returns `Option::Some` when the underlying concrete type is a `UdpHeader` or a `TcpHeader`
+    #[allow(clippy::wrong_self_convention)]
     fn into_transport_header(&mut self) -> Option<&mut dyn TransportHeader> {
         None
     }
@@ -52,6 +84,7 @@ pub trait TransportHeader {
     fn set_pseudo_header(&mut self, src_ip: [u8; 4], dst_ip: [u8; 4], data_len: u16);
 }
 
+#[derive(Debug)]
 struct PseudoHeader {
     src_ip: [u8; 4],
     dst_ip: [u8; 4],
@@ -64,6 +97,10 @@ impl<T: Header> Header for Box<T> {
         (*self).make()
     }
 
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
+        (**self).write_into(buf)
+    }
+
     fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError>{
         match T::parse(raw_data) {
             Ok(boxed_header) => Ok(Box::new(boxed_header)),
@@ -75,15 +112,151 @@ impl<T: Header> Header for Box<T> {
         (**self).get_proto()
     }
 
-    fn get_length(&self) -> u8{
+    fn get_length(&self) -> usize{
         (**self).get_length()
     }
 
-    fn get_min_length() -> u8{
+    fn get_min_length() -> usize{
         T::get_min_length()
     }
 
     fn into_transport_header(&mut self) -> Option<&mut dyn TransportHeader> {
         (**self).into_transport_header()
     }
-}
\ No newline at end of file
+}
+/// Any one of the headers in this module. This is what a `Packet`'s layers can be turned into when the type of each header
+/// isn't known ahead of time, e.g. when rebuilding a packet from a list of layers with `Packet::from_layers`.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnyHeader {
+    Ethernet(EthernetHeader),
+    Arp(ArpHeader),
+    Icmp(IcmpHeader),
+    Ip(IpHeader),
+    Tcp(TcpHeader),
+    Udp(UdpHeader),
+    Vlan(VlanHeader),
+    Llc(LlcHeader),
+    Snap(SnapHeader),
+    Lldp(LldpHeader),
+    Mpls(MplsHeader),
+    PwControlWord(PwControlWordHeader),
+}
+
+// calls the same method on whichever header is inside the `AnyHeader`
+macro_rules! dispatch {
+    ($any:expr, $header:ident => $body:expr) => (
+        match $any {
+            AnyHeader::Ethernet($header) => $body,
+            AnyHeader::Arp($header) => $body,
+            AnyHeader::Icmp($header) => $body,
+            AnyHeader::Ip($header) => $body,
+            AnyHeader::Tcp($header) => $body,
+            AnyHeader::Udp($header) => $body,
+            AnyHeader::Vlan($header) => $body,
+            AnyHeader::Llc($header) => $body,
+            AnyHeader::Snap($header) => $body,
+            AnyHeader::Lldp($header) => $body,
+            AnyHeader::Mpls($header) => $body,
+            AnyHeader::PwControlWord($header) => $body,
+        }
+    )
+}
+
+impl AnyHeader {
+    /// Parses `raw_data` as a header of the given protocol
+    pub fn parse_as(proto: Protocol, raw_data: &[u8]) -> Result<Self, ParseError> {
+        Ok(match proto {
+            Protocol::ETH => AnyHeader::Ethernet(*EthernetHeader::parse(raw_data)?),
+            Protocol::ARP => AnyHeader::Arp(*ArpHeader::parse(raw_data)?),
+            Protocol::ICMP => AnyHeader::Icmp(*IcmpHeader::parse(raw_data)?),
+            Protocol::IP => AnyHeader::Ip(*IpHeader::parse(raw_data)?),
+            Protocol::TCP => AnyHeader::Tcp(*TcpHeader::parse(raw_data)?),
+            Protocol::UDP => AnyHeader::Udp(*UdpHeader::parse(raw_data)?),
+            Protocol::VLAN => AnyHeader::Vlan(*VlanHeader::parse(raw_data)?),
+            Protocol::LLC => AnyHeader::Llc(*LlcHeader::parse(raw_data)?),
+            Protocol::SNAP => AnyHeader::Snap(*SnapHeader::parse(raw_data)?),
+            Protocol::LLDP => AnyHeader::Lldp(*LldpHeader::parse(raw_data)?),
+            Protocol::MPLS => AnyHeader::Mpls(*MplsHeader::parse(raw_data)?),
+            Protocol::PWCW => AnyHeader::PwControlWord(*PwControlWordHeader::parse(raw_data)?),
+        })
+    }
+}
+
+impl Header for AnyHeader {
+    fn make(self) -> PacketData {
+        dispatch!(self, h => h.make())
+    }
+
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
+        dispatch!(self, h => h.write_into(buf))
+    }
+
+    /// an `AnyHeader` can't tell which protocol the data is, so this always fails. use `AnyHeader::parse_as` instead
+    fn parse(_raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
+        Err(ParseError::InvalidFormat)
+    }
+
+    fn get_proto(&self) -> Protocol {
+        dispatch!(self, h => h.get_proto())
+    }
+
+    fn get_length(&self) -> usize {
+        dispatch!(self, h => Header::get_length(h))
+    }
+
+    /// the smallest of the minimum lengths of all the headers
+    fn get_min_length() -> usize {
+        [
+            EthernetHeader::get_min_length(),
+            ArpHeader::get_min_length(),
+            IcmpHeader::get_min_length(),
+            IpHeader::get_min_length(),
+            TcpHeader::get_min_length(),
+            UdpHeader::get_min_length(),
+            VlanHeader::get_min_length(),
+            LlcHeader::get_min_length(),
+            SnapHeader::get_min_length(),
+            LldpHeader::get_min_length(),
+            MplsHeader::get_min_length(),
+            PwControlWordHeader::get_min_length(),
+        ].iter().copied().min().unwrap()
+    }
+
+    fn into_transport_header(&mut self) -> Option<&mut dyn TransportHeader> {
+        dispatch!(self, h => h.into_transport_header())
+    }
+}
+
+impl fmt::Display for AnyHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        dispatch!(self, h => fmt::Display::fmt(h, f))
+    }
+}
+
+macro_rules! impl_from_header_for_any_header {
+    ( $($header:ident => $variant:ident),* ) => (
+        $(
+            impl From<$header> for AnyHeader {
+                fn from(h: $header) -> Self {
+                    AnyHeader::$variant(h)
+                }
+            }
+        )*
+    )
+}
+
+impl_from_header_for_any_header!(
+    EthernetHeader => Ethernet,
+    ArpHeader => Arp,
+    IcmpHeader => Icmp,
+    IpHeader => Ip,
+    TcpHeader => Tcp,
+    UdpHeader => Udp,
+    VlanHeader => Vlan,
+    LlcHeader => Llc,
+    SnapHeader => Snap,
+    LldpHeader => Lldp,
+    MplsHeader => Mpls,
+    PwControlWordHeader => PwControlWord
+);
@@ -0,0 +1,193 @@
+use super::{Header, Protocol, ParseError};
+use bytes::BufMut;
+use std::fmt;
+
+/// the reserved label which stands for "no label" at the bottom of an IPv4 packet's stack, popped by the penultimate hop
+pub const LABEL_IPV4_EXPLICIT_NULL: u32 = 0;
+/// the router alert label, which passes the packet up to the software of every router it goes through
+pub const LABEL_ROUTER_ALERT: u32 = 1;
+/// the IPv6 version of `LABEL_IPV4_EXPLICIT_NULL`
+pub const LABEL_IPV6_EXPLICIT_NULL: u32 = 2;
+/// the label a router advertises to be sent packets without a label (penultimate hop popping). it never appears on the wire
+pub const LABEL_IMPLICIT_NULL: u32 = 3;
+/// the largest label, as labels are 20 bits long
+pub const MAX_LABEL: u32 = 0xfffff;
+
+/// An MPLS label stack entry, i.e. one label of the stack which goes between an ethernet header with `ETHERTYPE_MPLS_UNICAST`
+/// or `ETHERTYPE_MPLS_MULTICAST` and the packet being switched.
+///
+/// Each label is its own header, so a stack of labels is built by stacking `MplsHeader`s. The bottom of stack bit says
+/// whether this is the last label: a new label is at the bottom of the stack, and stacking another label on top of it with `/`
+/// clears its bit
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MplsHeader {
+    /// the label, from 0 to `MAX_LABEL`
+    #[get]
+    #[set]
+    label: u32,
+
+    /// the traffic class (once called the experimental bits), from 0 to 7
+    #[get]
+    #[set]
+    traffic_class: u8,
+
+    #[get]
+    #[set]
+    bottom_of_stack: bool,
+
+    #[get]
+    #[set]
+    ttl: u8,
+}
+
+impl MplsHeader {
+    /// a label at the bottom of the stack, with a traffic class of 0 and a TTL of 64
+    pub fn new(label: u32) -> Self {
+        MplsHeader {
+            label,
+            traffic_class: 0,
+            bottom_of_stack: true,
+            ttl: 64,
+        }
+    }
+}
+
+/// the IPv4 explicit null label
+impl Default for MplsHeader {
+    fn default() -> Self {
+        Self::new(LABEL_IPV4_EXPLICIT_NULL)
+    }
+}
+
+impl fmt::Display for MplsHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "MultiProtocol Label Switching Header, Label: {}, Exp: {}, S: {}, TTL: {}",
+            self.label, self.traffic_class, self.bottom_of_stack as u8, self.ttl
+        )
+    }
+}
+
+impl Header for MplsHeader {
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
+        let entry = ((self.label & MAX_LABEL) << 12)
+            | ((self.traffic_class as u32 & 0x7) << 9)
+            | ((self.bottom_of_stack as u32) << 8)
+            | self.ttl as u32;
+        buf.put_u32(entry);
+    }
+
+    fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
+        if raw_data.len() < Self::get_min_length() {
+            return Err(ParseError::InvalidLength);
+        }
+        let entry = u32::from_be_bytes([raw_data[0], raw_data[1], raw_data[2], raw_data[3]]);
+        Ok(Box::new(Self {
+            label: entry >> 12,
+            traffic_class: ((entry >> 9) & 0x7) as u8,
+            bottom_of_stack: entry & 0x100 != 0,
+            ttl: entry as u8,
+        }))
+    }
+
+    fn get_proto(&self) -> Protocol {
+        Protocol::MPLS
+    }
+
+    fn get_length(&self) -> usize {
+        4
+    }
+
+    fn get_min_length() -> usize {
+        4
+    }
+}
+
+/// The control word (RFC 4385) which goes after the label stack of a pseudowire, most often followed by an ethernet frame (RFC 4448).
+///
+/// Its first 4 bits are always 0, which is how it is told apart from an IPv4 or IPv6 packet after the bottom label
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PwControlWordHeader {
+    /// the 4 flag bits, which are specific to the type of pseudowire
+    #[get]
+    #[set]
+    flags: u8,
+
+    /// the 2 fragmentation bits
+    #[get]
+    #[set]
+    fragmentation: u8,
+
+    /// the length of a payload shorter than the minimum ethernet payload, or 0
+    #[get]
+    #[set]
+    payload_length: u8,
+
+    /// the sequence number, or 0 if sequence numbers aren't used
+    #[get]
+    #[set]
+    sequence_number: u16,
+}
+
+impl PwControlWordHeader {
+    pub fn new(sequence_number: u16) -> Self {
+        PwControlWordHeader {
+            flags: 0,
+            fragmentation: 0,
+            payload_length: 0,
+            sequence_number,
+        }
+    }
+}
+
+/// a control word without a sequence number
+impl Default for PwControlWordHeader {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl fmt::Display for PwControlWordHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PW MPLS Control Word, Flags: 0x{:x}, FRG: {}, Length: {}, Sequence Number: {}",
+            self.flags, self.fragmentation, self.payload_length, self.sequence_number
+        )
+    }
+}
+
+impl Header for PwControlWordHeader {
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
+        buf.put_u8(self.flags & 0x0f);
+        buf.put_u8(((self.fragmentation & 0x3) << 6) | (self.payload_length & 0x3f));
+        buf.put_u16(self.sequence_number);
+    }
+
+    fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
+        if raw_data.len() < Self::get_min_length() {
+            return Err(ParseError::InvalidLength);
+        }
+        Ok(Box::new(Self {
+            flags: raw_data[0] & 0x0f,
+            fragmentation: raw_data[1] >> 6,
+            payload_length: raw_data[1] & 0x3f,
+            sequence_number: ((raw_data[2] as u16) << 8) + raw_data[3] as u16,
+        }))
+    }
+
+    fn get_proto(&self) -> Protocol {
+        Protocol::PWCW
+    }
+
+    fn get_length(&self) -> usize {
+        4
+    }
+
+    fn get_min_length() -> usize {
+        4
+    }
+}
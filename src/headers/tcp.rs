@@ -1,14 +1,24 @@
 use crate::AsBeBytes;
-use super::{Header, TransportHeader, PacketData, Protocol, ParseError, PseudoHeader};
+use super::{Header, TransportHeader, Protocol, ParseError, PseudoHeader};
+use bytes::BufMut;
+use std::fmt;
 
-#[derive(AddGetter, AddSetter)]
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TcpHeader {
     #[get] #[set] src_port: u16,
     #[get] #[set] dst_port: u16,
+    #[get] #[set] #[cfg_attr(feature = "serde", serde(default))] sequence_number: u32,
+    #[get] #[set] #[cfg_attr(feature = "serde", serde(default))] acknowledgment_number: u32,
+    /// the low 4 bits of the data offset byte, which are 3 reserved bits and the NS flag
+    #[get] #[set] #[cfg_attr(feature = "serde", serde(default))] reserved: u8,
     #[get] #[set] flags: u8,
     #[get] #[set] window: u16,
-    pseudo_header: Option<PseudoHeader>,
-    #[get] pseudo_header_set: bool
+    #[get] #[set] #[cfg_attr(feature = "serde", serde(default))] urgent_pointer: u16,
+    /// the options, which are padded with zeros to a multiple of 4 bytes when the header is written
+    #[get] #[set] #[cfg_attr(feature = "serde", serde(default))] options: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(skip))] pseudo_header: Option<PseudoHeader>,
+    #[cfg_attr(feature = "serde", serde(skip))] #[get] pseudo_header_set: bool
 }
 
 pub enum TcpFlags {
@@ -23,10 +33,15 @@ pub enum TcpFlags {
 impl TcpHeader {
     pub fn new(src_port: u16, dst_port: u16) -> Self {
         TcpHeader {
-            src_port: src_port,
-            dst_port: dst_port,
+            src_port,
+            dst_port,
+            sequence_number: 0,
+            acknowledgment_number: 0,
             window: 0xffff,
+            reserved: 0,
             flags: 0,
+            urgent_pointer: 0,
+            options: Vec::new(),
             pseudo_header: None,
             pseudo_header_set: false
         }
@@ -34,86 +49,119 @@ impl TcpHeader {
 
     pub fn set_flag(&mut self, f: TcpFlags) {
         match f {
-            TcpFlags::Urg => self.flags = self.flags | 0b00100000,
-            TcpFlags::Ack => self.flags = self.flags | 0b00010000,
-            TcpFlags::Psh => self.flags = self.flags | 0b00001000,
-            TcpFlags::Rst => self.flags = self.flags | 0b00000100,
-            TcpFlags::Syn => self.flags = self.flags | 0b00000010,
-            TcpFlags::Fin => self.flags = self.flags | 0b00000001,
+            TcpFlags::Urg => self.flags |= 0b00100000,
+            TcpFlags::Ack => self.flags |= 0b00010000,
+            TcpFlags::Psh => self.flags |= 0b00001000,
+            TcpFlags::Rst => self.flags |= 0b00000100,
+            TcpFlags::Syn => self.flags |= 0b00000010,
+            TcpFlags::Fin => self.flags |= 0b00000001,
         }
     }
 }
 
 impl TransportHeader for TcpHeader {
     fn set_pseudo_header(&mut self, src_ip: [u8; 4], dst_ip: [u8; 4], data_len: u16) {
-        if data_len > (0xffff - 20) {
-            panic!("too much data");
-        }
         self.pseudo_header = Some(PseudoHeader {
             src_ip,
             dst_ip,
             protocol: 6, // 6 = tcp
-            data_len: (data_len + 20) as u16,
+            // with too much data this wraps, the same as the length fields of the packet do, rather than refusing to build it
+            data_len: data_len.wrapping_add(self.get_length() as u16),
         });
         self.pseudo_header_set = true
     }
 }
 
+/// a SYN from port 20 to port 80
+impl Default for TcpHeader {
+    fn default() -> Self {
+        let mut header = Self::new(20, 80);
+        header.set_flag(TcpFlags::Syn);
+        header
+    }
+}
+
+impl fmt::Display for TcpHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Transmission Control Protocol, Src Port: {}, Dst Port: {}, Flags: 0x{:03x}, Window: {}",
+            self.src_port, self.dst_port, self.flags, self.window
+        )
+    }
+}
+
 impl Header for TcpHeader {
-    fn make(self) -> PacketData {
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
         let src_p = self.src_port.split_to_bytes();
         let dst_p = self.dst_port.split_to_bytes();
         let window_bytes = self.window.split_to_bytes();
-        let mut packet = vec![
+        let seq = self.sequence_number.split_to_bytes();
+        let ack = self.acknowledgment_number.split_to_bytes();
+        let urgent_bytes = self.urgent_pointer.split_to_bytes();
+        let mut packet: [u8; 20] = [
             src_p[0],
             src_p[1],
             dst_p[0],
             dst_p[1],
-            0,
-            0,
-            0,
-            0, // Seq num
-            0,
-            0,
-            0,
-            0, // Ack num
-            0, // Offset + 4 of the reserved bits, the other 2 of the 6 total reserved bits are included at the start of the `flags` byte
+            seq[0],
+            seq[1],
+            seq[2],
+            seq[3],
+            ack[0],
+            ack[1],
+            ack[2],
+            ack[3],
+            ((self.get_length() / 4) as u8) << 4 | (self.reserved & 0x0f), // data offset in 32 bit words, then the reserved bits and NS flag
             self.flags,
             window_bytes[0],
             window_bytes[1],
             0,
             0,
-            0,
-            0, // Urgent Pointer -> Should do this at some point
+            urgent_bytes[0],
+            urgent_bytes[1],
         ];
 
-        // calculate checksum
-        if let None = self.pseudo_header {
-            panic!("Please set the pseudo header data before calculating the checksum");
-        }
-        let pseudo_header = self.pseudo_header.unwrap();
-        let mut val = 0u32;
-        val += ip_sum(pseudo_header.src_ip);
-        val += ip_sum(pseudo_header.dst_ip);
-        val += pseudo_header.protocol as u32; // add the value of the protocol field. Since this field is preceeded by an empty reserved byte, it maintains its value so we can just add 6 to the value as so
-        val += pseudo_header.data_len as u32; // header length (in bytes) : when there are no options+padding present, the header length is 20 bytes. this is a 16 bit field which is aligned on a boundary so we can just add this one aswell.
-        // checksum over data
-        let checksum = finalize_checksum(val).split_to_bytes();
+        // calculate checksum. without a pseudo header there is nothing to calculate it over, so it's left as 0 until the header is in a packet
+        let checksum = match self.pseudo_header.as_ref() {
+            Some(pseudo_header) => {
+                let mut val = 0u32;
+                val += ip_sum(pseudo_header.src_ip);
+                val += ip_sum(pseudo_header.dst_ip);
+                val += pseudo_header.protocol as u32; // add the value of the protocol field. Since this field is preceeded by an empty reserved byte, it maintains its value so we can just add 6 to the value as so
+                val += pseudo_header.data_len as u32; // header length (in bytes) including the options and their padding. this is a 16 bit field which is aligned on a boundary so we can just add this one aswell.
+                // checksum over data
+                finalize_checksum(val).split_to_bytes()
+            }
+            None => [0, 0],
+        };
 
         packet[16] = checksum[0];
         packet[17] = checksum[1];
-        packet
+        buf.put_slice(&packet);
+        buf.put_slice(&self.options);
+        buf.put_bytes(0, self.get_length() - 20 - self.options.len());
     }
 
     fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
-        if raw_data.len() < Self::get_min_length().into() {
+        if raw_data.len() < Self::get_min_length() {
+            return Err(ParseError::InvalidLength);
+        }
+        // a data offset too short to be valid is treated as a header without options
+        let header_len = ((raw_data[12] >> 4) as usize * 4).max(20);
+        if raw_data.len() < header_len {
             return Err(ParseError::InvalidLength);
         }
         Ok(Box::new(Self {
             src_port: ((raw_data[0] as u16) << 8) + raw_data[1] as u16,
             dst_port: ((raw_data[2] as u16) << 8) + raw_data[3] as u16,
+            sequence_number: u32::from_be_bytes([raw_data[4], raw_data[5], raw_data[6], raw_data[7]]),
+            acknowledgment_number: u32::from_be_bytes([raw_data[8], raw_data[9], raw_data[10], raw_data[11]]),
+            reserved: raw_data[12] & 0x0f,
             flags: raw_data[13],
             window: ((raw_data[14] as u16) << 8) + raw_data[15] as u16,
+            urgent_pointer: ((raw_data[18] as u16) << 8) + raw_data[19] as u16,
+            options: raw_data[20..header_len].to_vec(),
             pseudo_header: None,
             pseudo_header_set: false
         }))
@@ -123,11 +171,11 @@ impl Header for TcpHeader {
         Protocol::TCP
     }
 
-    fn get_length(&self) -> u8 {
-        20
+    fn get_length(&self) -> usize {
+        20 + self.options.len().div_ceil(4) * 4
     }
 
-    fn get_min_length() -> u8 {
+    fn get_min_length() -> usize {
         20
     }
 
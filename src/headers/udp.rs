@@ -1,21 +1,24 @@
 use crate::AsBeBytes;
-use super::{Header, TransportHeader, PacketData, Protocol, ParseError, PseudoHeader};
+use super::{Header, TransportHeader, Protocol, ParseError, PseudoHeader};
+use bytes::BufMut;
+use std::fmt;
 
-#[derive(AddGetter, AddSetter)]
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UdpHeader {
     #[get] #[set] src_port: u16,
     #[get] #[set] dst_port: u16,
     #[get] #[set] length: u16,
     #[get] #[set] checksum: u16,
-    pseudo_header: Option<PseudoHeader>,
-    pseudo_header_set: bool
+    #[cfg_attr(feature = "serde", serde(skip))] pseudo_header: Option<PseudoHeader>,
+    #[cfg_attr(feature = "serde", serde(skip))] pseudo_header_set: bool
 }
 
 impl UdpHeader {
     pub fn new(src_port: u16, dst_port: u16) -> Self {
         UdpHeader {
-            src_port: src_port,
-            dst_port: dst_port,
+            src_port,
+            dst_port,
             length: 8,
             checksum: 0,
             pseudo_header: None,
@@ -26,26 +29,37 @@ impl UdpHeader {
 
 impl TransportHeader for UdpHeader {
     fn set_pseudo_header(&mut self, src_ip: [u8; 4], dst_ip: [u8; 4], data_len: u16) {
-        if data_len > (0xffff - 8) {
-            panic!("too much data");
-        }
-        self.length += data_len as u16;
+        // with too much data these wrap, the same as the length fields of the packet do, rather than refusing to build it
+        self.length = self.length.wrapping_add(data_len);
         self.pseudo_header = Some(PseudoHeader {
             src_ip,
             dst_ip,
             protocol: 17, // 17 = UDP
-            data_len: (data_len + 8) as u16,
+            data_len: data_len.wrapping_add(8),
         });
         self.pseudo_header_set = true;
     }
 }
 
+/// from port 53 to port 53
+impl Default for UdpHeader {
+    fn default() -> Self {
+        Self::new(53, 53)
+    }
+}
+
+impl fmt::Display for UdpHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "User Datagram Protocol, Src Port: {}, Dst Port: {}", self.src_port, self.dst_port)
+    }
+}
+
 impl Header for UdpHeader {
-    fn make(self) -> PacketData {
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
         let src_p = self.src_port.split_to_bytes();
         let dst_p = self.dst_port.split_to_bytes();
         let length_bytes = self.length.split_to_bytes();
-        let mut packet = vec![
+        let mut packet: [u8; 8] = [
             src_p[0],
             src_p[1],
             dst_p[0],
@@ -56,28 +70,29 @@ impl Header for UdpHeader {
             0
         ];
 
-        // calculate checksum
-        if let None = self.pseudo_header {
-            panic!("Please set the pseudo header data before calculating the checksum");
-        }
-        let pseudo_header = self.pseudo_header.unwrap();
-        let mut val = 0u32;
-        val += ip_sum(pseudo_header.src_ip);
-        val += ip_sum(pseudo_header.dst_ip);
-        val += pseudo_header.protocol as u32;
-        val += pseudo_header.data_len as u32;
+        // calculate checksum. without a pseudo header there is nothing to calculate it over, so it's left as 0 (no checksum) until the header is in a packet
+        let checksum = match self.pseudo_header.as_ref() {
+            Some(pseudo_header) => {
+                let mut val = 0u32;
+                val += ip_sum(pseudo_header.src_ip);
+                val += ip_sum(pseudo_header.dst_ip);
+                val += pseudo_header.protocol as u32;
+                val += pseudo_header.data_len as u32;
 
-        // add data to checksum
-        val += pseudo_header.data_len as u32;
-        let checksum = finalize_checksum(val).split_to_bytes();
+                // add data to checksum
+                val += pseudo_header.data_len as u32;
+                finalize_checksum(val).split_to_bytes()
+            }
+            None => [0, 0],
+        };
 
         packet[6] = checksum[0];
         packet[7] = checksum[1];
-        packet
+        buf.put_slice(&packet);
     }
 
     fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
-        if raw_data.len() < Self::get_min_length().into() {
+        if raw_data.len() < Self::get_min_length() {
             return Err(ParseError::InvalidLength);
         }
         Ok(Box::new(Self {
@@ -94,11 +109,11 @@ impl Header for UdpHeader {
         Protocol::UDP
     }
 
-    fn get_length(&self) -> u8 {
+    fn get_length(&self) -> usize {
         8
     }
 
-    fn get_min_length() -> u8 {
+    fn get_min_length() -> usize {
         8
     }
 
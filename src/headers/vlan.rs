@@ -0,0 +1,89 @@
+use super::{Header, Protocol, ParseError};
+use bytes::BufMut;
+use std::fmt;
+
+/// An 802.1Q VLAN tag. The tag protocol identifier (`ETHERTYPE_VLAN`, `ETHERTYPE_QINQ` or `ETHERTYPE_QINQ_LEGACY`) is the ethertype
+/// of the header before the tag, and the tag's own ethertype says what comes after it, so any number of tags can be stacked
+#[derive(AddGetter, AddSetter, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VlanHeader {
+    /// the priority code point, from 0 to 7
+    #[get]
+    #[set]
+    priority: u8,
+
+    /// the drop eligible indicator
+    #[get]
+    #[set]
+    drop_eligible: bool,
+
+    /// the VLAN identifier, from 0 to 4095
+    #[get]
+    #[set]
+    vlan_id: u16,
+
+    #[get]
+    #[set]
+    eth_type: u16,
+}
+
+impl VlanHeader {
+    /// a tag with priority 0 and an ethertype of 0, which is filled in when another header is stacked on top of it with `/`
+    pub fn new(vlan_id: u16) -> Self {
+        VlanHeader {
+            priority: 0,
+            drop_eligible: false,
+            vlan_id,
+            eth_type: 0,
+        }
+    }
+}
+
+/// a tag for VLAN 1, the default VLAN of most switches
+impl Default for VlanHeader {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl fmt::Display for VlanHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "802.1Q Virtual LAN, PRI: {}, DEI: {}, ID: {}, Type: 0x{:04x}",
+            self.priority, self.drop_eligible as u8, self.vlan_id, self.eth_type
+        )
+    }
+}
+
+impl Header for VlanHeader {
+    fn write_into<B: BufMut>(&self, buf: &mut B) {
+        let tci = ((self.priority as u16 & 0x7) << 13) | ((self.drop_eligible as u16) << 12) | (self.vlan_id & 0x0fff);
+        buf.put_u16(tci);
+        buf.put_u16(self.eth_type);
+    }
+
+    fn parse(raw_data: &[u8]) -> Result<Box<Self>, ParseError> {
+        if raw_data.len() < Self::get_min_length() {
+            return Err(ParseError::InvalidLength);
+        }
+        Ok(Box::new(Self {
+            priority: raw_data[0] >> 5,
+            drop_eligible: raw_data[0] & 0x10 != 0,
+            vlan_id: (((raw_data[0] & 0x0f) as u16) << 8) + raw_data[1] as u16,
+            eth_type: ((raw_data[2] as u16) << 8) + raw_data[3] as u16,
+        }))
+    }
+
+    fn get_proto(&self) -> Protocol {
+        Protocol::VLAN
+    }
+
+    fn get_length(&self) -> usize {
+        4
+    }
+
+    fn get_min_length() -> usize {
+        4
+    }
+}
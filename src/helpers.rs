@@ -1,4 +1,4 @@
-use std::{fmt, slice};
+use std::fmt;
 
 /// Converts a number to an array of its byte representation
 pub trait AsBeBytes {
@@ -15,10 +15,10 @@ macro_rules! impl_split_to_bytes {
             fn split_to_bytes(mut self) -> <Self as AsBeBytes>::Output {
                 let mut bytes_arr = [0u8; $num_bytes];
                 let mut i: usize = bytes_arr.len()-1;
-                while self > 256 {
+                while self >= 256 {
                     bytes_arr[i] = (self%256) as u8;
-                    self = self >> 8;
-                    i = i-1;
+                    self >>= 8;
+                    i -= 1;
                 }
                 bytes_arr[i] = self as u8;
                 bytes_arr
@@ -49,44 +49,120 @@ pub fn checksum(data: &[u8], skipword: usize) -> u16 {
 
 /// Finalises a checksum by making sure its 16 bits, then returning it's 1's compliment
 #[inline]
-fn finalize_checksum(mut cs: u32) -> u16 {
+pub(crate) fn finalize_checksum(mut cs: u32) -> u16 {
     while cs >> 16 != 0 {
         cs = (cs >> 16) + (cs & 0xFFFF);
     }
     !cs as u16
 }
 
-/// Return the sum of the data as 16-bit words (assumes big endian)
-pub fn sum_be_words(d: &[u8], mut skipword: usize) -> u32 {
-    let len = d.len();
-    let word_data: &[u16] = unsafe { slice::from_raw_parts(d.as_ptr() as *const u16, len / 2) };
-    let word_data_length = word_data.len();
-    skipword = ::std::cmp::min(skipword, word_data_length);
-
+/// Return the sum of the data as 16-bit words (assumes big endian). The word at index `skipword` is left out of the sum,
+/// unless `skipword` is 0, in which case every word is included.
+pub fn sum_be_words(d: &[u8], skipword: usize) -> u32 {
     let mut sum = 0u32;
-    let mut i = 0;
-    while i < word_data_length {
+    let mut words = d.chunks_exact(2);
+    for (i, word) in (&mut words).enumerate() {
         if i == skipword && i != 0 {
-            i += 1;
             continue;
         }
-        sum += u16::from_be(unsafe { *word_data.get_unchecked(i) }) as u32;
-        i += 1;
+        sum += u16::from_be_bytes([word[0], word[1]]) as u32;
     }
     // If the length is odd, make sure to checksum the final byte
-    if len & 1 != 0 {
-        sum += (unsafe { *d.get_unchecked(len - 1) } as u32) << 8;
+    if let [last] = words.remainder() {
+        sum += (*last as u32) << 8;
     }
-
     sum
 }
 
-#[derive(Debug)]
+/// Calculates the frame check sequence of an ethernet frame, the CRC-32 of everything from the destination MAC address to the end of the padding.
+/// On the wire, and in captures which include it, the FCS is written least significant byte first, i.e. as `fcs.to_le_bytes()`
+pub fn ethernet_fcs(frame: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for byte in frame {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Returns a canonical hex dump of the data, in the same format as `hexdump -C`: the offset of each line,
+/// 16 bytes per line in hex and then the same bytes as ASCII, with a final line holding the total length
+pub fn hexdump(data: &[u8]) -> String {
+    let mut out = String::new();
+    if data.is_empty() {
+        return out;
+    }
+    for (line, chunk) in data.chunks(16).enumerate() {
+        out.push_str(&format!("{:08x}  ", line * 16));
+        for i in 0..16 {
+            match chunk.get(i) {
+                Some(b) => out.push_str(&format!("{:02x} ", b)),
+                None => out.push_str("   "),
+            }
+            if i == 7 {
+                out.push(' ');
+            }
+        }
+        out.push_str(" |");
+        out.extend(chunk.iter().map(|b| if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '.' }));
+        out.push_str("|\n");
+    }
+    out.push_str(&format!("{:08x}\n", data.len()));
+    out
+}
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// the `index`th number of a seeded pseudo random sequence (splitmix64), so that generated packets can be reproduced from their seed.
+/// any number in the sequence can be worked out without generating the ones before it
+pub(crate) fn random_u64(seed: u64, index: u64) -> u64 {
+    mix64(seed.wrapping_add(index.wrapping_add(1).wrapping_mul(GOLDEN_GAMMA)))
+}
+
+/// A seeded pseudo random number generator, stepping through the same sequence as `random_u64`
+#[derive(Debug, Clone)]
+pub(crate) struct Rng {
+    seed: u64,
+    index: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { seed, index: 0 }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.index += 1;
+        random_u64(self.seed, self.index - 1)
+    }
+
+    /// a number in `0..n`, `n` must not be 0
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// one of the items in `items`, which must not be empty
+    pub(crate) fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ParseError {
     InvalidCharacter,
     InvalidLength,
     InvalidFormat,
-    NotYetImplemented
+    NotYetImplemented,
+    /// a check which `ParseConfig::strict` makes failed
+    FailedCheck(crate::ParseCheck),
 }
 
 impl ParseError {
@@ -95,7 +171,8 @@ impl ParseError {
             Self::InvalidCharacter => "invalid character encountered",
             Self::InvalidLength => "invalid length for the protocol format",
             Self::InvalidFormat => "invalid format of data for the protocol",
-            Self::NotYetImplemented => "the implementation for parsing this type of packet has not yet been made"
+            Self::NotYetImplemented => "the implementation for parsing this type of packet has not yet been made",
+            Self::FailedCheck(_) => "the packet does not conform to its protocol",
         }
     }
 }
@@ -106,4 +183,19 @@ impl fmt::Display for ParseError {
     }
 }
 
-impl std::error::Error for ParseError {}
\ No newline at end of file
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+pub enum WriteError {
+    BufferTooSmall { needed: usize, available: usize },
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BufferTooSmall { needed, available } => write!(f, "Failed to write packet: the buffer is too small, {} bytes are needed but only {} are available", needed, available)
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
\ No newline at end of file
@@ -0,0 +1,159 @@
+//! Importers for packets pasted as text, like text2pcap: plain hex streams (wireshark's "copy as hex stream"),
+//! and hex dumps with offsets such as the output of `xxd`, `hexdump -C`, `od -Ax -tx1 -v`, `tcpdump -xx` / `-XX`
+//! and wireshark's "copy as hex + ASCII dump".
+//!
+//! In a hex dump, each line has to start with the offset of its first byte, followed by the bytes in hex, either one byte per group
+//! or several (`4500 0054`). Anything after the bytes, such as an ASCII column, is ignored, as are lines which don't start with an offset
+//! (e.g. the packet summary lines in tcpdump's output). An offset of 0 starts a new packet.
+//!
+//! The offsets say how many bytes each line holds, so a line with more bytes than the offset of the next line allows has had some of its
+//! ASCII column read as hex and is cut short, while a line with fewer bytes leaves a gap, which is an error.
+
+use super::{Packet, ParseError};
+
+/// Converts a plain hex stream, e.g. `"45 00 00 54"`, `"45:00:00:54"` or `"0x4500, 0x0054"`, into bytes.
+/// Whitespace, `:`, `-` and `,` separators and `0x` prefixes are ignored
+pub fn from_hex_stream(text: &str) -> Result<Vec<u8>, ParseError> {
+    let mut digits = Vec::new();
+    for token in text.split(|c: char| c.is_whitespace() || c == ':' || c == '-' || c == ',') {
+        let token = token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")).unwrap_or(token);
+        for c in token.chars() {
+            digits.push(c.to_digit(16).ok_or(ParseError::InvalidCharacter)? as u8);
+        }
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(ParseError::InvalidLength);
+    }
+    Ok(digits.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect())
+}
+
+/// Converts a hex dump with offsets into the bytes of each of the packets in it
+pub fn from_hex_dump(text: &str) -> Result<Vec<Vec<u8>>, ParseError> {
+    let mut packets = Vec::new();
+    let mut current: Vec<u8> = Vec::new();
+    for line in text.lines().filter_map(dump_line) {
+        if line.offset == 0 && !current.is_empty() {
+            packets.push(std::mem::take(&mut current));
+        }
+        // the previous line had more bytes than it should have, so some of its ASCII column must have been read as hex
+        if line.offset < current.len() {
+            current.truncate(line.offset);
+        }
+        if line.offset > current.len() {
+            return Err(ParseError::InvalidLength);
+        }
+        current.extend(line.bytes);
+    }
+    if !current.is_empty() {
+        packets.push(current);
+    }
+    if packets.is_empty() {
+        return Err(ParseError::InvalidFormat);
+    }
+    Ok(packets)
+}
+
+/// Converts text holding a single packet, either as a hex dump with offsets or as a plain hex stream, into bytes.
+/// If the text is a hex dump with more than one packet in it, only the first is returned.
+///
+/// The text is taken to be a hex dump when its first line starts with an offset of 0 wider than one byte (so that `"00 11 22"` is
+/// still a hex stream) and the offsets of the lines after it are all as wide and only go up, or back to 0
+pub fn from_text(text: &str) -> Result<Vec<u8>, ParseError> {
+    let lines: Vec<DumpLine> = text.lines().filter_map(dump_line).collect();
+    let is_dump = match lines.first() {
+        Some(first) => {
+            first.offset == 0
+                && first.width > 2
+                && !first.bytes.is_empty()
+                && lines.windows(2).all(|pair| {
+                    pair[1].width == first.width && (pair[1].offset == 0 || pair[1].offset > pair[0].offset)
+                })
+        }
+        None => false,
+    };
+    if is_dump {
+        Ok(from_hex_dump(text)?.swap_remove(0))
+    } else {
+        from_hex_stream(text)
+    }
+}
+
+impl Packet {
+    /// Parses a packet from text, either a hex dump with offsets or a plain hex stream. See the `import` module for the formats accepted
+    pub fn from_hex(text: &str) -> Result<Self, ParseError> {
+        let data = from_text(text)?;
+        if data.is_empty() {
+            return Err(ParseError::InvalidLength);
+        }
+        Packet::parse(&data)
+    }
+}
+
+/// a line of a hex dump
+struct DumpLine {
+    offset: usize,
+    /// the number of hex digits in the offset
+    width: usize,
+    bytes: Vec<u8>,
+}
+
+/// splits a line of a hex dump into its offset and bytes, or returns `None` if the line doesn't start with an offset.
+/// A line with only an offset, such as the last line of `hexdump -C` or `od`, gives the length of the packet
+fn dump_line(line: &str) -> Option<DumpLine> {
+    // `hexdump -C` puts its ASCII column between `|`s and `od -tx1z` between `>` and `<`. other formats have no markers,
+    // and may have a `|` or `>` in their ASCII column
+    let trimmed_end = line.trim_end();
+    let marker = if trimmed_end.ends_with('|') {
+        line.find('|')
+    } else if trimmed_end.ends_with('<') {
+        line.find('>')
+    } else {
+        None
+    };
+    let (line, has_ascii_marker) = match marker {
+        Some(i) => (&line[..i], true),
+        None => (line, false),
+    };
+    let line = line.trim_start();
+    let offset_end = line.find(char::is_whitespace).unwrap_or(line.len());
+    let (offset, width) = parse_offset(&line[..offset_end])?;
+
+    let mut bytes = Vec::new();
+    let mut rest = &line[offset_end..];
+    loop {
+        let trimmed = rest.trim_start();
+        if trimmed.is_empty() {
+            break;
+        }
+        // without a marker, the ASCII column is told apart from the bytes by the wider gap before it
+        let gap = rest.len() - trimmed.len();
+        if !has_ascii_marker && !bytes.is_empty() && gap >= 2 {
+            break;
+        }
+        let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        match hex_group(&trimmed[..end]) {
+            Some(group) => bytes.extend(group),
+            None => break,
+        }
+        rest = &trimmed[end..];
+    }
+    Some(DumpLine { offset, width, bytes })
+}
+
+/// parses the offset at the start of a hex dump line, e.g. `00000010:`, `0x0010:` or `0010`, into its value and number of digits
+fn parse_offset(token: &str) -> Option<(usize, usize)> {
+    let token = token.strip_suffix(':').unwrap_or(token);
+    let token = token.strip_prefix("0x").unwrap_or(token);
+    if token.is_empty() || !token.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((usize::from_str_radix(token, 16).ok()?, token.len()))
+}
+
+/// parses a group of one or more bytes written as hex with no separators, e.g. `45` or `4500`
+fn hex_group(token: &str) -> Option<Vec<u8>> {
+    if token.is_empty() || !token.len().is_multiple_of(2) || !token.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..token.len()).step_by(2).map(|i| u8::from_str_radix(&token[i..(i + 2)], 16).ok()).collect()
+}
@@ -3,30 +3,73 @@
 pub mod protocol_numbers;
 pub mod ethertype_numbers;
 pub mod headers;
+pub mod dissect;
+pub mod import;
+pub mod diff;
+pub mod template;
+pub mod fuzz;
+pub mod lint;
+pub mod neighbor;
 mod protocol;
 mod helpers;
+mod compose;
+mod display;
+mod codegen;
+mod overrides;
+mod parse;
+mod mac;
+mod trailer;
+#[cfg(feature = "serde")]
+mod serde_support;
 
 pub use protocol::*;
 pub use helpers::*;
+pub use overrides::ComputedField;
+pub use parse::{ParseCheck, ParseConfig, ParseWarning, PartialPacket, Truncated};
+pub use mac::{MacAddr, MacFormat};
+use overrides::Overrides;
+use trailer::Trailer;
+use bytes::BufMut;
+use std::borrow::Cow;
 use headers::{
     Header,
+    AnyHeader,
+    PacketData,
     EthernetHeader,
     ArpHeader,
     IcmpHeader,
     IpHeader,
     TcpHeader,
-    UdpHeader
+    UdpHeader,
+    VlanHeader,
+    LlcHeader,
+    SnapHeader,
+    LldpHeader,
+    MplsHeader,
+    PwControlWordHeader
 };
-use std::collections::HashMap;
 
 /// represents a network packet. can be used to build or parse packets.
-#[derive(AddSetter)]
+///
+/// The length and checksum fields of the headers are kept up to date every time the packet is changed,
+/// so the packet can be written out as many times as needed with `write_to` or `write_into` without doing any extra work.
+#[derive(Clone)]
 pub struct Packet {
     buffer: Vec<u8>,
-    selection: HashMap<Protocol, u32>,
-    current_index: u32,
-    #[set]
+    layers: Vec<Layer>,
     payload: Vec<u8>,
+    /// the padding and FCS of an ethernet frame, which come after the payload
+    trailer: Trailer,
+}
+
+/// the position of a single header within the internal packet buffer
+#[derive(Clone, Copy)]
+struct Layer {
+    proto: Protocol,
+    offset: usize,
+    length: usize,
+    /// the computed fields of the header which are fixed rather than calculated
+    overrides: Overrides,
 }
 
 impl Packet {
@@ -34,10 +77,10 @@ impl Packet {
     /// note that the headers arent created with this method, you still have to add them with add_header.
     pub fn new(protos: Vec<Protocol>) -> Self {
         Self {
-            buffer: Vec::with_capacity(protos.iter().fold(0, |c, protocol| c + protocol.min_header_len()) as usize),
-            selection: HashMap::new(),
-            current_index: 0,
+            buffer: Vec::with_capacity(protos.iter().fold(0, |c, protocol| c + protocol.min_header_len())),
+            layers: Vec::with_capacity(protos.len()),
             payload: Vec::new(),
+            trailer: Trailer::default(),
         }
     }
 
@@ -45,161 +88,379 @@ impl Packet {
     pub fn new_empty() -> Self {
         Self {
             buffer: Vec::new(),
-            selection: HashMap::new(),
-            current_index: 0,
+            layers: Vec::new(),
             payload: Vec::new(),
+            trailer: Trailer::default(),
         }
     }
 
     /// Adds the header into the internal packet buffer.
     /// If the header is TCP or UDP, this method will call the `set_pseudo_header` method for you,
     /// as this method is required to be called before calculating the checksum of the header
-    pub fn add_header(&mut self, mut buf: impl Header) {
+    pub fn add_header(&mut self, buf: impl Header) {
+        let index = self.layers.len();
+        self.insert_header(index, buf);
+    }
+
+    /// Inserts the header at position `index` in the layer stack, shifting all the layers after it up by one.
+    /// An `index` of 0 makes the header the outermost layer, and an `index` equal to `layer_count()` appends it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > layer_count()`
+    pub fn insert_header(&mut self, index: usize, mut buf: impl Header) {
+        assert!(index <= self.layers.len(), "layer index {} out of range for a packet with {} layers", index, self.layers.len());
+        self.calculate_fields(&mut buf);
+        let offset = match self.layers.get(index) {
+            Some(layer) => layer.offset,
+            None => self.buffer.len(),
+        };
+        let proto = buf.get_proto();
+        let data = buf.make();
+        let length = data.len();
+        self.splice_layer_data(offset, 0, data);
+        self.shift_offsets(index, length as isize);
+        self.layers.insert(index, Layer { proto, offset, length, overrides: Overrides::default() });
+        self.finalize();
+    }
+
+    /// Removes the layer at position `index`, returning the raw bytes of the removed header,
+    /// or `None` if there is no layer at that position. The layers after it are moved down to fill the gap.
+    pub fn remove_header(&mut self, index: usize) -> Option<PacketData> {
+        if index >= self.layers.len() {
+            return None;
+        }
+        let layer = self.layers.remove(index);
+        let start = layer.offset;
+        let removed = self.buffer.drain(start..(start + layer.length)).collect();
+        self.shift_offsets(index, -(layer.length as isize));
+        self.finalize();
+        Some(removed)
+    }
+
+    /// Replaces the layer at position `index` with the given header, returning the raw bytes of the old header,
+    /// or `None` (without adding the header) if there is no layer at that position.
+    /// The new header does not need to be the same length or protocol as the one it replaces.
+    /// If it is the same protocol, any computed fields which were overridden in the old header stay overridden.
+    pub fn replace_header(&mut self, index: usize, mut buf: impl Header) -> Option<PacketData> {
+        let old = *self.layers.get(index)?;
         self.calculate_fields(&mut buf);
-        self.selection.insert(buf.get_proto(), self.current_index);
-        self.current_index += buf.get_length() as u32;
-        self.buffer.extend(buf.make().into_iter());
+        let proto = buf.get_proto();
+        let data = buf.make();
+        let length = data.len();
+        let removed = self.splice_layer_data(old.offset, old.length, data);
+        self.shift_offsets(index + 1, length as isize - old.length as isize);
+        let overrides = if proto == old.proto { old.overrides } else { Overrides::default() };
+        self.layers[index] = Layer { proto, offset: old.offset, length, overrides };
+        self.finalize();
+        Some(removed)
+    }
+
+    /// swaps `remove_len` bytes of the buffer at `offset` for `data`, returning the bytes that were removed
+    fn splice_layer_data(&mut self, offset: usize, remove_len: usize, data: PacketData) -> PacketData {
+        self.buffer.splice(offset..(offset + remove_len), data).collect()
+    }
+
+    /// moves the offsets of every layer from position `from` onwards by `delta` bytes
+    fn shift_offsets(&mut self, from: usize, delta: isize) {
+        for layer in self.layers[from..].iter_mut() {
+            layer.offset = (layer.offset as isize + delta) as usize;
+        }
     }
 
     /// used internally to call functions which calculate checsum and length fields when the header is added to the packet
     fn calculate_fields(&mut self, buf: &mut impl Header) {
-        match buf.into_transport_header() {
-            Some(th) => {
-                match self.get_header_as_slice(Protocol::IP) {
-                    Some(ip_header) => {
-                        let src_ip: [u8; 4] = [ip_header[12], ip_header[13], ip_header[14], ip_header[15]];
-                        let dst_ip: [u8; 4] = [ip_header[16], ip_header[17], ip_header[18], ip_header[19]];
-                        let all_data_len: u16 = (self.buffer.len() + self.payload.len()) as u16;
-                        th.set_pseudo_header(src_ip, dst_ip, all_data_len);
-                    },
-                    None => {}
-                }
+        if let Some(th) = buf.into_transport_header() {
+            if let Some(ip_header) = self.get_header_as_slice(Protocol::IP) {
+                let src_ip: [u8; 4] = [ip_header[12], ip_header[13], ip_header[14], ip_header[15]];
+                let dst_ip: [u8; 4] = [ip_header[16], ip_header[17], ip_header[18], ip_header[19]];
+                let all_data_len: u16 = (self.buffer.len() + self.payload.len()) as u16;
+                th.set_pseudo_header(src_ip, dst_ip, all_data_len);
             }
-            None => {}
-        };
+        }
     }
 
     /// If the header already exists in the packet, it will be updated with the one passed to this function.
     /// if the header doesn't already exist in the packet, it will be added as if you'd called `add_header` instead.
-    pub fn update_header(&mut self, mut new_buf: impl Header) {
-        match self.selection.remove(&new_buf.get_proto()){
-            Some(i) => {
-                self.calculate_fields(&mut new_buf);
-                let proto = new_buf.get_proto();
-                self.selection.insert(proto, i);
-                let mut data_vec = new_buf.make();
-                let data = data_vec.as_mut_slice();
-                let index: usize = i as usize;
-                let section = unsafe { self.buffer.get_unchecked_mut(index..(index + proto.min_header_len() as usize)) };
-                let mut i = 0;
-                for byte in section.iter_mut() {
-                    *byte = data[i];
-                    i += 1;
-                }
+    ///
+    /// When the packet contains more than one header of the same protocol, e.g. IP in IP, the innermost one is updated.
+    pub fn update_header(&mut self, new_buf: impl Header) {
+        match self.get_layer_index(new_buf.get_proto()) {
+            Some(index) => {
+                self.replace_header(index, new_buf);
             },
             None => self.add_header(new_buf)
         }
     }
 
+    /// Returns the position in the layer stack of the innermost header with the given protocol, if there is one
+    pub fn get_layer_index(&self, p: Protocol) -> Option<usize> {
+        self.layers.iter().rposition(|layer| layer.proto == p)
+    }
+
+    /// Returns the protocols of the headers in this packet, from the outermost layer inwards
+    pub fn get_protocols(&self) -> Vec<Protocol> {
+        self.layers.iter().map(|layer| layer.proto).collect()
+    }
+
+    /// Returns the number of headers in this packet
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns the data which comes after the last header in this packet
+    pub fn get_payload(&self) -> &Vec<u8> {
+        &self.payload
+    }
+
+    /// Replaces the payload of this packet with the given data
+    pub fn set_payload(&mut self, v: Vec<u8>) {
+        self.payload = v;
+        self.finalize();
+    }
+
     /// Appends the given data to the payload of this packet
     pub fn extend_payload<T: IntoIterator<Item = u8>>(&mut self, buf: T) {
         self.payload.extend(buf);
+        self.finalize();
+    }
+
+    /// recalculates the length fields of the IP and UDP headers and the checksums of the IP, TCP, UDP and ICMP headers,
+    /// over the current headers and payload, and then the ethernet padding and FCS. this is called whenever the packet changes
+    fn finalize(&mut self) {
+        finalize_layers(&self.layers, &mut self.buffer, &self.payload);
+        self.finalize_trailer();
+    }
+
+    /// Returns the total length in bytes of the cooked packet, i.e. all of the headers plus the payload,
+    /// plus the padding and FCS of an ethernet frame
+    pub fn len(&self) -> usize {
+        self.buffer.len() + self.payload.len() + self.trailer.bytes.len()
+    }
+
+    /// Returns true if the packet has no headers and no payload
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     /// consumes self and returns the buffer which is the cooked data packet.
-    pub fn into_vec(mut self) -> Vec<u8> {
-
-        // calculate ICMP checksum if present.
-        // this needs to be done here as it should include the payload in the checksum calculation
-        if self.selection.contains_key(&Protocol::ICMP) {
-            let index: usize = *self.selection.get(&Protocol::ICMP).unwrap() as usize;
-            let mut icmp_data: Vec<u8> = self.buffer[(index as usize)..].iter().map(|x| *x).collect(); // assuming here that icmp header is the last one, i.e. there are no more added after it
-            if self.payload.len() > 0 {
-                icmp_data.extend(self.payload.iter());
-            }
-            let checksum = checksum(&icmp_data, 1).split_to_bytes();
-            self.buffer[index + 2] = checksum[0];
-            self.buffer[index + 3] = checksum[1];
+    pub fn into_vec(self) -> Vec<u8> {
+        let Self { mut buffer, mut payload, mut trailer, .. } = self;
+        buffer.append(&mut payload);
+        buffer.append(&mut trailer.bytes);
+        buffer
+    }
+
+    /// Writes the cooked packet to the start of `buf` without consuming the packet or allocating, returning the number of bytes written.
+    /// Returns an error, and leaves `buf` untouched, if `buf` is shorter than `len()`
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<usize, WriteError> {
+        let length = self.len();
+        if buf.len() < length {
+            return Err(WriteError::BufferTooSmall { needed: length, available: buf.len() });
+        }
+        let (headers, rest) = buf[..length].split_at_mut(self.buffer.len());
+        let (payload, trailer) = rest.split_at_mut(self.payload.len());
+        headers.copy_from_slice(&self.buffer);
+        payload.copy_from_slice(&self.payload);
+        trailer.copy_from_slice(&self.trailer.bytes);
+        Ok(length)
+    }
+
+    /// Writes the cooked packet onto the end of `buf` without consuming the packet
+    pub fn write_into<B: BufMut>(&self, buf: &mut B) {
+        buf.put_slice(&self.buffer);
+        buf.put_slice(&self.payload);
+        buf.put_slice(&self.trailer.bytes);
+    }
+
+    /// Returns `Option::Some(&[u8])` if the header is found in this packet, else None.
+    /// This is the innermost header of that protocol, the same as `get_layer_index`
+    pub fn get_header_as_slice(&self, p: Protocol) -> Option<&[u8]> {
+        let index = self.get_layer_index(p)?;
+        Some(&self.buffer[self.layers[index].offset..])
+    }
+
+    /// Returns the header at position `index` in the layer stack, or `None` if there is no layer at that position
+    pub fn get_layer(&self, index: usize) -> Option<AnyHeader> {
+        let layer = self.layers.get(index)?;
+        Some(AnyHeader::parse_as(layer.proto, &self.layer_data(index)?).unwrap())
+    }
+
+    /// the bytes of the header at position `index`, with an overridden header length put back to the real length of the header,
+    /// so that it parses with all of its options whatever the override says
+    fn layer_data(&self, index: usize) -> Option<Cow<'_, [u8]>> {
+        let layer = self.layers.get(index)?;
+        let data = &self.buffer[layer.offset..(layer.offset + layer.length)];
+        if layer.overrides.header_length.is_none() {
+            return Some(Cow::Borrowed(data));
+        }
+        let mut data = data.to_vec();
+        let words = (layer.length / 4) as u8;
+        match layer.proto {
+            Protocol::IP => data[0] = (data[0] & 0xf0) | words,
+            Protocol::TCP => data[12] = (data[12] & 0x0f) | (words << 4),
+            _ => {}
         }
-        self.buffer.append(&mut self.payload);
-        self.buffer
+        Some(Cow::Owned(data))
     }
 
+    /// Returns all of the headers in this packet, from the outermost layer inwards
+    pub fn get_layers(&self) -> Vec<AnyHeader> {
+        (0..self.layers.len()).filter_map(|index| self.get_layer(index)).collect()
+    }
 
-    /// Try to create a `Packet` from raw packet data and populate it with the values in the given data packet
-    pub fn parse(raw_data: &[u8]) -> Result<Self, ParseError> {
+    /// Returns the labels of the MPLS label stack, outermost first. `get_mpls_header` only returns the bottom one
+    pub fn get_mpls_labels(&self) -> Vec<MplsHeader> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.proto == Protocol::MPLS)
+            .map(|layer| *MplsHeader::parse(&self.buffer[layer.offset..]).unwrap())
+            .collect()
+    }
+
+    /// Builds a packet from a list of headers, outermost first, and a payload. The length and checksum fields are calculated as usual,
+    /// so this can be used to rebuild a finalised packet from a stored list of layers
+    pub fn from_layers(layers: Vec<AnyHeader>, payload: Vec<u8>) -> Self {
         let mut packet = Self::new_empty();
-        if raw_data[0] >> 4 == 4 {
-            packet.parse_ip_packet(raw_data)?;
-            return Ok(packet);
+        packet.payload = payload;
+        for header in layers {
+            packet.add_header(header);
         }
-        packet.parse_ethernet_packet(raw_data)?;
-        Ok(packet)
-    }
-
-    fn parse_ip_packet(&mut self, raw_data: &[u8]) -> Result<(), ParseError> {
-        let ip_header = IpHeader::parse(raw_data)?;
-        let next_protocol = Protocol::from(*ip_header.get_next_protocol());
-        let ip_hdr_len = ip_header.get_length() as usize;
-        self.add_header(ip_header);
-        match next_protocol {
-            Protocol::ETH => {
-                self.add_header(EthernetHeader::parse(&raw_data[ip_hdr_len..])?); // Ethernet in ip encapsulation
+        packet.finalize();
+        packet
+    }
+
+    /// Returns the raw bytes of the header at position `index` in the layer stack, or `None` if there is no layer at that position
+    pub fn get_layer_as_slice(&self, index: usize) -> Option<&[u8]> {
+        let layer = self.layers.get(index)?;
+        let start = layer.offset;
+        Some(&self.buffer[start..(start + layer.length)])
+    }
+}
+
+/// calculates the length and checksum fields of every layer in `headers`, which are followed on the wire by `payload`,
+/// apart from the ones which have been overridden.
+/// the layers are done from the innermost outwards, so that the checksums of outer layers cover the final values of the inner ones
+fn finalize_layers(layers: &[Layer], headers: &mut [u8], payload: &[u8]) {
+    let total_len = headers.len() + payload.len();
+    for (index, layer) in layers.iter().enumerate().rev() {
+        let offset = layer.offset;
+        let overrides = layer.overrides;
+        let put_u16 = |headers: &mut [u8], position: usize, value: u16| headers[position..(position + 2)].copy_from_slice(&value.to_be_bytes());
+        match layer.proto {
+            Protocol::IP => {
+                if is_ipv6(layer, headers) {
+                    let payload_len = overrides.length.unwrap_or((total_len - offset - 40) as u16);
+                    put_u16(headers, offset + 4, payload_len);
+                } else {
+                    let words = overrides.header_length.unwrap_or((layer.length / 4) as u16) as u8;
+                    headers[offset] = (headers[offset] & 0xf0) | (words & 0x0f);
+                    let packet_len = overrides.length.unwrap_or((total_len - offset) as u16);
+                    put_u16(headers, offset + 2, packet_len);
+                    let cs = overrides.checksum.unwrap_or_else(|| checksum(&headers[offset..(offset + layer.length)], 5));
+                    put_u16(headers, offset + 10, cs);
+                }
             },
-            Protocol::ICMP => {
-                self.add_header(IcmpHeader::parse(&raw_data[ip_hdr_len..])?);
+            Protocol::UDP => {
+                let udp_len = overrides.length.unwrap_or((total_len - offset) as u16);
+                put_u16(headers, offset + 4, udp_len);
+                if let Some(ip) = enclosing_ip_layer(layers, index) {
+                    let mut cs = transport_checksum(headers, payload, ip, offset, protocol_numbers::IPPROTO_UDP, 6);
+                    if cs == 0 {
+                        // a checksum of 0 means "no checksum" for udp, so it gets transmitted as all ones instead
+                        cs = 0xffff;
+                    }
+                    put_u16(headers, offset + 6, overrides.checksum.unwrap_or(cs));
+                } else if let Some(cs) = overrides.checksum {
+                    put_u16(headers, offset + 6, cs);
+                }
             },
             Protocol::TCP => {
-                self.add_header(TcpHeader::parse(&raw_data[ip_hdr_len..])?);
+                let words = overrides.header_length.unwrap_or((layer.length / 4) as u16) as u8;
+                headers[offset + 12] = (headers[offset + 12] & 0x0f) | (words << 4);
+                if let Some(ip) = enclosing_ip_layer(layers, index) {
+                    let cs = transport_checksum(headers, payload, ip, offset, protocol_numbers::IPPROTO_TCP, 16);
+                    put_u16(headers, offset + 16, overrides.checksum.unwrap_or(cs));
+                } else if let Some(cs) = overrides.checksum {
+                    put_u16(headers, offset + 16, cs);
+                }
             },
-            Protocol::UDP => {
-                self.add_header(UdpHeader::parse(&raw_data[ip_hdr_len..])?);
+            Protocol::ICMP => {
+                // the ICMPv6 checksum covers a pseudo header as well, like TCP and UDP
+                let cs = match enclosing_ip_layer(layers, index).filter(|ip| is_ipv6(ip, headers)) {
+                    Some(ip) => transport_checksum(headers, payload, ip, offset, protocol_numbers::IPPROTO_ICMPV6, 2),
+                    None => {
+                        put_u16(headers, offset + 2, 0);
+                        finalize_checksum(sum_at(&headers[offset..], 0) + sum_at(payload, headers.len() - offset))
+                    }
+                };
+                put_u16(headers, offset + 2, overrides.checksum.unwrap_or(cs));
             },
-            Protocol::IP => {
-                self.add_header(IpHeader::parse(&raw_data[ip_hdr_len..])?);
+            Protocol::ETH | Protocol::VLAN => {
+                // the type field is the length of an 802.3 frame, counted from the LLC header
+                if let Some(llc) = layers.get(index + 1).filter(|next| next.proto == Protocol::LLC) {
+                    let length = overrides.length.unwrap_or((total_len - llc.offset) as u16);
+                    put_u16(headers, offset + layer.length - 2, length);
+                }
             },
-            _ => panic!("not a valid ip protocol"),
+            Protocol::ARP | Protocol::LLC | Protocol::SNAP | Protocol::LLDP | Protocol::MPLS | Protocol::PWCW => {}
         }
-        Ok(())
     }
+}
 
-    fn parse_ethernet_packet(&mut self, raw_data: &[u8]) -> Result<(), ParseError> {
-        let hdr: Box<EthernetHeader> = EthernetHeader::parse(raw_data)?;
-        let et = *hdr.get_eth_type();
-        self.add_header(hdr);
-        match et {
-            ethertype_numbers::ETHERTYPE_IPV4 => {
-                self.parse_ip_packet(&raw_data[(EthernetHeader::get_min_length() as usize)..])?;
-            },
-            ethertype_numbers::ETHERTYPE_ARP |
-            ethertype_numbers::ETHERTYPE_IPV6 |
-            ethertype_numbers::ETHERTYPE_RARP |
-            ethertype_numbers::ETHERTYPE_LLDP => {
-                return Err(ParseError::NotYetImplemented);
-            },
-            _ => return Err(ParseError::InvalidFormat)
-        }
-        Ok(())
-    }
+/// whether the IP layer is IPv6. this goes by the length of the layer as well as the version field,
+/// so that a header with a bad version can't make the length and checksum calculations read past the end of it
+fn is_ipv6(layer: &Layer, headers: &[u8]) -> bool {
+    layer.length >= 40 && headers[layer.offset] >> 4 == 6
+}
 
-    /// Returns `Option::Some(&[u8])` if the header is found in this packet, else None
-    pub fn get_header_as_slice(&self, p: Protocol) -> Option<&[u8]> {
-        match self.selection.get(&p) {
-            Some(index) => {
-                Some(&self.buffer[(*index as usize)..])
-            },
-            None => None,
-        }
+/// finds the IP layer which carries the layer at position `index`, i.e. the closest IP layer before it
+fn enclosing_ip_layer(layers: &[Layer], index: usize) -> Option<&Layer> {
+    layers[..index].iter().rev().find(|layer| layer.proto == Protocol::IP)
+}
+
+/// sums `data` as 16 bit words as if it started `position` bytes into the packet, folded to 16 bits.
+/// data at an odd position has its bytes the other way round in each word, which, because the sum is ones' complement,
+/// is the same as swapping the bytes of the sum (RFC 1071)
+fn sum_at(data: &[u8], position: usize) -> u32 {
+    let mut sum = sum_be_words(data, 0);
+    while sum >> 16 != 0 {
+        sum = (sum >> 16) + (sum & 0xffff);
+    }
+    if position & 1 == 1 {
+        (sum as u16).swap_bytes() as u32
+    } else {
+        sum
     }
 }
 
+/// calculates the checksum of a TCP or UDP segment starting at `offset` in `headers` and carrying on into `payload`,
+/// including the pseudo header built from the IP header `ip`. the checksum field at `checksum_offset` within the segment is zeroed first
+fn transport_checksum(headers: &mut [u8], payload: &[u8], ip: &Layer, offset: usize, protocol: u8, checksum_offset: usize) -> u16 {
+    let field = offset + checksum_offset;
+    headers[field..(field + 2)].copy_from_slice(&[0, 0]);
+    let ip_header = &headers[ip.offset..];
+    let addresses = if is_ipv6(ip, headers) {
+        &ip_header[8..40]
+    } else {
+        &ip_header[12..20]
+    };
+    let segment_len = (headers.len() - offset + payload.len()) as u32;
+    let mut sum = sum_be_words(addresses, 0);
+    sum += protocol as u32;
+    sum += (segment_len >> 16) + (segment_len & 0xffff);
+    sum += sum_at(&headers[offset..], 0);
+    sum += sum_at(payload, headers.len() - offset);
+    finalize_checksum(sum)
+}
+
 macro_rules! impl_get_header_methods {
     ( $($funname:ident : $proto:path : $ret:ty),* ) => (
         impl Packet {
             $(
                 pub fn $funname(&self) -> Option<Box<$ret>> {
-                    let index = self.selection.get(&$proto)?;
-                    Some(<$ret>::parse(&self.buffer[(*index as usize)..]).unwrap())
+                    let data = self.layer_data(self.get_layer_index($proto)?)?;
+                    Some(<$ret>::parse(&data).unwrap())
                 }
             )*
         }
@@ -212,5 +473,11 @@ impl_get_header_methods!(
     get_eth_header : Protocol::ETH : EthernetHeader,
     get_tcp_header : Protocol::TCP : TcpHeader,
     get_udp_header : Protocol::UDP : UdpHeader,
-    get_icmp_header : Protocol::ICMP : IcmpHeader
+    get_icmp_header : Protocol::ICMP : IcmpHeader,
+    get_vlan_header : Protocol::VLAN : VlanHeader,
+    get_llc_header : Protocol::LLC : LlcHeader,
+    get_snap_header : Protocol::SNAP : SnapHeader,
+    get_lldp_header : Protocol::LLDP : LldpHeader,
+    get_mpls_header : Protocol::MPLS : MplsHeader,
+    get_pw_control_word_header : Protocol::PWCW : PwControlWordHeader
 );
\ No newline at end of file
@@ -0,0 +1,287 @@
+//! A lint pass over a packet, flagging things which are legal to build but which a real network would drop, reject or
+//! treat as an attack, such as a TTL of 0, a broadcast source address or a TCP segment with both SYN and FIN set.
+//!
+//! Findings name the layer and the wireshark style field (`ip.ttl`, `eth.src`, ...) they are about, the same names `Packet::dissect` uses.
+
+use super::{ethertype_numbers, protocol_numbers, MacAddr, Packet, Protocol};
+use crate::headers::{LldpTlv, OUI_ETHERTYPE, REPLY, TLV_CHASSIS_ID, TLV_END, TLV_PORT_ID, TLV_TTL};
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// How much a finding matters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// unusual, but with legitimate uses
+    Info,
+    /// likely to be dropped or mishandled somewhere along the way
+    Warning,
+    /// breaks the protocol, and will be dropped or flagged as an attack
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Something suspicious about one field of a packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    /// the position in the layer stack of the header the field is in
+    pub layer: usize,
+    /// the name of the field, e.g. `ip.ttl`
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: layer {} ({}): {}", self.severity, self.layer, self.field, self.message)
+    }
+}
+
+impl Packet {
+    /// Checks the packet for things which don't follow the RFCs or which would get it dropped, returning what it finds
+    /// in the order of the layers they are in
+    pub fn lint(&self) -> Vec<Finding> {
+        let mut lint = Lint { packet: self, findings: Vec::new() };
+        for (index, layer) in self.layers.iter().enumerate() {
+            let data = &self.buffer[layer.offset..(layer.offset + layer.length)];
+            match layer.proto {
+                Protocol::ETH => lint.ethernet(index, data),
+                Protocol::VLAN => lint.ethertype(index, "vlan.etype", u16::from_be_bytes([data[2], data[3]])),
+                Protocol::SNAP if data[0..3] == OUI_ETHERTYPE => lint.ethertype(index, "llc.pid", u16::from_be_bytes([data[3], data[4]])),
+                Protocol::ARP => lint.arp(index, data),
+                Protocol::IP if super::is_ipv6(layer, &self.buffer) => lint.ipv6(index, data),
+                Protocol::IP => lint.ipv4(index, data),
+                Protocol::TCP => lint.tcp(index, data),
+                Protocol::LLDP => lint.lldp(index, data),
+                Protocol::MPLS => lint.mpls(index, data),
+                Protocol::UDP | Protocol::ICMP | Protocol::LLC | Protocol::SNAP | Protocol::PWCW => {}
+            }
+        }
+        lint.findings
+    }
+}
+
+struct Lint<'a> {
+    packet: &'a Packet,
+    findings: Vec<Finding>,
+}
+
+impl<'a> Lint<'a> {
+    fn add(&mut self, severity: Severity, layer: usize, field: &'static str, message: String) {
+        self.findings.push(Finding { severity, layer, field, message });
+    }
+
+    /// the protocol and data of the layer after the one at position `index`, if there is one
+    fn next_layer(&self, index: usize) -> Option<(Protocol, &'a [u8])> {
+        let packet = self.packet;
+        let layer = packet.layers.get(index + 1)?;
+        Some((layer.proto, &packet.buffer[layer.offset..(layer.offset + layer.length)]))
+    }
+
+    fn ethernet(&mut self, index: usize, data: &[u8]) {
+        let src = MacAddr::new(data[6], data[7], data[8], data[9], data[10], data[11]);
+        if src.is_broadcast() {
+            self.add(Severity::Error, index, "eth.src", String::from("the source MAC address is the broadcast address"));
+        } else if src.is_multicast() {
+            self.add(Severity::Error, index, "eth.src", String::from("the source MAC address is a multicast address"));
+        }
+        self.ethertype(index, "eth.type", u16::from_be_bytes([data[12], data[13]]));
+    }
+
+    /// checks the ethertype of an ethernet header or VLAN tag at position `index` against the next layer
+    fn ethertype(&mut self, index: usize, field: &'static str, eth_type: u16) {
+        let expected: &[u16] = match self.next_layer(index) {
+            Some((Protocol::IP, next)) if next[0] >> 4 == 6 => &[ethertype_numbers::ETHERTYPE_IPV6],
+            Some((Protocol::IP, _)) => &[ethertype_numbers::ETHERTYPE_IPV4],
+            Some((Protocol::ARP, _)) => &[ethertype_numbers::ETHERTYPE_ARP, ethertype_numbers::ETHERTYPE_RARP],
+            Some((Protocol::LLDP, _)) => &[ethertype_numbers::ETHERTYPE_LLDP],
+            Some((Protocol::MPLS, _)) => &[ethertype_numbers::ETHERTYPE_MPLS_UNICAST, ethertype_numbers::ETHERTYPE_MPLS_MULTICAST],
+            Some((Protocol::VLAN, _)) => &[
+                ethertype_numbers::ETHERTYPE_VLAN,
+                ethertype_numbers::ETHERTYPE_QINQ,
+                ethertype_numbers::ETHERTYPE_QINQ_LEGACY,
+            ],
+            // the type field is then a length, which is calculated
+            Some((Protocol::LLC, _)) => return,
+            Some((Protocol::ETH, _)) | None => return,
+            Some(_) => &[],
+        };
+        if !expected.contains(&eth_type) {
+            let next = self.packet.layers[index + 1].proto;
+            self.add(Severity::Error, index, field, format!("{} is 0x{:04x}, but the next layer is {}", field, eth_type, next));
+        }
+    }
+
+    fn arp(&mut self, index: usize, data: &[u8]) {
+        let (hardware_size, protocol_size) = (data[4] as usize, data[5] as usize);
+        if data.len() < 8 + hardware_size + protocol_size {
+            return;
+        }
+        let sender_mac = &data[8..(8 + hardware_size)];
+        let mut broadcast = false;
+        if index > 0 && self.packet.layers[index - 1].proto == Protocol::ETH {
+            let eth = self.packet.layers[index - 1];
+            if hardware_size == 6 && sender_mac != &self.packet.buffer[(eth.offset + 6)..(eth.offset + 12)] {
+                let message = String::from("the sender MAC address is not the source MAC address of the ethernet frame");
+                self.add(Severity::Warning, index, "arp.src.hw_mac", message);
+            }
+            broadcast = self.packet.buffer[eth.offset..(eth.offset + 6)] == [0xff; 6];
+        }
+        if protocol_size == 4 {
+            let start = 8 + hardware_size;
+            let sender_ip = Ipv4Addr::new(data[start], data[start + 1], data[start + 2], data[start + 3]);
+            if let Some((severity, problem)) = ipv4_source_problem(sender_ip) {
+                self.add(severity, index, "arp.src.proto_ipv4", format!("the sender address {} {}", sender_ip, problem));
+            }
+            // a reply goes back to whoever asked, so one which is broadcast (or sent to the broadcast hardware address) can only be a
+            // gratuitous ARP announcing the sender's own address, which has the sender's address as the target address too
+            let target_start = start + 4 + hardware_size;
+            let opcode = u16::from_be_bytes([data[6], data[7]]);
+            if opcode == REPLY && data.len() >= target_start + 4 {
+                let target_ip = Ipv4Addr::new(data[target_start], data[target_start + 1], data[target_start + 2], data[target_start + 3]);
+                broadcast |= hardware_size > 0 && data[(start + 4)..target_start].iter().all(|b| *b == 0xff);
+                if broadcast && sender_ip != target_ip {
+                    let message = format!("the reply is broadcast like a gratuitous ARP, but its sender address {} is not its target address {}", sender_ip, target_ip);
+                    self.add(Severity::Warning, index, "arp.src.proto_ipv4", message);
+                }
+            }
+        }
+    }
+
+    /// an LLDPDU has to start with the chassis ID, port ID and TTL TLVs, in that order, and end with an end TLV
+    fn lldp(&mut self, index: usize, data: &[u8]) {
+        let mut types = Vec::new();
+        let mut start = 0;
+        while let Ok((tlv, length)) = LldpTlv::parse(&data[start..]) {
+            types.push(tlv.tlv_type());
+            start += length;
+            if tlv == LldpTlv::End {
+                break;
+            }
+        }
+        let mandatory = [TLV_CHASSIS_ID, TLV_PORT_ID, TLV_TTL];
+        if types.len() < 3 || types[..3] != mandatory {
+            let message = String::from("the LLDPDU doesn't start with the chassis ID, port ID and TTL TLVs");
+            self.add(Severity::Error, index, "lldp.tlv", message);
+        }
+        if types.last() != Some(&TLV_END) {
+            self.add(Severity::Warning, index, "lldp.tlv", String::from("the LLDPDU doesn't end with an end of LLDPDU TLV"));
+        }
+    }
+
+    /// the bottom of stack bit should be set on the last label, and only the last label
+    fn mpls(&mut self, index: usize, data: &[u8]) {
+        let bottom = data[2] & 0x01 != 0;
+        let next_is_label = matches!(self.next_layer(index), Some((Protocol::MPLS, _)));
+        if bottom && next_is_label {
+            self.add(Severity::Error, index, "mpls.bottom", String::from("the bottom of stack bit is set, but another label comes after this one"));
+        } else if !bottom && !next_is_label {
+            self.add(Severity::Error, index, "mpls.bottom", String::from("the bottom of stack bit isn't set on the last label"));
+        }
+        if data[3] == 0 {
+            self.add(Severity::Warning, index, "mpls.ttl", String::from("the TTL is 0, so the next router will drop the packet"));
+        }
+    }
+
+    fn ipv4(&mut self, index: usize, data: &[u8]) {
+        if data[8] == 0 {
+            self.add(Severity::Warning, index, "ip.ttl", String::from("the TTL is 0, so the first router will drop the packet"));
+        }
+        let src = Ipv4Addr::new(data[12], data[13], data[14], data[15]);
+        if let Some((severity, problem)) = ipv4_source_problem(src) {
+            self.add(severity, index, "ip.src", format!("the source address {} {}", src, problem));
+        }
+        if data[6] & 0x80 != 0 {
+            self.add(Severity::Warning, index, "ip.flags.rb", String::from("the reserved flag is set"));
+        }
+        let header_len = (data[0] & 0x0f) as usize * 4;
+        let payload_len = u16::from_be_bytes([data[2], data[3]]) as usize;
+        self.next_protocol(index, "ip.proto", data[9], payload_len.saturating_sub(header_len));
+    }
+
+    fn ipv6(&mut self, index: usize, data: &[u8]) {
+        if data[7] == 0 {
+            self.add(Severity::Warning, index, "ipv6.hlim", String::from("the hop limit is 0, so the first router will drop the packet"));
+        }
+        let mut octets = [0u8; 16];
+        octets.copy_from_slice(&data[8..24]);
+        let src = Ipv6Addr::from(octets);
+        if src.is_multicast() {
+            self.add(Severity::Error, index, "ipv6.src", format!("the source address {} is a multicast address", src));
+        } else if src.is_loopback() {
+            self.add(Severity::Warning, index, "ipv6.src", format!("the source address {} is the loopback address", src));
+        }
+        let payload_len = u16::from_be_bytes([data[4], data[5]]) as usize;
+        self.next_protocol(index, "ipv6.nxt", data[6], payload_len);
+    }
+
+    /// checks the protocol number of an IP header at position `index` against the next layer,
+    /// and the length of a UDP next layer against `payload_len`, the IP payload length
+    fn next_protocol(&mut self, index: usize, field: &'static str, protocol: u8, payload_len: usize) {
+        let (next, next_data) = match self.next_layer(index) {
+            Some(next) => next,
+            None => return,
+        };
+        let expected: &[u8] = match next {
+            Protocol::IP => &[protocol_numbers::IPPROTO_IPV4, protocol_numbers::IPPROTO_IPV6],
+            Protocol::TCP => &[protocol_numbers::IPPROTO_TCP],
+            Protocol::UDP => &[protocol_numbers::IPPROTO_UDP],
+            Protocol::ICMP => &[protocol_numbers::IPPROTO_ICMP, protocol_numbers::IPPROTO_ICMPV6],
+            Protocol::ETH => &[protocol_numbers::IPPROTO_ETHERIP],
+            Protocol::ARP | Protocol::VLAN | Protocol::LLC | Protocol::SNAP | Protocol::LLDP | Protocol::MPLS | Protocol::PWCW => &[],
+        };
+        if !expected.contains(&protocol) {
+            self.add(Severity::Error, index, field, format!("{} is {}, but the next layer is {}", field, protocol, next));
+        }
+        if next == Protocol::UDP {
+            let udp_len = u16::from_be_bytes([next_data[4], next_data[5]]) as usize;
+            if udp_len != payload_len {
+                let message = format!("udp.length is {}, but the IP header leaves {} bytes for it", udp_len, payload_len);
+                self.add(Severity::Error, index + 1, "udp.length", message);
+            }
+        }
+    }
+
+    fn tcp(&mut self, index: usize, data: &[u8]) {
+        const SYN: u8 = 0x02;
+        let flags = data[13];
+        if flags & SYN != 0 && flags & 0x01 != 0 {
+            self.add(Severity::Error, index, "tcp.flags", String::from("SYN and FIN are both set"));
+        }
+        if flags & SYN != 0 && flags & 0x04 != 0 {
+            self.add(Severity::Error, index, "tcp.flags", String::from("SYN and RST are both set"));
+        }
+        if data[12] & 0x0e != 0 {
+            self.add(Severity::Warning, index, "tcp.flags.res", String::from("reserved bits are set"));
+        }
+    }
+}
+
+/// what is wrong with an IPv4 address as a source address, if anything
+fn ipv4_source_problem(address: Ipv4Addr) -> Option<(Severity, &'static str)> {
+    let first = address.octets()[0];
+    if address.is_broadcast() {
+        Some((Severity::Error, "is the broadcast address"))
+    } else if address.is_multicast() {
+        Some((Severity::Error, "is a multicast address"))
+    } else if address.is_loopback() {
+        Some((Severity::Warning, "is a loopback address, which is never routed off the host"))
+    } else if address.is_unspecified() {
+        Some((Severity::Info, "is unspecified, which is only used by hosts which don't know their address yet"))
+    } else if first == 0 {
+        Some((Severity::Warning, "is in 0.0.0.0/8, which can't be used as a source"))
+    } else if first >= 240 {
+        Some((Severity::Warning, "is in the reserved range 240.0.0.0/4"))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,238 @@
+//! MAC addresses, as used by the link layer headers
+
+use super::ParseError;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// A 48 bit MAC address.
+///
+/// It can be parsed from and formatted as "01:23:45:67:89:ab", "01-23-45-67-89-ab" or "0123.4567.89ab",
+/// and converts to and from `[u8; 6]`, so arrays can still be passed anywhere a `MacAddr` is expected.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MacAddr([u8; 6]);
+
+/// The ways a `MacAddr` can be written out with `MacAddr::format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacFormat {
+    /// "01:23:45:67:89:ab", which is what `Display` uses
+    Colon,
+    /// "01-23-45-67-89-ab", as windows writes them
+    Dash,
+    /// "0123.4567.89ab", as cisco writes them
+    Dot,
+}
+
+impl MacAddr {
+    /// ff:ff:ff:ff:ff:ff
+    pub const BROADCAST: MacAddr = MacAddr([0xff; 6]);
+    /// 00:00:00:00:00:00
+    pub const ZERO: MacAddr = MacAddr([0; 6]);
+
+    pub const fn new(a: u8, b: u8, c: u8, d: u8, e: u8, f: u8) -> Self {
+        MacAddr([a, b, c, d, e, f])
+    }
+
+    pub fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+
+    /// the organizationally unique identifier, which is the first 3 bytes
+    pub fn oui(&self) -> [u8; 3] {
+        [self.0[0], self.0[1], self.0[2]]
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        *self == Self::BROADCAST
+    }
+
+    /// true if the group bit is set, which includes the broadcast address
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
+
+    pub fn is_unicast(&self) -> bool {
+        !self.is_multicast()
+    }
+
+    /// true if the address was assigned locally rather than by the manufacturer, e.g. a randomised or virtual machine address
+    pub fn is_locally_administered(&self) -> bool {
+        self.0[0] & 0x02 != 0
+    }
+
+    /// true if the address was assigned by the manufacturer, from their OUI
+    pub fn is_universal(&self) -> bool {
+        !self.is_locally_administered()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    /// the MAC address an IPv4 multicast group is sent to (01:00:5e followed by the low 23 bits of the group),
+    /// or `None` if `group` isn't a multicast address
+    pub fn from_ipv4_multicast(group: Ipv4Addr) -> Option<Self> {
+        if !group.is_multicast() {
+            return None;
+        }
+        let o = group.octets();
+        Some(MacAddr([0x01, 0x00, 0x5e, o[1] & 0x7f, o[2], o[3]]))
+    }
+
+    /// the MAC address an IPv6 multicast group is sent to (33:33 followed by the low 32 bits of the group),
+    /// or `None` if `group` isn't a multicast address
+    pub fn from_ipv6_multicast(group: Ipv6Addr) -> Option<Self> {
+        if !group.is_multicast() {
+            return None;
+        }
+        let o = group.octets();
+        Some(MacAddr([0x33, 0x33, o[12], o[13], o[14], o[15]]))
+    }
+
+    /// the modified EUI-64 made from this address, as used for IPv6 interface identifiers:
+    /// ff:fe is inserted in the middle and the universal/local bit is flipped
+    pub fn to_eui64(&self) -> [u8; 8] {
+        let m = self.0;
+        [m[0] ^ 0x02, m[1], m[2], 0xff, 0xfe, m[3], m[4], m[5]]
+    }
+
+    /// the address a modified EUI-64 was made from, or `None` if it wasn't made from a MAC address
+    pub fn from_eui64(eui64: [u8; 8]) -> Option<Self> {
+        if eui64[3] != 0xff || eui64[4] != 0xfe {
+            return None;
+        }
+        Some(MacAddr([eui64[0] ^ 0x02, eui64[1], eui64[2], eui64[5], eui64[6], eui64[7]]))
+    }
+
+    /// the fe80::/64 link local address with an interface identifier made from this address
+    pub fn to_ipv6_link_local(&self) -> Ipv6Addr {
+        let mut octets = [0u8; 16];
+        octets[0] = 0xfe;
+        octets[1] = 0x80;
+        octets[8..].copy_from_slice(&self.to_eui64());
+        Ipv6Addr::from(octets)
+    }
+
+    pub fn format(&self, format: MacFormat) -> String {
+        let m = self.0;
+        match format {
+            MacFormat::Colon => self.to_string(),
+            MacFormat::Dash => self.to_string().replace(':', "-"),
+            MacFormat::Dot => format!("{:02x}{:02x}.{:02x}{:02x}.{:02x}{:02x}", m[0], m[1], m[2], m[3], m[4], m[5]),
+        }
+    }
+
+    /// the name of the vendor the OUI is assigned to, from a small built in table of common vendors
+    /// (mostly of network equipment and virtual machines) rather than the full IEEE registry
+    #[cfg(feature = "oui")]
+    pub fn vendor(&self) -> Option<&'static str> {
+        let oui = self.oui();
+        OUI_VENDORS.iter().find(|(o, _)| *o == oui).map(|(_, vendor)| *vendor)
+    }
+}
+
+impl From<[u8; 6]> for MacAddr {
+    fn from(octets: [u8; 6]) -> Self {
+        MacAddr(octets)
+    }
+}
+
+impl From<MacAddr> for [u8; 6] {
+    fn from(mac: MacAddr) -> Self {
+        mac.0
+    }
+}
+
+impl AsRef<[u8]> for MacAddr {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl PartialEq<[u8; 6]> for MacAddr {
+    fn eq(&self, other: &[u8; 6]) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = self.0;
+        write!(f, "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}", m[0], m[1], m[2], m[3], m[4], m[5])
+    }
+}
+
+impl fmt::Debug for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// parses any of the `MacFormat`s, in upper or lower case
+impl FromStr for MacAddr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let groups: Vec<&str> = if s.contains('.') {
+            s.split('.').collect()
+        } else if s.contains('-') {
+            s.split('-').collect()
+        } else {
+            s.split(':').collect()
+        };
+        let digits_per_group = match groups.len() {
+            3 => 4,
+            6 => 2,
+            _ => return Err(ParseError::InvalidFormat),
+        };
+        let mut octets = [0u8; 6];
+        let mut i = 0;
+        for group in groups {
+            // colon and dash groups can drop their leading zero, e.g. "0:1b:21:a:b:c"
+            if group.is_empty() || group.len() > digits_per_group || (digits_per_group == 4 && group.len() != 4) {
+                return Err(ParseError::InvalidFormat);
+            }
+            if !group.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ParseError::InvalidCharacter);
+            }
+            let value = u16::from_str_radix(group, 16).unwrap();
+            if digits_per_group == 4 {
+                octets[i..(i + 2)].copy_from_slice(&value.to_be_bytes());
+                i += 2;
+            } else {
+                octets[i] = value as u8;
+                i += 1;
+            }
+        }
+        Ok(MacAddr(octets))
+    }
+}
+
+#[cfg(feature = "oui")]
+const OUI_VENDORS: &[([u8; 3], &str)] = &[
+    ([0x00, 0x00, 0x0c], "Cisco"),
+    ([0x00, 0x02, 0xb3], "Intel"),
+    ([0x00, 0x03, 0x93], "Apple"),
+    ([0x00, 0x03, 0xff], "Microsoft"),
+    ([0x00, 0x04, 0x4b], "NVIDIA"),
+    ([0x00, 0x05, 0x69], "VMware"),
+    ([0x00, 0x05, 0x85], "Juniper Networks"),
+    ([0x00, 0x09, 0x0f], "Fortinet"),
+    ([0x00, 0x0c, 0x29], "VMware"),
+    ([0x00, 0x0c, 0x42], "MikroTik"),
+    ([0x00, 0x10, 0x18], "Broadcom"),
+    ([0x00, 0x14, 0x22], "Dell"),
+    ([0x00, 0x15, 0x5d], "Microsoft Hyper-V"),
+    ([0x00, 0x16, 0x3e], "Xen"),
+    ([0x00, 0x18, 0x0a], "Cisco Meraki"),
+    ([0x00, 0x1b, 0x21], "Intel"),
+    ([0x00, 0x1c, 0x14], "VMware"),
+    ([0x00, 0x1c, 0x42], "Parallels"),
+    ([0x00, 0x50, 0x56], "VMware"),
+    ([0x00, 0xe0, 0x4c], "Realtek"),
+    ([0x08, 0x00, 0x27], "VirtualBox"),
+    ([0x52, 0x54, 0x00], "QEMU"),
+    ([0xb8, 0x27, 0xeb], "Raspberry Pi"),
+    ([0xdc, 0xa6, 0x32], "Raspberry Pi"),
+    ([0xe4, 0x5f, 0x01], "Raspberry Pi"),
+];
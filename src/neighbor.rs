@@ -0,0 +1,142 @@
+//! Ready made ARP and IPv6 neighbor discovery packets, each a complete ethernet frame.
+//!
+//! The ARP packets are IPv4 over ethernet, with the probe and announcement of RFC 5227. The neighbor discovery packets are
+//! ICMPv6 messages (RFC 4861), which put the reserved or flags word in the identifier and sequence number of the `IcmpHeader`,
+//! and the target address and link layer address option in the payload.
+//!
+//! ```text
+//! let request = neighbor::arp_request(my_mac, [10, 0, 0, 1], [10, 0, 0, 2]);
+//! let reply = neighbor::arp_reply(&request, their_mac).unwrap();
+//! ```
+
+use super::{MacAddr, Packet, Protocol};
+use crate::headers::{ArpHeader, EthernetHeader, IcmpHeader, IpHeader, REPLY, REQUEST};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// the ICMPv6 type of a neighbor solicitation
+pub const NEIGHBOR_SOLICITATION: u8 = 135;
+/// the ICMPv6 type of a neighbor advertisement
+pub const NEIGHBOR_ADVERTISEMENT: u8 = 136;
+
+/// the neighbor advertisement flags, as they are found in the `IcmpHeader` identifier
+pub const NA_ROUTER: u16 = 0x8000;
+pub const NA_SOLICITED: u16 = 0x4000;
+pub const NA_OVERRIDE: u16 = 0x2000;
+
+/// the neighbor discovery option types of the link layer address options
+pub const OPTION_SOURCE_LINK_ADDRESS: u8 = 1;
+pub const OPTION_TARGET_LINK_ADDRESS: u8 = 2;
+
+/// the all nodes multicast address, which unsolicited advertisements are sent to
+const ALL_NODES: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1);
+
+/// An ARP request from `sender_mac` and `sender_ip` asking who has `target_ip`, broadcast
+pub fn arp_request<T: Into<Ipv4Addr>>(sender_mac: MacAddr, sender_ip: T, target_ip: T) -> Packet {
+    arp(REQUEST, sender_mac, sender_ip.into(), MacAddr::ZERO, target_ip.into(), MacAddr::BROADCAST)
+}
+
+/// The reply to the ARP request `request`, saying its target IP address is at `mac`, sent straight back to whoever asked.
+/// Returns `None` if `request` isn't an IPv4 over ethernet ARP request
+pub fn arp_reply(request: &Packet, mac: MacAddr) -> Option<Packet> {
+    request.get_eth_header()?;
+    let asked = request.get_arp_header()?;
+    if *asked.get_opcode() != REQUEST {
+        return None;
+    }
+    let (asker_mac, asker_ip) = (asked.get_sender_mac()?, asked.get_sender_ip()?);
+    Some(arp(REPLY, mac, asked.get_destination_ip()?, asker_mac, asker_ip, asker_mac))
+}
+
+/// A gratuitous ARP reply, broadcasting that `ip` is at `mac` so that other hosts update their caches, e.g. after a failover
+pub fn gratuitous_arp<T: Into<Ipv4Addr>>(mac: MacAddr, ip: T) -> Packet {
+    let ip = ip.into();
+    arp(REPLY, mac, ip, MacAddr::BROADCAST, ip, MacAddr::BROADCAST)
+}
+
+/// An ARP probe (RFC 5227) checking whether anyone else is using `ip` before taking it, which has a sender IP address of 0.0.0.0
+/// so that no one's cache is changed
+pub fn arp_probe<T: Into<Ipv4Addr>>(mac: MacAddr, ip: T) -> Packet {
+    arp(REQUEST, mac, Ipv4Addr::UNSPECIFIED, MacAddr::ZERO, ip.into(), MacAddr::BROADCAST)
+}
+
+/// An ARP announcement (RFC 5227) claiming `ip` after probing for it, which is a request with `ip` as both the sender and target
+pub fn arp_announce<T: Into<Ipv4Addr>>(mac: MacAddr, ip: T) -> Packet {
+    let ip = ip.into();
+    arp(REQUEST, mac, ip, MacAddr::ZERO, ip, MacAddr::BROADCAST)
+}
+
+fn arp(opcode: u16, sender_mac: MacAddr, sender_ip: Ipv4Addr, target_mac: MacAddr, target_ip: Ipv4Addr, eth_dst: MacAddr) -> Packet {
+    let mut arp = ArpHeader::new(sender_mac, sender_ip.octets(), target_mac, target_ip.octets());
+    arp.set_opcode(opcode);
+    EthernetHeader::new(sender_mac, eth_dst, 0) / arp
+}
+
+/// The solicited node multicast address for `ip` (ff02::1:ff00:0/104 with the low 24 bits of `ip`), which solicitations for it are sent to
+pub fn solicited_node_address(ip: Ipv6Addr) -> Ipv6Addr {
+    let o = ip.octets();
+    Ipv6Addr::from([0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0xff, o[13], o[14], o[15]])
+}
+
+/// A neighbor solicitation from `sender_mac` and `sender_ip` asking for the link layer address of `target`,
+/// sent to the solicited node multicast address of `target`
+pub fn neighbor_solicitation(sender_mac: MacAddr, sender_ip: Ipv6Addr, target: Ipv6Addr) -> Packet {
+    let mut payload = target.octets().to_vec();
+    payload.extend_from_slice(&link_address_option(OPTION_SOURCE_LINK_ADDRESS, sender_mac));
+    ndp(NEIGHBOR_SOLICITATION, 0, sender_mac, sender_ip, multicast(solicited_node_address(target)), payload)
+}
+
+/// A neighbor solicitation for duplicate address detection, checking whether anyone else is using `target` before taking it.
+/// Like an ARP probe it comes from the unspecified address, and so has no source link layer address option
+pub fn duplicate_address_probe(mac: MacAddr, target: Ipv6Addr) -> Packet {
+    ndp(NEIGHBOR_SOLICITATION, 0, mac, Ipv6Addr::UNSPECIFIED, multicast(solicited_node_address(target)), target.octets().to_vec())
+}
+
+/// The solicited advertisement answering the neighbor solicitation `solicitation`, saying its target address is at `mac`.
+/// Returns `None` if `solicitation` isn't a neighbor solicitation in an ethernet frame, or is a duplicate address probe,
+/// which is answered with an `unsolicited_neighbor_advertisement` instead
+pub fn neighbor_advertisement(solicitation: &Packet, mac: MacAddr) -> Option<Packet> {
+    let eth = solicitation.get_eth_header()?;
+    let ip = solicitation.get_ip_header()?;
+    let icmp = solicitation.get_icmp_header()?;
+    let payload = solicitation.get_payload();
+    if *icmp.get_msg_type() != NEIGHBOR_SOLICITATION || payload.len() < 16 {
+        return None;
+    }
+    let asker = match ip.get_src_ip() {
+        IpAddr::V6(asker) if !asker.is_unspecified() => *asker,
+        _ => return None,
+    };
+    let mut target = [0u8; 16];
+    target.copy_from_slice(&payload[..16]);
+    Some(advertisement(NA_SOLICITED | NA_OVERRIDE, mac, Ipv6Addr::from(target), (*eth.get_src_mac(), asker)))
+}
+
+/// An unsolicited neighbor advertisement, telling every node on the link that `ip` is at `mac`, the IPv6 version of `gratuitous_arp`
+pub fn unsolicited_neighbor_advertisement(mac: MacAddr, ip: Ipv6Addr) -> Packet {
+    advertisement(NA_OVERRIDE, mac, ip, multicast(ALL_NODES))
+}
+
+fn advertisement(flags: u16, mac: MacAddr, target: Ipv6Addr, dst: (MacAddr, Ipv6Addr)) -> Packet {
+    let mut payload = target.octets().to_vec();
+    payload.extend_from_slice(&link_address_option(OPTION_TARGET_LINK_ADDRESS, mac));
+    ndp(NEIGHBOR_ADVERTISEMENT, flags, mac, target, dst, payload)
+}
+
+/// the ethernet and IPv6 destination addresses of a message to the multicast group `group`
+fn multicast(group: Ipv6Addr) -> (MacAddr, Ipv6Addr) {
+    (MacAddr::from_ipv6_multicast(group).unwrap(), group)
+}
+
+/// a neighbor discovery message from `src_mac` and `src_ip` to the ethernet and IPv6 addresses `dst`. neighbor discovery messages
+/// are always sent with a hop limit of 255, so that they can't come from off the link
+fn ndp(msg_type: u8, flags: u16, src_mac: MacAddr, src_ip: Ipv6Addr, dst: (MacAddr, Ipv6Addr), payload: Vec<u8>) -> Packet {
+    let mut ip = IpHeader::new(src_ip, dst.1, Protocol::ICMP);
+    ip.set_ttl(255);
+    EthernetHeader::new(src_mac, dst.0, 0) / ip / IcmpHeader::new(msg_type, 0, flags, 0) / payload
+}
+
+/// a source or target link layer address option, whose length is in units of 8 bytes
+fn link_address_option(option_type: u8, mac: MacAddr) -> [u8; 8] {
+    let m = mac.octets();
+    [option_type, 1, m[0], m[1], m[2], m[3], m[4], m[5]]
+}
@@ -0,0 +1,141 @@
+use super::{Packet, Protocol};
+
+/// A header field which is normally calculated for you whenever the packet changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ComputedField {
+    /// the header checksum of IPv4, or the checksum of TCP, UDP or ICMP
+    Checksum,
+    /// the total length of IPv4, the payload length of IPv6, the length of UDP,
+    /// or the length of an 802.3 frame in the type field of an ethernet header or VLAN tag which is followed by LLC
+    Length,
+    /// the IHL of IPv4 or the data offset of TCP, in 32 bit words
+    HeaderLength,
+}
+
+impl ComputedField {
+    /// the computed field with the given dissection name, e.g. `ComputedField::Checksum` for `ip.checksum`
+    pub(crate) fn from_field_name(name: &str) -> Option<Self> {
+        match name {
+            "ip.checksum" | "tcp.checksum" | "udp.checksum" | "icmp.checksum" => Some(ComputedField::Checksum),
+            "ip.len" | "ipv6.plen" | "udp.length" | "eth.len" | "vlan.len" => Some(ComputedField::Length),
+            "ip.hdr_len" | "tcp.hdr_len" => Some(ComputedField::HeaderLength),
+            _ => None,
+        }
+    }
+}
+
+/// the values a layer's computed fields are fixed at, instead of being calculated
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Overrides {
+    pub(crate) checksum: Option<u16>,
+    pub(crate) length: Option<u16>,
+    pub(crate) header_length: Option<u16>,
+}
+
+impl Overrides {
+    fn slot(&mut self, field: ComputedField) -> &mut Option<u16> {
+        match field {
+            ComputedField::Checksum => &mut self.checksum,
+            ComputedField::Length => &mut self.length,
+            ComputedField::HeaderLength => &mut self.header_length,
+        }
+    }
+}
+
+impl Packet {
+    /// Fixes a field of the layer at position `index` which would otherwise be calculated, so that packets with deliberately wrong
+    /// checksums or lengths can be built. The other computed fields are still calculated as normal, and include the overridden value,
+    /// e.g. the IPv4 header checksum is calculated over an overridden total length. Header lengths only keep their lowest 4 bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no layer at `index`, or if its header doesn't have the field (e.g. ethernet has no checksum,
+    /// and IPv6 has neither a checksum nor a header length)
+    pub fn set_override(&mut self, index: usize, field: ComputedField, value: u16) {
+        assert!(self.has_computed_field(index, field), "layer {} has no {:?} field", index, field);
+        let value = if field == ComputedField::HeaderLength { value & 0x0f } else { value };
+        *self.layers[index].overrides.slot(field) = Some(value);
+        self.finalize();
+    }
+
+    /// Returns the value a computed field of the layer at position `index` has been fixed at, if it has been
+    pub fn get_override(&self, index: usize, field: ComputedField) -> Option<u16> {
+        let mut overrides = self.layers.get(index)?.overrides;
+        *overrides.slot(field)
+    }
+
+    /// Goes back to calculating a field of the layer at position `index`, if it had been overridden
+    pub fn clear_override(&mut self, index: usize, field: ComputedField) {
+        if let Some(layer) = self.layers.get_mut(index) {
+            *layer.overrides.slot(field) = None;
+            self.finalize();
+        }
+    }
+
+    /// Fixes every computed field of every layer, and the ethernet FCS, at the value it has now, so that changing the packet afterwards
+    /// leaves them alone. `Packet::parse_exact` does this to keep the captured values
+    pub fn pin_computed_fields(&mut self) {
+        for index in 0..self.layers.len() {
+            for field in [ComputedField::Checksum, ComputedField::Length, ComputedField::HeaderLength] {
+                if let Some(value) = self.computed_field_value(index, field) {
+                    *self.layers[index].overrides.slot(field) = Some(value);
+                }
+            }
+        }
+        self.trailer.fcs_override = self.get_fcs();
+        self.finalize();
+    }
+
+    /// Goes back to calculating every field of every layer, and the ethernet FCS
+    pub fn clear_overrides(&mut self) {
+        for layer in self.layers.iter_mut() {
+            layer.overrides = Overrides::default();
+        }
+        self.trailer.fcs_override = None;
+        self.finalize();
+    }
+
+    /// the value the computed field currently has in the header at position `index`, if the header has that field
+    pub(crate) fn computed_field_value(&self, index: usize, field: ComputedField) -> Option<u16> {
+        if !self.has_computed_field(index, field) {
+            return None;
+        }
+        let layer = self.layers[index];
+        let data = &self.buffer[layer.offset..(layer.offset + layer.length)];
+        let u16_at = |position: usize| u16::from_be_bytes([data[position], data[position + 1]]);
+        let value = match (layer.proto, field) {
+            (Protocol::IP, ComputedField::Length) if super::is_ipv6(&layer, &self.buffer) => u16_at(4),
+            (Protocol::IP, ComputedField::Length) => u16_at(2),
+            (Protocol::IP, ComputedField::Checksum) => u16_at(10),
+            (Protocol::IP, ComputedField::HeaderLength) => (data[0] & 0x0f) as u16,
+            (Protocol::TCP, ComputedField::Checksum) => u16_at(16),
+            (Protocol::TCP, ComputedField::HeaderLength) => (data[12] >> 4) as u16,
+            (Protocol::UDP, ComputedField::Length) => u16_at(4),
+            (Protocol::UDP, ComputedField::Checksum) => u16_at(6),
+            (Protocol::ICMP, ComputedField::Checksum) => u16_at(2),
+            (Protocol::ETH, ComputedField::Length) | (Protocol::VLAN, ComputedField::Length) => u16_at(layer.length - 2),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// whether the header at position `index` has the given computed field
+    pub fn has_computed_field(&self, index: usize, field: ComputedField) -> bool {
+        let layer = match self.layers.get(index) {
+            Some(layer) => layer,
+            None => return false,
+        };
+        match (layer.proto, field) {
+            (Protocol::IP, ComputedField::Length) => true,
+            (Protocol::IP, _) => !super::is_ipv6(layer, &self.buffer),
+            (Protocol::TCP, ComputedField::Checksum) | (Protocol::TCP, ComputedField::HeaderLength) => true,
+            (Protocol::UDP, ComputedField::Checksum) | (Protocol::UDP, ComputedField::Length) => true,
+            (Protocol::ICMP, ComputedField::Checksum) => true,
+            (Protocol::ETH, ComputedField::Length) | (Protocol::VLAN, ComputedField::Length) => {
+                self.layers.get(index + 1).is_some_and(|next| next.proto == Protocol::LLC)
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,579 @@
+use super::{ethernet_fcs, ethertype_numbers, protocol_numbers, ComputedField, Layer, Overrides, Packet, ParseError, Protocol};
+use super::trailer::MIN_FRAME_LEN;
+use crate::headers::{
+    ArpHeader, EthernetHeader, Header, IcmpHeader, IpHeader, LlcHeader, LldpHeader, MplsHeader, PwControlWordHeader, SnapHeader, TcpHeader, UdpHeader,
+    VlanHeader,
+};
+use crate::headers::{OUI_ETHERTYPE, SAP_SNAP};
+use std::fmt;
+
+/// The result of parsing a packet which may have been cut short, e.g. by the snaplen of a capture
+#[derive(Debug, Clone)]
+pub struct PartialPacket {
+    /// the layers which were captured in full, and whatever was captured of the payload.
+    /// the length and checksum fields are fixed at their captured values, as they can't be recalculated without the missing data
+    pub packet: Packet,
+    /// what was cut short, if anything
+    pub truncated: Option<Truncated>,
+    /// the number of bytes which were captured
+    pub captured_length: usize,
+    /// the length of the packet on the wire, going by the 802.3 length of an LLC frame or the length field of the outermost IP header.
+    /// this is the same as `captured_length` when there is no length to go by
+    pub original_length: usize,
+}
+
+/// The part of a packet which was cut short
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Truncated {
+    /// the header which would have been at position `index` in the layer stack was cut off part way through.
+    /// `data` holds the part of it which was captured, and the packet has no payload
+    Header { index: usize, proto: Protocol, data: Vec<u8> },
+    /// every header was captured, but the payload is shorter than the length fields say it should be
+    Payload,
+}
+
+impl PartialPacket {
+    /// whether any of the packet is missing
+    pub fn is_truncated(&self) -> bool {
+        self.truncated.is_some()
+    }
+}
+
+/// A check on whether a packet conforms to its protocols, which `ParseConfig` can make while parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParseCheck {
+    /// IP headers are the version their ethertype says (either after an MPLS label stack), and IPv4 and TCP headers say they are
+    /// at least 20 bytes long
+    VersionAndHeaderLength,
+    /// the IP, UDP and 802.3 length fields match the amount of data, and no header is cut off
+    LengthFields,
+    /// the reserved bits of IPv4 and TCP headers are zero
+    ReservedBits,
+    /// the IPv4, TCP, UDP and ICMP checksums and the ethernet FCS are right. a UDP checksum of 0, meaning no checksum, is allowed
+    Checksums,
+    /// every ethertype and IP protocol number is one which can be parsed
+    KnownProtocols,
+}
+
+impl ParseCheck {
+    /// every check
+    pub const ALL: [ParseCheck; 5] = [
+        ParseCheck::KnownProtocols,
+        ParseCheck::VersionAndHeaderLength,
+        ParseCheck::LengthFields,
+        ParseCheck::ReservedBits,
+        ParseCheck::Checksums,
+    ];
+}
+
+/// How `Packet::parse_with` treats packets which don't conform to their protocols
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseConfig {
+    strict: bool,
+    checks: Vec<ParseCheck>,
+    exact: bool,
+    fcs: Option<bool>,
+}
+
+impl ParseConfig {
+    /// makes every check, and fails with `ParseError::FailedCheck` as soon as one of them fails
+    pub fn strict() -> Self {
+        Self { strict: true, checks: ParseCheck::ALL.to_vec(), exact: false, fcs: None }
+    }
+
+    /// makes every check, and returns the ones which fail as warnings. a header which is cut off,
+    /// and anything after an ethertype or IP protocol which can't be parsed, becomes payload instead of being an error
+    pub fn lenient() -> Self {
+        Self { strict: false, checks: ParseCheck::ALL.to_vec(), exact: false, fcs: None }
+    }
+
+    /// makes `check` as well, if it had been skipped
+    pub fn check(mut self, check: ParseCheck) -> Self {
+        if !self.checks.contains(&check) {
+            self.checks.push(check);
+        }
+        self
+    }
+
+    /// leaves out `check`. anything it would have found is let through without a warning
+    pub fn skip(mut self, check: ParseCheck) -> Self {
+        self.checks.retain(|c| *c != check);
+        self
+    }
+
+    /// keeps the length and checksum fields at their captured values, the same as `Packet::parse_exact`
+    pub fn exact(mut self) -> Self {
+        self.exact = true;
+        self
+    }
+
+    /// says whether ethernet frames end with an FCS, rather than taking the last 4 bytes to be one only when they match.
+    /// a frame which is said to have one has its FCS checked with `ParseCheck::Checksums`
+    pub fn fcs(mut self, present: bool) -> Self {
+        self.fcs = Some(present);
+        self
+    }
+
+    /// whether `check` is made
+    pub fn is_checked(&self, check: ParseCheck) -> bool {
+        self.checks.contains(&check)
+    }
+
+    /// whether a failed check fails the parse
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+}
+
+/// parses leniently
+impl Default for ParseConfig {
+    fn default() -> Self {
+        Self::lenient()
+    }
+}
+
+/// A check which failed while parsing leniently
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    pub check: ParseCheck,
+    /// the position in the layer stack of the header which failed the check
+    pub layer: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "layer {}: {}", self.layer, self.message)
+    }
+}
+
+impl Packet {
+    /// Try to create a `Packet` from raw packet data and populate it with the values in the given data packet.
+    ///
+    /// The headers keep the bytes they were captured with, including any options and reserved bits,
+    /// but their length and checksum fields are recalculated, so a packet with a bad checksum comes out with a good one.
+    /// Use `parse_exact` to keep those as they were too.
+    ///
+    /// An ethernet frame which ends with a matching FCS keeps it, and padding after the end of the IP packet, ARP header, LLDPDU or 802.3 frame in a minimum length frame
+    /// is kept as padding rather than becoming payload (see `get_trailer`)
+    pub fn parse(raw_data: &[u8]) -> Result<Self, ParseError> {
+        let mut packet = Parser::new(None, false).parse(raw_data)?.packet;
+        packet.finalize();
+        Ok(packet)
+    }
+
+    /// Parses raw packet data the same way as `parse`, but with every length and checksum field fixed at its captured value
+    /// (see `set_override`), so that writing the packet out again reproduces `raw_data` byte for byte.
+    ///
+    /// Changing a header afterwards only changes the fields which were changed; the lengths and checksums which depend on them
+    /// keep their captured values until they are recalculated with `clear_override` or `clear_overrides`
+    pub fn parse_exact(raw_data: &[u8]) -> Result<Self, ParseError> {
+        let mut packet = Parser::new(None, false).parse(raw_data)?.packet;
+        packet.pin_computed_fields();
+        Ok(packet)
+    }
+
+    /// Parses as much of a packet as was captured. Rather than failing with `ParseError::InvalidLength` when a header is cut off,
+    /// this returns the layers before it along with the captured part of the cut off header, and compares the captured length
+    /// with the length the IP header says the packet should have, so a short payload is noticed too.
+    /// Other errors, such as an unsupported ethertype, are still returned
+    pub fn parse_partial(raw_data: &[u8]) -> Result<PartialPacket, ParseError> {
+        let mut parser = Parser::new(None, true).parse(raw_data)?;
+        parser.packet.pin_computed_fields();
+        let captured_length = raw_data.len();
+        let original_length = parser.claimed_length().map_or(captured_length, |length| length.max(captured_length));
+        let truncated = match parser.truncated {
+            None if original_length > captured_length => Some(Truncated::Payload),
+            truncated => truncated,
+        };
+        Ok(PartialPacket { packet: parser.packet, truncated, captured_length, original_length })
+    }
+
+    /// Parses raw packet data, making the checks in `config` on the way. A strict config fails on the first check which fails,
+    /// while a lenient one parses as much as it can and returns the failed checks alongside the packet
+    pub fn parse_with(raw_data: &[u8], config: &ParseConfig) -> Result<(Self, Vec<ParseWarning>), ParseError> {
+        let mut parser = Parser::new(Some(config), !config.strict).parse(raw_data)?;
+        if let Some(Truncated::Header { proto, data, .. }) = parser.truncated.take() {
+            let message = format!("the {} header is cut off after {} bytes", proto, data.len());
+            parser.warn(ParseCheck::LengthFields, parser.packet.layers.len(), message)?;
+            parser.packet.payload = data;
+        }
+        parser.check_fields()?;
+        let mut packet = parser.packet;
+        if config.exact {
+            packet.pin_computed_fields();
+        } else {
+            packet.finalize();
+        }
+        Ok((packet, parser.warnings))
+    }
+}
+
+/// splits raw data into layers and payload, leaving every byte as it was
+struct Parser<'a> {
+    packet: Packet,
+    /// the checks to make, or `None` to fail on anything which can't be parsed without making any checks
+    config: Option<&'a ParseConfig>,
+    /// whether a header which was cut off ends the packet, rather than being an error
+    partial: bool,
+    truncated: Option<Truncated>,
+    warnings: Vec<ParseWarning>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(config: Option<&'a ParseConfig>, partial: bool) -> Self {
+        Self { packet: Packet::new_empty(), config, partial, truncated: None, warnings: Vec::new() }
+    }
+
+    fn parse(mut self, raw_data: &[u8]) -> Result<Self, ParseError> {
+        if raw_data.is_empty() {
+            return Err(ParseError::InvalidLength);
+        }
+        if raw_data[0] >> 4 == 4 {
+            self.ip(raw_data)?;
+        } else {
+            self.ethernet(raw_data)?;
+        }
+        // everything after the last header we know how to parse is payload, apart from the padding and FCS of an ethernet frame
+        if self.truncated.is_none() {
+            let end = self.trailer(raw_data)?;
+            self.packet.payload = raw_data[self.packet.buffer.len()..end].to_vec();
+        }
+        Ok(self)
+    }
+
+    /// splits the padding and FCS off the end of an ethernet frame, returning where the payload ends.
+    /// the FCS is looked for when the config doesn't say whether there is one, and the bytes after the end of the IP packet are padding
+    /// if the frame is no longer than the minimum length, which is the only time it's padded
+    fn trailer(&mut self, raw_data: &[u8]) -> Result<usize, ParseError> {
+        let headers_len = self.packet.buffer.len();
+        let mut end = raw_data.len();
+        if self.packet.layers.first().map(|layer| layer.proto) != Some(Protocol::ETH) || end < headers_len + 4 {
+            return Ok(end);
+        }
+        let captured_fcs = u32::from_le_bytes([raw_data[end - 4], raw_data[end - 3], raw_data[end - 2], raw_data[end - 1]]);
+        let calculated_fcs = ethernet_fcs(&raw_data[..(end - 4)]);
+        let fcs = self.config.and_then(|config| config.fcs).unwrap_or(captured_fcs == calculated_fcs);
+        if fcs {
+            end -= 4;
+            if captured_fcs != calculated_fcs {
+                let message = format!("eth.fcs is 0x{:08x}, should be 0x{:08x}", captured_fcs, calculated_fcs);
+                self.warn(ParseCheck::Checksums, 0, message)?;
+            }
+        }
+        let mut padding = Vec::new();
+        if let Some(ip_end) = self.claimed_length() {
+            if end <= MIN_FRAME_LEN && ip_end >= headers_len && ip_end < end {
+                padding = raw_data[ip_end..end].to_vec();
+                end = ip_end;
+            }
+        }
+        let trailer = &mut self.packet.trailer;
+        trailer.padding = padding;
+        trailer.fcs = fcs;
+        trailer.bytes = raw_data[end..].to_vec();
+        Ok(end)
+    }
+
+    fn ip(&mut self, raw_data: &[u8]) -> Result<(), ParseError> {
+        let ip_header: Box<IpHeader> = match self.layer(raw_data, Protocol::IP)? {
+            Some(header) => header,
+            None => return Ok(()),
+        };
+        let next_protocol = *ip_header.get_next_protocol();
+        let data = &raw_data[ip_header.get_length()..];
+        match next_protocol {
+            protocol_numbers::IPPROTO_ETHERIP => {
+                self.layer::<EthernetHeader>(data, Protocol::ETH)?; // Ethernet in ip encapsulation
+            },
+            protocol_numbers::IPPROTO_ICMP | protocol_numbers::IPPROTO_ICMPV6 => {
+                self.layer::<IcmpHeader>(data, Protocol::ICMP)?;
+            },
+            protocol_numbers::IPPROTO_TCP => {
+                self.layer::<TcpHeader>(data, Protocol::TCP)?;
+            },
+            protocol_numbers::IPPROTO_UDP => {
+                self.layer::<UdpHeader>(data, Protocol::UDP)?;
+            },
+            protocol_numbers::IPPROTO_IPV4 => {
+                self.layer::<IpHeader>(data, Protocol::IP)?;
+            },
+            _ => return self.unknown_protocol(ParseError::InvalidFormat, format!("ip.proto {} can't be parsed", next_protocol)),
+        }
+        Ok(())
+    }
+
+    fn ethernet(&mut self, raw_data: &[u8]) -> Result<(), ParseError> {
+        let hdr: Box<EthernetHeader> = match self.layer(raw_data, Protocol::ETH)? {
+            Some(header) => header,
+            None => return Ok(()),
+        };
+        self.ethertype(*hdr.get_eth_type(), "eth.type", &raw_data[hdr.get_length()..])
+    }
+
+    /// parses what comes after an ethernet header, VLAN tag or SNAP header with the ethertype `et`, which is in the field called `field`.
+    /// VLAN tags are parsed through to the ethertype of the innermost one, and a type field which is an 802.3 length is followed by LLC
+    fn ethertype(&mut self, et: u16, field: &str, raw_data: &[u8]) -> Result<(), ParseError> {
+        let message = format!("{} 0x{:04x} can't be parsed", field, et);
+        match et {
+            0..=ethertype_numbers::MAX_8023_LENGTH => {
+                self.llc(raw_data)?;
+            },
+            ethertype_numbers::ETHERTYPE_IPV4 |
+            ethertype_numbers::ETHERTYPE_IPV6 => {
+                self.ip(raw_data)?;
+            },
+            ethertype_numbers::ETHERTYPE_VLAN |
+            ethertype_numbers::ETHERTYPE_QINQ |
+            ethertype_numbers::ETHERTYPE_QINQ_LEGACY => {
+                if let Some(tag) = self.layer::<VlanHeader>(raw_data, Protocol::VLAN)? {
+                    self.ethertype(*tag.get_eth_type(), "vlan.etype", &raw_data[tag.get_length()..])?;
+                }
+            },
+            ethertype_numbers::ETHERTYPE_ARP |
+            ethertype_numbers::ETHERTYPE_RARP => {
+                self.layer::<ArpHeader>(raw_data, Protocol::ARP)?;
+            },
+            ethertype_numbers::ETHERTYPE_LLDP => {
+                self.layer::<LldpHeader>(raw_data, Protocol::LLDP)?;
+            },
+            ethertype_numbers::ETHERTYPE_MPLS_UNICAST |
+            ethertype_numbers::ETHERTYPE_MPLS_MULTICAST => {
+                self.mpls(raw_data)?;
+            },
+            _ => return self.unknown_protocol(ParseError::InvalidFormat, message)
+        }
+        Ok(())
+    }
+
+    /// parses an MPLS label stack down to the bottom label. nothing says what comes after that, so it's guessed from the first 4 bits:
+    /// 4 is IPv4, 6 is IPv6 and 0 is the control word of a pseudowire, which is taken to be carrying an ethernet frame.
+    /// anything else is payload
+    fn mpls(&mut self, raw_data: &[u8]) -> Result<(), ParseError> {
+        let mut data = raw_data;
+        loop {
+            let label: Box<MplsHeader> = match self.layer(data, Protocol::MPLS)? {
+                Some(header) => header,
+                None => return Ok(()),
+            };
+            data = &data[label.get_length()..];
+            if *label.get_bottom_of_stack() {
+                break;
+            }
+        }
+        match data.first().map(|first| first >> 4) {
+            Some(4) | Some(6) => self.ip(data),
+            Some(0) => {
+                if self.layer::<PwControlWordHeader>(data, Protocol::PWCW)?.is_some() && data.len() > 4 {
+                    self.ethernet(&data[4..])?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// parses an LLC header, and the SNAP header after it if there is one. whatever comes after any other SAP, or a SNAP header
+    /// which isn't for an ethertype, is payload
+    fn llc(&mut self, raw_data: &[u8]) -> Result<(), ParseError> {
+        let llc: Box<LlcHeader> = match self.layer(raw_data, Protocol::LLC)? {
+            Some(header) => header,
+            None => return Ok(()),
+        };
+        if *llc.get_dsap() != SAP_SNAP || *llc.get_ssap() != SAP_SNAP {
+            return Ok(());
+        }
+        let data = &raw_data[llc.get_length()..];
+        if let Some(snap) = self.layer::<SnapHeader>(data, Protocol::SNAP)? {
+            let protocol_id = *snap.get_protocol_id();
+            if *snap.get_oui() == OUI_ETHERTYPE && !ethertype_numbers::is_length(protocol_id) {
+                self.ethertype(protocol_id, "llc.pid", &data[snap.get_length()..])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// called when the last layer names a protocol which can't be parsed. without a config this is the error `error`,
+    /// otherwise it's a failed `KnownProtocols` check, and the rest of the data is payload if parsing carries on
+    fn unknown_protocol(&mut self, error: ParseError, message: String) -> Result<(), ParseError> {
+        if self.config.is_none() {
+            return Err(error);
+        }
+        self.warn(ParseCheck::KnownProtocols, self.packet.layers.len() - 1, message)
+    }
+
+    /// records that `check` failed for the layer at position `layer`, which is an error when parsing strictly
+    fn warn(&mut self, check: ParseCheck, layer: usize, message: String) -> Result<(), ParseError> {
+        match self.config {
+            Some(config) if config.is_checked(check) => {
+                if config.strict {
+                    return Err(ParseError::FailedCheck(check));
+                }
+                self.warnings.push(ParseWarning { check, layer, message });
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// makes the checks on the fields of the headers which have been parsed, comparing the captured lengths and checksums
+    /// with the ones which would be calculated for the packet
+    fn check_fields(&mut self) -> Result<(), ParseError> {
+        let captured = &self.packet;
+        let mut lengths = captured.clone();
+        lengths.finalize();
+        // the checksums are calculated over the captured lengths, so that a wrong length isn't reported as a wrong checksum as well
+        let mut checksums = captured.clone();
+        checksums.pin_computed_fields();
+        for index in 0..checksums.layers.len() {
+            checksums.clear_override(index, ComputedField::Checksum);
+        }
+        let mut failed = Vec::new();
+        for (index, layer) in captured.layers.iter().enumerate() {
+            let data = &captured.buffer[layer.offset..(layer.offset + layer.length)];
+            let ipv6 = super::is_ipv6(layer, &captured.buffer);
+            if let Some(version) = expected_ip_version(captured, index).filter(|_| layer.proto == Protocol::IP) {
+                if data[0] >> 4 != version {
+                    let message = format!("ip.version is {}, should be {}", data[0] >> 4, version);
+                    failed.push((ParseCheck::VersionAndHeaderLength, index, message));
+                }
+            }
+            let fields = [
+                (ComputedField::HeaderLength, ParseCheck::VersionAndHeaderLength, &lengths),
+                (ComputedField::Length, ParseCheck::LengthFields, &lengths),
+                (ComputedField::Checksum, ParseCheck::Checksums, &checksums),
+            ];
+            for (field, check, calculated) in fields.iter() {
+                let values = (captured.computed_field_value(index, *field), calculated.computed_field_value(index, *field));
+                if let (Some(actual), Some(expected)) = values {
+                    let no_udp_checksum = layer.proto == Protocol::UDP && *field == ComputedField::Checksum && actual == 0;
+                    if actual != expected && !no_udp_checksum {
+                        let message = format!(
+                            "{} is {}, should be {}",
+                            field_name(layer.proto, *field, ipv6),
+                            show_value(*field, actual),
+                            show_value(*field, expected)
+                        );
+                        failed.push((*check, index, message));
+                    }
+                }
+            }
+            match layer.proto {
+                Protocol::IP if !ipv6 && data[6] & 0x80 != 0 => {
+                    failed.push((ParseCheck::ReservedBits, index, String::from("ip.flags.rb is set")));
+                }
+                Protocol::TCP if data[12] & 0x0e != 0 => {
+                    failed.push((ParseCheck::ReservedBits, index, String::from("tcp.flags.res is set")));
+                }
+                _ => {}
+            }
+        }
+        for (check, index, message) in failed {
+            self.warn(check, index, message)?;
+        }
+        Ok(())
+    }
+
+    /// parses a header of type `H` from the start of `raw_data`, and appends the bytes it was parsed from as a new layer.
+    /// when parsing a partial packet, a header which was cut off is kept to one side and `None` is returned
+    fn layer<H: Header>(&mut self, raw_data: &[u8], proto: Protocol) -> Result<Option<Box<H>>, ParseError> {
+        let parsed = H::parse(raw_data).and_then(|header| {
+            if raw_data.len() < header.get_length() {
+                Err(ParseError::InvalidLength)
+            } else {
+                Ok(header)
+            }
+        });
+        let header = match parsed {
+            Ok(header) => header,
+            Err(ParseError::InvalidLength) if self.partial => {
+                let index = self.packet.layers.len();
+                self.truncated = Some(Truncated::Header { index, proto, data: raw_data.to_vec() });
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        let length = header.get_length();
+        let offset = self.packet.buffer.len();
+        self.packet.buffer.extend_from_slice(&raw_data[..length]);
+        self.packet.layers.push(Layer { proto, offset, length, overrides: Overrides::default() });
+        Ok(Some(header))
+    }
+
+    /// the length of the whole packet according to the 802.3 length of an LLC frame, or else the outermost IP header,
+    /// which may be the header that was cut off
+    fn claimed_length(&self) -> Option<usize> {
+        let packet = &self.packet;
+        if let Some(index) = packet.layers.iter().position(|layer| layer.proto == Protocol::LLC) {
+            let field = packet.layers[index].offset - 2;
+            let length = u16::from_be_bytes([packet.buffer[field], packet.buffer[field + 1]]) as usize;
+            return Some(packet.layers[index].offset + length);
+        }
+        // nothing comes after an ARP header or LLDPDU
+        if let Some(last) = packet.layers.iter().find(|layer| layer.proto == Protocol::ARP || layer.proto == Protocol::LLDP) {
+            return Some(last.offset + last.length);
+        }
+        let ip = packet.layers.iter().find(|layer| layer.proto == Protocol::IP);
+        match (ip, &self.truncated) {
+            (Some(layer), _) => ip_length(&packet.buffer[layer.offset..]).map(|length| layer.offset + length),
+            (None, Some(Truncated::Header { proto: Protocol::IP, data, .. })) => {
+                ip_length(data).map(|length| packet.buffer.len() + length)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// the IP version the ethertype before the IP header at position `index` says it is, or 4 after IP in IP.
+/// an MPLS label stack doesn't say, so the version there is what the IP header was guessed from, and there's nothing to check
+fn expected_ip_version(packet: &Packet, index: usize) -> Option<u8> {
+    let previous = match index.checked_sub(1) {
+        Some(previous) => packet.layers[previous],
+        None => return Some(4),
+    };
+    match previous.proto {
+        Protocol::MPLS => None,
+        // the ethertype is the last field of an ethernet header, VLAN tag and SNAP header
+        Protocol::ETH | Protocol::VLAN | Protocol::SNAP => {
+            let end = previous.offset + previous.length;
+            let ethertype = u16::from_be_bytes([packet.buffer[end - 2], packet.buffer[end - 1]]);
+            Some(if ethertype == ethertype_numbers::ETHERTYPE_IPV6 { 6 } else { 4 })
+        }
+        _ => Some(4),
+    }
+}
+
+/// the wireshark style name of a computed field
+fn field_name(proto: Protocol, field: ComputedField, ipv6: bool) -> &'static str {
+    match (proto, field) {
+        (Protocol::IP, ComputedField::Length) if ipv6 => "ipv6.plen",
+        (Protocol::IP, ComputedField::Length) => "ip.len",
+        (Protocol::IP, ComputedField::Checksum) => "ip.checksum",
+        (Protocol::IP, ComputedField::HeaderLength) => "ip.hdr_len",
+        (Protocol::TCP, ComputedField::HeaderLength) => "tcp.hdr_len",
+        (Protocol::TCP, _) => "tcp.checksum",
+        (Protocol::UDP, ComputedField::Length) => "udp.length",
+        (Protocol::UDP, _) => "udp.checksum",
+        (Protocol::ETH, _) => "eth.len",
+        (Protocol::VLAN, _) => "vlan.len",
+        _ => "icmp.checksum",
+    }
+}
+
+fn show_value(field: ComputedField, value: u16) -> String {
+    match field {
+        ComputedField::Checksum => format!("0x{:04x}", value),
+        ComputedField::Length => value.to_string(),
+        ComputedField::HeaderLength => format!("{} bytes", value * 4),
+    }
+}
+
+/// the length of an IP packet, including its header, from the start of its header, if enough of the header is there to say
+fn ip_length(header: &[u8]) -> Option<usize> {
+    let u16_at = |position: usize| header.get(position..(position + 2)).map(|b| u16::from_be_bytes([b[0], b[1]]) as usize);
+    match header.first()? >> 4 {
+        4 => u16_at(2),
+        6 => u16_at(4).map(|payload_len| payload_len + 40),
+        _ => None,
+    }
+}
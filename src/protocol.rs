@@ -6,13 +6,20 @@ use super::{
     IcmpHeader,
     IpHeader,
     TcpHeader,
-    UdpHeader
+    UdpHeader,
+    VlanHeader,
+    LlcHeader,
+    SnapHeader,
+    LldpHeader,
+    MplsHeader,
+    PwControlWordHeader
 };
 use std::fmt;
 
 type ProtocolNumber = u8;
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Protocol {
     ETH,
     ARP,
@@ -20,10 +27,17 @@ pub enum Protocol {
     TCP,
     UDP,
     IP,
+    VLAN,
+    LLC,
+    SNAP,
+    LLDP,
+    MPLS,
+    /// the control word of a pseudowire, after the MPLS label stack
+    PWCW,
 }
 
 impl Protocol {
-    pub fn min_header_len(&self) -> u8 {
+    pub fn min_header_len(&self) -> usize {
         match self {
             Self::ETH => EthernetHeader::get_min_length(),
             Self::ARP => ArpHeader::get_min_length(),
@@ -31,6 +45,12 @@ impl Protocol {
             Self::TCP => TcpHeader::get_min_length(),
             Self::UDP => UdpHeader::get_min_length(), // not yet implemented
             Self::IP => IpHeader::get_min_length(),
+            Self::VLAN => VlanHeader::get_min_length(),
+            Self::LLC => LlcHeader::get_min_length(),
+            Self::SNAP => SnapHeader::get_min_length(),
+            Self::LLDP => LldpHeader::get_min_length(),
+            Self::MPLS => MplsHeader::get_min_length(),
+            Self::PWCW => PwControlWordHeader::get_min_length(),
         }
     }
 
@@ -43,6 +63,10 @@ impl Protocol {
             Self::UDP => protocol_numbers::IPPROTO_UDP,
             Self::IP => protocol_numbers::IPPROTO_IPV4,
             Self::ARP => panic!("ARP does not have an assigned ip protocol number"),
+            Self::VLAN => panic!("VLAN tags do not have an assigned ip protocol number"),
+            Self::LLC | Self::SNAP => panic!("LLC and SNAP do not have an assigned ip protocol number"),
+            Self::LLDP => panic!("LLDP does not have an assigned ip protocol number"),
+            Self::MPLS | Self::PWCW => panic!("MPLS in IP is not supported"),
         }
     }
 }
@@ -69,6 +93,12 @@ impl fmt::Display for Protocol {
             Protocol::TCP => write!(f, "TCP"),
             Protocol::UDP => write!(f, "UDP"),
             Protocol::IP => write!(f, "IP"),
+            Protocol::VLAN => write!(f, "VLAN"),
+            Protocol::LLC => write!(f, "LLC"),
+            Protocol::SNAP => write!(f, "SNAP"),
+            Protocol::LLDP => write!(f, "LLDP"),
+            Protocol::MPLS => write!(f, "MPLS"),
+            Protocol::PWCW => write!(f, "PW control word"),
         }
     }
 }
\ No newline at end of file
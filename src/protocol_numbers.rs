@@ -216,4 +216,34 @@ pub const IPPROTO_SCTP: u8        = 132;
 /// divert pseudo-protocol
 pub const IPPROTO_DIVERT: u8      = 254;
 /// raw IP packet
-pub const IPPROTO_RAW: u8         = 255;
\ No newline at end of file
+pub const IPPROTO_RAW: u8         = 255;
+/// Returns the short name of the protocol with the given number, as shown by packet analysers, for the more common protocols
+pub fn protocol_name(protocol: u8) -> Option<&'static str> {
+    Some(match protocol {
+        IPPROTO_IP => "HOPOPT",
+        IPPROTO_ICMP => "ICMP",
+        IPPROTO_IGMP => "IGMP",
+        IPPROTO_IPV4 => "IPv4",
+        IPPROTO_TCP => "TCP",
+        IPPROTO_EGP => "EGP",
+        IPPROTO_PIGP => "IGP",
+        IPPROTO_UDP => "UDP",
+        IPPROTO_IPV6 => "IPv6",
+        IPPROTO_ROUTING => "IPv6-Route",
+        IPPROTO_FRAGMENT => "IPv6-Frag",
+        IPPROTO_RSVP => "RSVP",
+        IPPROTO_GRE => "GRE",
+        IPPROTO_ESP => "ESP",
+        IPPROTO_AH => "AH",
+        IPPROTO_ICMPV6 => "ICMPv6",
+        IPPROTO_NONE => "IPv6-NoNxt",
+        IPPROTO_DSTOPTS => "IPv6-Opts",
+        IPPROTO_IGRP => "IGRP",
+        IPPROTO_OSPFIGP => "OSPF",
+        IPPROTO_ETHERIP => "EtherIP",
+        IPPROTO_PIM => "PIM",
+        IPPROTO_IPCOMP => "IPComp",
+        IPPROTO_SCTP => "SCTP",
+        _ => return None,
+    })
+}
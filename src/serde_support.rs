@@ -0,0 +1,133 @@
+//! helpers for (de)serialising raw address fields in a human readable form when the format is human readable (e.g. JSON, YAML),
+//! and as plain byte arrays otherwise
+
+use serde::{de, Deserialize, Deserializer, Serializer, Serialize};
+use crate::{MacAddr, Packet};
+use crate::headers::AnyHeader;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// mac addresses, as "01:23:45:67:89:ab"
+impl Serialize for MacAddr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            self.octets().serialize(serializer)
+        }
+    }
+}
+
+/// accepts any of the forms `MacAddr::from_str` does
+impl<'de> Deserialize<'de> for MacAddr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            s.parse().map_err(|_| de::Error::custom(format!("invalid mac address: {}", s)))
+        } else {
+            Ok(MacAddr::from(<[u8; 6]>::deserialize(deserializer)?))
+        }
+    }
+}
+
+/// ARP hardware addresses, as "01:23:45:67:89:ab" whatever their length
+pub(crate) mod hardware_address {
+    use super::*;
+    use crate::headers::hw_address_string;
+
+    pub fn serialize<S: Serializer>(address: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hw_address_string(address))
+        } else {
+            address.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        super::deserialize_address(deserializer)
+    }
+}
+
+/// ARP protocol addresses, as IPv4 or IPv6 addresses when they are 4 or 16 bytes long, and like hardware addresses otherwise
+pub(crate) mod protocol_address {
+    use super::*;
+    use crate::headers::proto_address_string;
+
+    pub fn serialize<S: Serializer>(address: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&proto_address_string(address))
+        } else {
+            address.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        super::deserialize_address(deserializer)
+    }
+}
+
+/// accepts IPv4 and IPv6 addresses, any of the forms `MacAddr::from_str` does, and colon separated hex bytes
+fn deserialize_address<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    if !deserializer.is_human_readable() {
+        return Vec::deserialize(deserializer);
+    }
+    let s = String::deserialize(deserializer)?;
+    if let Ok(ip) = s.parse::<Ipv4Addr>() {
+        return Ok(ip.octets().to_vec());
+    }
+    if let Ok(ip) = s.parse::<Ipv6Addr>() {
+        return Ok(ip.octets().to_vec());
+    }
+    if let Ok(mac) = s.parse::<MacAddr>() {
+        return Ok(mac.as_ref().to_vec());
+    }
+    if s.is_empty() {
+        return Ok(Vec::new());
+    }
+    s.split(':')
+        .map(|byte| if byte.len() == 2 { u8::from_str_radix(byte, 16).ok() } else { None })
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| de::Error::custom(format!("invalid address: {}", s)))
+}
+
+/// a `Packet` is (de)serialised as its ordered list of layers plus the payload, and how an ethernet frame ends if it isn't just the payload
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Packet")]
+struct PacketRepr {
+    layers: Vec<AnyHeader>,
+    payload: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    padding: Vec<u8>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pad_to_minimum: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    fcs: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl Serialize for Packet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PacketRepr {
+            layers: self.get_layers(),
+            payload: self.get_payload().clone(),
+            padding: self.trailer.padding.clone(),
+            pad_to_minimum: self.trailer.pad_to_minimum,
+            fcs: self.trailer.fcs,
+        }.serialize(serializer)
+    }
+}
+
+/// rebuilds the packet with `Packet::from_layers`, so the length and checksum fields, and the FCS, are recalculated
+impl<'de> Deserialize<'de> for Packet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = PacketRepr::deserialize(deserializer)?;
+        let mut packet = Packet::from_layers(repr.layers, repr.payload);
+        packet.trailer.padding = repr.padding;
+        packet.trailer.pad_to_minimum = repr.pad_to_minimum;
+        packet.trailer.fcs = repr.fcs;
+        packet.finalize();
+        Ok(packet)
+    }
+}
@@ -0,0 +1,258 @@
+//! Packet templates, where any field of any header can take its values from a generator, for sweeps and fuzzing.
+//!
+//! A template starts from an existing `Packet`, and fields are picked out by their wireshark style names (`ip.ttl`, `tcp.dstport`, ...),
+//! the same names `Packet::dissect` uses. Expanding the template is lazy: each packet is only built when the iterator reaches it,
+//! and the length and checksum fields are calculated for every one of them, the same as for any other `Packet`, unless they are
+//! templated themselves, in which case they are fixed at the generated values with `Packet::set_override`.
+//!
+//! ```text
+//! let template = Template::new(packet)
+//!     .set("ip.ttl", Generator::List(vec![1, 64, 255]))
+//!     .set("tcp.dstport", Generator::range(1, 1024));
+//! for packet in template.product() { /* 3 * 1024 packets */ }
+//! ```
+
+use super::{random_u64, ComputedField, Packet};
+use crate::dissect::Field;
+
+/// Where the values of a templated field come from. Values are truncated to the width of the field they're written to,
+/// and for fields longer than 8 bytes (addresses, payload) the value replaces the last 8 bytes of the field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Generator {
+    /// always the same value
+    Fixed(u64),
+    /// every `step`th value from `start` up to and including `end`
+    Range { start: u64, end: u64, step: u64 },
+    /// each of the values in turn
+    List(Vec<u64>),
+    /// `count` pseudo random values between `min` and `max` inclusive, which are the same every time for the same seed.
+    /// If `min` is more than `max`, the two are swapped round
+    Random { seed: u64, count: usize, min: u64, max: u64 },
+    /// `count` values counting up from `start` by `step`, wrapping around when they no longer fit in the field
+    Incrementing { start: u64, step: u64, count: usize },
+}
+
+impl Generator {
+    /// every value from `start` up to and including `end`
+    pub fn range(start: u64, end: u64) -> Self {
+        Generator::Range { start, end, step: 1 }
+    }
+
+    /// `count` pseudo random values, covering the whole width of the field
+    pub fn random(seed: u64, count: usize) -> Self {
+        Generator::Random { seed, count, min: 0, max: u64::MAX }
+    }
+
+    /// the number of values, or `None` for `Fixed`, which has the same value however many times it's asked for one
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Generator::Fixed(_) => None,
+            Generator::Range { start, end, step } => {
+                if start > end {
+                    Some(0)
+                } else {
+                    Some(((end - start) / (*step).max(1)) as usize + 1)
+                }
+            }
+            Generator::List(values) => Some(values.len()),
+            Generator::Random { count, .. } | Generator::Incrementing { count, .. } => Some(*count),
+        }
+    }
+
+    /// true if the generator has no values at all
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Returns the value at position `index`, which must be less than `len()`
+    pub fn get(&self, index: usize) -> u64 {
+        match self {
+            Generator::Fixed(value) => *value,
+            Generator::Range { start, step, .. } => start.wrapping_add((index as u64).wrapping_mul((*step).max(1))),
+            Generator::List(values) => values[index],
+            Generator::Random { seed, min, max, .. } => {
+                let value = random_u64(*seed, index as u64);
+                let (min, max) = if min <= max { (min, max) } else { (max, min) };
+                match (max - min).checked_add(1) {
+                    Some(span) => min + value % span,
+                    None => value,
+                }
+            }
+            Generator::Incrementing { start, step, .. } => start.wrapping_add(step.wrapping_mul(index as u64)),
+        }
+    }
+}
+
+/// A packet with some of its fields taken from generators
+#[derive(Debug, Clone)]
+pub struct Template {
+    base: Packet,
+    fields: Vec<(Target, Generator)>,
+}
+
+/// where the values of a templated field go
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    /// straight into the bytes of the packet
+    Bytes(Location),
+    /// a length or checksum of the layer at the given position, which would be recalculated over anything written to its bytes,
+    /// so it is overridden instead
+    Computed(usize, ComputedField),
+}
+
+/// the position of a templated field within the packet
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Location {
+    pub(crate) offset: usize,
+    pub(crate) length: usize,
+    pub(crate) bitmask: u64,
+}
+
+impl Location {
+    /// whether the field is in the padding or FCS after the payload, which `write_field` can't write to
+    pub(crate) fn is_in_trailer(&self, packet: &Packet) -> bool {
+        self.offset + self.length > packet.buffer.len() + packet.payload.len()
+    }
+}
+
+impl From<&Field> for Location {
+    fn from(field: &Field) -> Self {
+        Self { offset: field.offset, length: field.length, bitmask: field.bitmask }
+    }
+}
+
+impl Template {
+    /// Starts a template from `base`. Any field which isn't templated keeps the value it has in `base`
+    pub fn new(base: Packet) -> Self {
+        Self { base, fields: Vec::new() }
+    }
+
+    /// Takes the values of the field called `field` (e.g. `ip.ttl`) from `generator`, in the outermost layer which has that field.
+    /// Panics if none of the layers in the packet have a field with that name, or if the field is the ethernet padding or FCS
+    /// (see `set_in_layer`)
+    pub fn set(self, field: &str, generator: Generator) -> Self {
+        let layer = self.base.dissect().iter().position(|l| l.get_field(field).is_some());
+        match layer {
+            Some(index) => self.set_in_layer(index, field, generator),
+            None => panic!("there is no field called {} in the packet", field),
+        }
+    }
+
+    /// Takes the values of the field called `field` in the layer at position `index` from `generator`,
+    /// e.g. to template the inner header of IP in IP. Panics if that layer doesn't have a field with that name.
+    ///
+    /// Lengths and checksums (`ip.len`, `udp.checksum`, ...) are fixed at the generated values with `Packet::set_override`,
+    /// rather than being recalculated over them. The ethernet padding and FCS (`eth.padding` and `eth.fcs`) are worked out
+    /// from the rest of the packet whenever it changes, so they can't be templated, and also panic.
+    /// Use `Packet::set_padding` and `Packet::set_fcs_override` on the base packet instead
+    pub fn set_in_layer(mut self, index: usize, field: &str, generator: Generator) -> Self {
+        let location = self.base.dissect().get(index).and_then(|l| l.get_field(field)).map(Location::from);
+        let computed = ComputedField::from_field_name(field).filter(|computed| self.base.has_computed_field(index, *computed));
+        match (location, computed) {
+            (Some(location), _) if location.is_in_trailer(&self.base) => {
+                panic!("{} is in the padding or FCS of the packet, which can't be templated", field)
+            }
+            (Some(_), Some(computed)) => self.fields.push((Target::Computed(index, computed), generator)),
+            (Some(location), None) => self.fields.push((Target::Bytes(location), generator)),
+            (None, _) => panic!("there is no field called {} in layer {} of the packet", field, index),
+        }
+        self
+    }
+
+    /// Returns an iterator over every combination of the generators' values, with the field set last changing the fastest.
+    /// `Fixed` generators count as having one value
+    pub fn product(&self) -> Packets<'_> {
+        let total = self.fields.iter().try_fold(1usize, |total, (_, g)| total.checked_mul(g.len().unwrap_or(1)));
+        Packets { template: self, zipped: false, next: 0, total: total.unwrap_or(usize::MAX) }
+    }
+
+    /// Returns an iterator which takes the next value of every generator for each packet, so the first packet has the first value
+    /// of each generator, the second the second value and so on, stopping when any of them runs out. `Fixed` generators never run out
+    pub fn zip(&self) -> Packets<'_> {
+        let total = self.fields.iter().filter_map(|(_, g)| g.len()).min().unwrap_or(1);
+        Packets { template: self, zipped: true, next: 0, total }
+    }
+
+    /// builds the packet with the value at position `indices[i]` of the generator of each field `i`
+    fn build(&self, indices: impl Iterator<Item = usize>) -> Packet {
+        let mut packet = self.base.clone();
+        for ((target, generator), index) in self.fields.iter().zip(indices) {
+            match target {
+                Target::Bytes(location) => write_field(&mut packet, location, generator.get(index)),
+                Target::Computed(layer, field) => packet.set_override(*layer, *field, generator.get(index) as u16),
+            }
+        }
+        packet.finalize();
+        packet
+    }
+}
+
+/// The packets a `Template` expands into, built one at a time as the iterator is advanced
+#[derive(Debug, Clone)]
+pub struct Packets<'a> {
+    template: &'a Template,
+    zipped: bool,
+    next: usize,
+    total: usize,
+}
+
+impl Iterator for Packets<'_> {
+    type Item = Packet;
+
+    fn next(&mut self) -> Option<Packet> {
+        if self.next >= self.total {
+            return None;
+        }
+        let position = self.next;
+        self.next += 1;
+        let fields = &self.template.fields;
+        if self.zipped {
+            return Some(self.template.build(fields.iter().map(|(_, g)| if g.len().is_none() { 0 } else { position })));
+        }
+        // the position is a mixed radix number, with a digit for each field and the last field as the least significant digit
+        let mut remaining = position;
+        let mut indices = vec![0; fields.len()];
+        for (i, (_, generator)) in fields.iter().enumerate().rev() {
+            let len = generator.len().unwrap_or(1);
+            indices[i] = remaining % len;
+            remaining /= len;
+        }
+        Some(self.template.build(indices.into_iter()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.total - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Packets<'_> {}
+
+/// writes `value` into the bytes of the packet at `location`, which may be in the headers or the payload, but not the trailer
+pub(crate) fn write_field(packet: &mut Packet, location: &Location, value: u64) {
+    if location.length > 8 {
+        for (i, b) in value.to_be_bytes().iter().enumerate() {
+            *byte_mut(packet, location.offset + location.length - 8 + i) = *b;
+        }
+        return;
+    }
+    let old = (0..location.length).fold(0u64, |v, i| (v << 8) | *byte_mut(packet, location.offset + i) as u64);
+    let new = if location.bitmask == 0 {
+        value
+    } else {
+        (old & !location.bitmask) | ((value << location.bitmask.trailing_zeros()) & location.bitmask)
+    };
+    for i in 0..location.length {
+        *byte_mut(packet, location.offset + i) = (new >> (8 * (location.length - 1 - i))) as u8;
+    }
+}
+
+/// the byte at `position` from the start of the packet, counting on from the headers into the payload
+fn byte_mut(packet: &mut Packet, position: usize) -> &mut u8 {
+    let header_len = packet.buffer.len();
+    if position < header_len {
+        &mut packet.buffer[position]
+    } else {
+        &mut packet.payload[position - header_len]
+    }
+}
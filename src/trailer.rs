@@ -0,0 +1,103 @@
+use super::{ethernet_fcs, Packet, Protocol};
+
+/// ethernet frames shorter than this, not counting the FCS, are padded when they are sent
+pub(crate) const MIN_FRAME_LEN: usize = 60;
+
+/// what is written after the payload of a packet which starts with an ethernet header
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Trailer {
+    /// padding which is always written, e.g. the padding a frame was captured with
+    pub(crate) padding: Vec<u8>,
+    /// whether the frame is padded with zeros to `MIN_FRAME_LEN` bytes, after `padding`
+    pub(crate) pad_to_minimum: bool,
+    /// whether the frame ends with an FCS
+    pub(crate) fcs: bool,
+    /// the value the FCS is fixed at, instead of being calculated
+    pub(crate) fcs_override: Option<u32>,
+    /// the padding and FCS as they are written, which is worked out whenever the packet changes
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl Packet {
+    /// Pads the frame with zeros up to the 60 byte minimum length of an ethernet frame (64 bytes with the FCS) when it is shorter.
+    /// The padding comes after the payload and isn't counted by the IP and UDP length fields.
+    /// Like the FCS, this is only done for packets whose first layer is ethernet
+    pub fn set_pad_to_minimum(&mut self, pad: bool) {
+        self.trailer.pad_to_minimum = pad;
+        self.finalize();
+    }
+
+    /// Sets padding which is always written after the payload, whatever the length of the frame.
+    /// Parsing keeps the padding a frame was captured with here
+    pub fn set_padding(&mut self, padding: Vec<u8>) {
+        self.trailer.padding = padding;
+        self.finalize();
+    }
+
+    /// Returns the padding which is written after the payload, including any which is added to reach the minimum length
+    pub fn get_padding(&self) -> &[u8] {
+        let fcs_len = if self.get_fcs().is_some() { 4 } else { 0 };
+        &self.trailer.bytes[..(self.trailer.bytes.len() - fcs_len)]
+    }
+
+    /// Ends the frame with an FCS, which is calculated over the whole frame, including the padding, whenever the packet changes
+    pub fn set_fcs(&mut self, fcs: bool) {
+        self.trailer.fcs = fcs;
+        self.finalize();
+    }
+
+    /// Fixes the FCS at `value` instead of calculating it, so that frames with a bad FCS can be built. This also turns the FCS on.
+    /// `clear_overrides` goes back to calculating it, and `pin_computed_fields` fixes it at its current value
+    pub fn set_fcs_override(&mut self, value: u32) {
+        self.trailer.fcs = true;
+        self.trailer.fcs_override = Some(value);
+        self.finalize();
+    }
+
+    /// Returns the FCS which ends the frame, if it has one
+    pub fn get_fcs(&self) -> Option<u32> {
+        if !self.trailer.fcs || self.trailer.bytes.len() < 4 {
+            return None;
+        }
+        let fcs = &self.trailer.bytes[(self.trailer.bytes.len() - 4)..];
+        Some(u32::from_le_bytes([fcs[0], fcs[1], fcs[2], fcs[3]]))
+    }
+
+    /// Returns whether the FCS which ends the frame is right, or `None` if the frame has no FCS
+    pub fn verify_fcs(&self) -> Option<bool> {
+        let fcs = self.get_fcs()?;
+        let frame_len = self.len() - 4;
+        let mut frame = Vec::with_capacity(frame_len);
+        self.write_into(&mut frame);
+        Some(ethernet_fcs(&frame[..frame_len]) == fcs)
+    }
+
+    /// Returns the bytes which are written after the payload: the padding and the FCS
+    pub fn get_trailer(&self) -> &[u8] {
+        &self.trailer.bytes
+    }
+
+    /// works out the padding and FCS for the current headers and payload
+    pub(crate) fn finalize_trailer(&mut self) {
+        let Self { buffer, layers, payload, trailer } = self;
+        trailer.bytes.clear();
+        if layers.first().map(|layer| layer.proto) != Some(Protocol::ETH) {
+            return;
+        }
+        trailer.bytes.extend_from_slice(&trailer.padding);
+        let frame_len = buffer.len() + payload.len() + trailer.bytes.len();
+        if trailer.pad_to_minimum && frame_len < MIN_FRAME_LEN {
+            trailer.bytes.resize(trailer.bytes.len() + MIN_FRAME_LEN - frame_len, 0);
+        }
+        if trailer.fcs {
+            let fcs = trailer.fcs_override.unwrap_or_else(|| {
+                let mut frame = Vec::with_capacity(frame_len.max(MIN_FRAME_LEN));
+                frame.extend_from_slice(buffer);
+                frame.extend_from_slice(payload);
+                frame.extend_from_slice(&trailer.bytes);
+                ethernet_fcs(&frame)
+            });
+            trailer.bytes.extend_from_slice(&fcs.to_le_bytes());
+        }
+    }
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "serde")]
+extern crate packet_crafter;
+
+use packet_crafter::*;
+
+#[test]
+fn test_packet_json_round_trip() {
+    let p = headers::EthernetHeader::new([6, 5, 4, 3, 2, 1], [1, 2, 3, 4, 5, 6], 0)
+        / headers::IpHeader::new([192, 168, 1, 128], [192, 168, 1, 38], Protocol::UDP)
+        / headers::UdpHeader::new(5000, 53)
+        / "query";
+    let json = serde_json::to_string(&p).unwrap();
+    assert!(json.contains(r#""dst_mac":"01:02:03:04:05:06""#));
+    assert!(json.contains(r#""src_ip":"192.168.1.128""#));
+    let rebuilt: Packet = serde_json::from_str(&json).unwrap();
+    assert_eq!(rebuilt.into_vec(), p.into_vec());
+}
+
+#[test]
+fn test_packet_from_fixture() {
+    let fixture = r#"{
+        "layers": [
+            {"Ethernet": {"dst_mac": "ff:ff:ff:ff:ff:ff", "src_mac": "00-11-22-33-44-55", "eth_type": 2054}},
+            {"Arp": {
+                "hardware_type": 1, "protocol_type": 2048, "hardware_size": 6, "protocol_size": 4, "opcode": 1,
+                "sender_mac": "00:11:22:33:44:55", "sender_ip": "10.0.0.1",
+                "destination_mac": "00:00:00:00:00:00", "destination_ip": "10.0.0.2"
+            }}
+        ],
+        "payload": []
+    }"#;
+    let p: Packet = serde_json::from_str(fixture).unwrap();
+    assert_eq!(p.get_protocols(), vec![Protocol::ETH, Protocol::ARP]);
+    let arp = p.get_arp_header().unwrap();
+    assert_eq!(arp.get_sender_mac(), Some(MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55)));
+    assert_eq!(arp.get_destination_ip(), Some(std::net::Ipv4Addr::new(10, 0, 0, 2)));
+
+    let bad_mac = fixture.replace("00-11-22-33-44-55", "00:11:22:33:44");
+    assert!(serde_json::from_str::<Packet>(&bad_mac).is_err());
+    let dotted = fixture.replace("00-11-22-33-44-55", "0011.2233.4455");
+    assert_eq!(serde_json::from_str::<Packet>(&dotted).unwrap().get_eth_header().unwrap().get_src_mac().to_string(), "00:11:22:33:44:55");
+}
+
+#[test]
+fn test_malformed_fixtures_are_errors() {
+    let p = headers::IpHeader::default() / headers::UdpHeader::new(5000, 53);
+    let json = serde_json::to_string(&p).unwrap();
+    assert!(serde_json::from_str::<Packet>(&json).is_ok());
+    let mixed = json.replace(r#""src_ip":"127.0.0.1""#, r#""src_ip":"fd00::1""#);
+    let error = serde_json::from_str::<Packet>(&mixed).unwrap_err().to_string();
+    assert!(error.contains("the addresses fd00::1 and 127.0.0.1 are different IP versions"), "{}", error);
+
+    let ip = headers::IpHeader::new([0xfd00, 0, 0, 0, 0, 0, 0, 1], [0xfd00, 0, 0, 0, 0, 0, 0, 2], Protocol::UDP);
+    let mut json = serde_json::to_value(&ip).unwrap();
+    json["flow_label"] = serde_json::json!(0x100000);
+    assert!(serde_json::from_value::<headers::IpHeader>(json.clone()).is_err());
+    json["flow_label"] = serde_json::json!(0xfffff);
+    assert_eq!(serde_json::from_value::<headers::IpHeader>(json).unwrap().get_flow_label(), &0xfffff);
+
+    let arp = serde_json::to_value(headers::ArpHeader::default()).unwrap();
+    for (field, value) in [("hardware_size", serde_json::json!(4)), ("protocol_size", serde_json::json!(16)), ("sender_proto_address", serde_json::json!("fd00::1"))] {
+        let mut bad = arp.clone();
+        bad[field] = value;
+        assert!(serde_json::from_value::<headers::ArpHeader>(bad).is_err(), "{}", field);
+    }
+    assert!(serde_json::from_value::<headers::ArpHeader>(arp).is_ok());
+}
+
+#[test]
+fn test_header_serde() {
+    let ip = headers::IpHeader::new([0xfd00, 0, 0, 0, 0, 0, 0, 1], [0xfd00, 0, 0, 0, 0, 0, 0, 2], Protocol::TCP);
+    let json = serde_json::to_value(&ip).unwrap();
+    assert_eq!(json["src_ip"], "fd00::1");
+    let back: headers::IpHeader = serde_json::from_value(json).unwrap();
+    assert_eq!(back.get_dst_ip(), ip.get_dst_ip());
+    assert_eq!(serde_json::to_string(&Protocol::TCP).unwrap(), r#""TCP""#);
+}
+
+#[test]
+fn test_packet_trailer_serde() {
+    let mut p = headers::EthernetHeader::default() / headers::IpHeader::default() / headers::UdpHeader::new(1, 2);
+    p.set_pad_to_minimum(true);
+    p.set_fcs(true);
+    let json = serde_json::to_string(&p).unwrap();
+    assert!(json.contains(r#""pad_to_minimum":true,"fcs":true"#));
+    let back: Packet = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.into_vec(), p.into_vec());
+    let plain = serde_json::to_string(&(headers::IpHeader::default() / "x")).unwrap();
+    assert!(!plain.contains("fcs"));
+}
+
+#[test]
+fn test_arp_address_serde() {
+    let arp = headers::ArpHeader::with_addresses(headers::HARDWARE_INFINIBAND, 0x86dd, vec![0xab; 20], vec![0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1], vec![0; 20], vec![0; 16]);
+    let json = serde_json::to_value(&arp).unwrap();
+    assert_eq!(json["sender_hw_address"].as_str().unwrap().len(), 59);
+    assert_eq!(json["sender_proto_address"], "fe80::1");
+    assert_eq!(json["destination_proto_address"], "::");
+    let back: headers::ArpHeader = serde_json::from_value(json).unwrap();
+    assert_eq!(back.get_sender_hw_address(), arp.get_sender_hw_address());
+    assert_eq!(back.get_sender_proto_address(), arp.get_sender_proto_address());
+
+    let ethernet = serde_json::to_value(headers::ArpHeader::new([0, 0x11, 0x22, 0x33, 0x44, 0x55], [10, 0, 0, 1], [0; 6], [10, 0, 0, 2])).unwrap();
+    assert_eq!(ethernet["sender_hw_address"], "00:11:22:33:44:55");
+    assert_eq!(ethernet["sender_proto_address"], "10.0.0.1");
+}
+
+#[test]
+fn test_lldp_serde() {
+    let lldp = headers::LldpHeader::new([0, 0x11, 0x22, 0x33, 0x44, 0x55], "eth0", 120);
+    let json = serde_json::to_value(&lldp).unwrap();
+    assert_eq!(json["tlvs"][2]["Ttl"], 120);
+    assert_eq!(json["tlvs"][3], "End");
+    let back: headers::LldpHeader = serde_json::from_value(json).unwrap();
+    assert_eq!(back.get_tlvs(), lldp.get_tlvs());
+}
@@ -236,3 +236,1536 @@ fn test_2_byte_u16_to_bytes() {
     let x: u16 = 0b00100001_00101100;
     assert_eq!([0b00100001, 0b00101100], x.split_to_bytes());
 }
+
+#[test]
+fn test_insert_header_between_layers() {
+    let mut p = Packet::new(vec![Protocol::ETH, Protocol::IP, Protocol::TCP]);
+    p.add_header(headers::EthernetHeader::new(
+        [6, 5, 4, 3, 2, 1],
+        [1, 2, 3, 4, 5, 6],
+        ethertype_numbers::ETHERTYPE_IPV4,
+    ));
+    p.add_header(headers::IcmpHeader::new(8, 0, 0xd49e, 1));
+    p.insert_header(1, headers::IpHeader::new(
+        [192, 168, 1, 128],
+        [192, 168, 1, 38],
+        Protocol::ICMP,
+    ));
+    assert_eq!(p.get_protocols(), vec![Protocol::ETH, Protocol::IP, Protocol::ICMP]);
+    assert_eq!(p.get_layer_as_slice(1).unwrap().len(), 20);
+    let icmp_header_slice = p
+        .get_header_as_slice(Protocol::ICMP)
+        .expect("could not find icmp header");
+    assert_eq!(icmp_header_slice[0], 8);
+    assert_eq!(icmp_header_slice.len(), 8);
+}
+
+#[test]
+fn test_remove_header() {
+    let mut p = Packet::new(vec![Protocol::IP, Protocol::IP, Protocol::ICMP]);
+    p.add_header(headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::IP));
+    p.add_header(headers::IpHeader::new([192, 168, 1, 128], [192, 168, 1, 38], Protocol::ICMP));
+    p.add_header(headers::IcmpHeader::new(8, 0, 0xd49e, 1));
+    let removed = p.remove_header(0).expect("there should be a layer to remove");
+    assert_eq!(removed.len(), 20);
+    assert_eq!(removed[12..16], [10, 0, 0, 1]);
+    assert_eq!(p.layer_count(), 2);
+    assert_eq!(p.get_ip_header().unwrap().get_src_ip(), &std::net::IpAddr::from([192, 168, 1, 128]));
+    assert!(p.remove_header(2).is_none());
+    let data = p.into_vec();
+    assert_eq!(data.len(), 28);
+    assert_eq!(data[20], 8);
+}
+
+#[test]
+fn test_replace_header_with_different_length() {
+    let mut p = Packet::new(vec![Protocol::ETH, Protocol::IP, Protocol::ICMP]);
+    p.add_header(headers::EthernetHeader::new(
+        [6, 5, 4, 3, 2, 1],
+        [1, 2, 3, 4, 5, 6],
+        ethertype_numbers::ETHERTYPE_IPV4,
+    ));
+    p.add_header(headers::IpHeader::new([192, 168, 1, 128], [192, 168, 1, 38], Protocol::ICMP));
+    p.add_header(headers::IcmpHeader::new(8, 0, 0xd49e, 1));
+    let old = p.replace_header(1, headers::IpHeader::new(
+        [0xfd00, 0, 0, 0, 0, 0, 0, 1],
+        [0xfd00, 0, 0, 0, 0, 0, 0, 2],
+        Protocol::ICMP,
+    ));
+    assert_eq!(old.unwrap().len(), 20);
+    assert_eq!(p.get_layer_as_slice(1).unwrap().len(), 40);
+    let icmp_header_slice = p
+        .get_header_as_slice(Protocol::ICMP)
+        .expect("could not find icmp header");
+    assert_eq!(icmp_header_slice.len(), 8);
+    assert_eq!(icmp_header_slice[0], 8);
+    assert_eq!(p.into_vec().len(), 14 + 40 + 8);
+}
+
+#[test]
+fn test_compose_layers_with_div() {
+    let data = (headers::EthernetHeader::default()
+        / headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP)
+        / headers::TcpHeader::new(1234, 80)
+        / "Hello, world!")
+        .into_vec();
+    assert_eq!(data.len(), 14 + 20 + 20 + 13);
+    assert_eq!(data[0..6], [0xff; 6]);
+    assert_eq!(data[12..14], ethertype_numbers::ETHERTYPE_IPV4.to_be_bytes());
+
+    // ip total length and header checksum
+    assert_eq!(data[16..18], 53u16.to_be_bytes());
+    assert_eq!(checksum(&data[14..34], 0), 0);
+
+    // tcp checksum, including the pseudo header and the payload
+    let mut sum = sum_be_words(&data[26..34], 0);
+    sum += 6 + 33;
+    sum += sum_be_words(&data[34..], 0);
+    while sum >> 16 != 0 {
+        sum = (sum >> 16) + (sum & 0xffff);
+    }
+    assert_eq!(sum, 0xffff);
+    assert_eq!(&data[54..], b"Hello, world!");
+}
+
+#[test]
+fn test_compose_fills_in_next_protocol_fields() {
+    let p = headers::EthernetHeader::default()
+        / headers::IpHeader::default()
+        / headers::UdpHeader::default()
+        / vec![1, 2, 3];
+    assert_eq!(p.get_protocols(), vec![Protocol::ETH, Protocol::IP, Protocol::UDP]);
+    assert_eq!(p.get_ip_header().unwrap().get_next_protocol(), &protocol_numbers::IPPROTO_UDP);
+    let data = p.into_vec();
+    // udp length covers the header and payload
+    assert_eq!(data[38..40], 11u16.to_be_bytes());
+
+    let v6 = headers::EthernetHeader::default()
+        / headers::IpHeader::new([0xfd00, 0, 0, 0, 0, 0, 0, 1], [0xfd00, 0, 0, 0, 0, 0, 0, 2], Protocol::ICMP);
+    assert_eq!(v6.get_eth_header().unwrap().get_eth_type(), &ethertype_numbers::ETHERTYPE_IPV6);
+
+    // explicitly set values are left alone
+    let explicit = headers::EthernetHeader::new([0; 6], [0xff; 6], 0x1234) / headers::IpHeader::default();
+    assert_eq!(explicit.get_eth_header().unwrap().get_eth_type(), &0x1234);
+}
+
+#[test]
+fn test_header_write_to_does_not_consume() {
+    let eth_header = headers::EthernetHeader::new([6, 5, 4, 3, 2, 1], [1, 2, 3, 4, 5, 6], ethertype_numbers::ETHERTYPE_IPV4);
+    let mut buf = [0u8; 20];
+    assert_eq!(eth_header.write_to(&mut buf).unwrap(), 14);
+    assert_eq!(buf[0..14], [1, 2, 3, 4, 5, 6, 6, 5, 4, 3, 2, 1, 8, 0]);
+    assert_eq!(buf[14..], [0; 6]);
+
+    let mut too_small = [0u8; 10];
+    match eth_header.write_to(&mut too_small) {
+        Err(WriteError::BufferTooSmall { needed, available }) => {
+            assert_eq!(needed, 14);
+            assert_eq!(available, 10);
+        },
+        _ => panic!("writing to a buffer which is too small should fail"),
+    }
+    assert_eq!(too_small, [0; 10]);
+
+    let mut v = Vec::new();
+    eth_header.write_into(&mut v);
+    assert_eq!(v, eth_header.make());
+}
+
+#[test]
+fn test_transport_headers_write_without_a_pseudo_header() {
+    // outside a packet there is no IP header to calculate the checksum over, so it's written as 0
+    let tcp = headers::TcpHeader::new(1234, 80);
+    let mut buf = [0xffu8; 20];
+    assert_eq!(tcp.write_to(&mut buf).unwrap(), 20);
+    assert_eq!(&buf[0..4], &[0x04, 0xd2, 0x00, 0x50]);
+    assert_eq!(&buf[16..18], &[0, 0]);
+    assert_eq!(tcp.make().len(), 20);
+
+    let udp = headers::UdpHeader::new(1234, 53);
+    let data = udp.make();
+    assert_eq!(data.len(), 8);
+    assert_eq!(&data[6..8], &[0, 0]);
+}
+
+#[test]
+fn test_packet_write_to_matches_into_vec() {
+    let p = headers::EthernetHeader::default()
+        / headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP)
+        / headers::UdpHeader::new(5000, 53)
+        / "query";
+    let mut ring = [0u8; 128];
+    let written = p.write_to(&mut ring).unwrap();
+    assert_eq!(written, p.len());
+    assert_eq!(p.write_to(&mut ring[written..]).unwrap(), written);
+    assert!(p.write_to(&mut ring[..10]).is_err());
+
+    let mut v = Vec::new();
+    p.write_into(&mut v);
+    p.write_into(&mut v);
+    assert_eq!(v[..written], ring[..written]);
+    assert_eq!(v[written..], ring[written..(written * 2)]);
+    assert_eq!(p.into_vec(), ring[..written].to_vec());
+}
+
+#[test]
+fn test_parse_keeps_payload() {
+    let data = (headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP)
+        / headers::UdpHeader::new(5000, 53)
+        / "query")
+        .into_vec();
+    let packet = Packet::parse(&data).unwrap();
+    assert_eq!(packet.get_payload(), &b"query".to_vec());
+    assert_eq!(packet.into_vec(), data);
+}
+
+#[test]
+fn test_layers_beyond_255_bytes() {
+    let mut p = Packet::new_empty();
+    for _ in 0..14 {
+        p.push_header(headers::IpHeader::default());
+    }
+    p.push_header(headers::TcpHeader::new(1234, 80));
+    p.extend_payload(vec![0xaa; 300]);
+    assert_eq!(p.layer_count(), 15);
+    let tcp_header_slice = p.get_header_as_slice(Protocol::TCP).unwrap();
+    assert_eq!(tcp_header_slice[..4], [0x04, 0xd2, 0, 80]);
+    let innermost_ip = p.get_layer_as_slice(13).unwrap();
+    assert_eq!(innermost_ip[9], protocol_numbers::IPPROTO_TCP);
+    let data = p.into_vec();
+    assert_eq!(data.len(), 14 * 20 + 20 + 300);
+    assert_eq!(data[280..282], [0x04, 0xd2]);
+    // the outermost IP header's total length covers all of it
+    assert_eq!(data[2..4], 600u16.to_be_bytes());
+}
+
+#[test]
+fn test_rebuild_packet_from_layers() {
+    let p = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::ICMP)
+        / headers::IcmpHeader::new(8, 0, 7, 1)
+        / "ping";
+    let layers = p.get_layers();
+    assert_eq!(layers.len(), 2);
+    match &layers[1] {
+        headers::AnyHeader::Icmp(icmp) => assert_eq!(icmp.get_identifier(), &7),
+        _ => panic!("second layer should be icmp"),
+    }
+    let rebuilt = Packet::from_layers(layers, p.get_payload().clone());
+    assert_eq!(rebuilt.into_vec(), p.into_vec());
+}
+
+#[test]
+fn test_dissect_fields() {
+    let mut tcp = headers::TcpHeader::new(51000, 443);
+    tcp.set_flag(headers::TcpFlags::Syn);
+    tcp.set_flag(headers::TcpFlags::Ack);
+    let p = headers::EthernetHeader::new([6, 5, 4, 3, 2, 1], [1, 2, 3, 4, 5, 6], 0)
+        / headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP)
+        / tcp
+        / "hi";
+    let layers = p.dissect();
+    let names: Vec<&str> = layers.iter().map(|l| l.name).collect();
+    assert_eq!(names, vec!["eth", "ip", "tcp", "data"]);
+
+    let eth_type = layers[0].get_field("eth.type").unwrap();
+    assert_eq!(eth_type.show, "IPv4 (0x0800)");
+    assert_eq!(eth_type.offset, 12);
+
+    let proto = layers[1].get_field("ip.proto").unwrap();
+    assert_eq!(proto.show, "TCP (6)");
+    assert_eq!(proto.raw, vec![6]);
+    assert_eq!(proto.offset, 23);
+    let df = layers[1].get_field("ip.flags.df").unwrap();
+    assert_eq!(df.value(), Some(1));
+    assert_eq!(layers[1].get_field("ip.src").unwrap().show, "10.0.0.1");
+
+    assert_eq!(layers[2].get_field("tcp.dstport").unwrap().show, "443 (https)");
+    assert_eq!(layers[2].get_field("tcp.flags").unwrap().show, "0x012 (ACK, SYN)");
+    assert_eq!(layers[2].get_field("tcp.flags.syn").unwrap().show, "Set");
+    assert_eq!(layers[2].get_field("tcp.flags.fin").unwrap().value(), Some(0));
+    assert_eq!(layers[3].offset, 54);
+}
+
+#[test]
+fn test_dissection_json() {
+    let p = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::IP)
+        / headers::IpHeader::new([192, 168, 0, 1], [192, 168, 0, 2], Protocol::UDP)
+        / headers::UdpHeader::new(5000, 53);
+    let json = p.to_json();
+    let value: serde_json::Value = serde_json::from_str(&json).expect("dissection should be valid json");
+    let layers = &value["_source"]["layers"];
+    assert_eq!(layers["frame"]["frame.protocols"], "ip:ip:udp");
+    assert_eq!(layers["frame"]["frame.len"], "48");
+    assert_eq!(layers["ip"][0]["ip.proto"], "IPv4 (4)");
+    assert_eq!(layers["ip"][1]["ip.dst"], "192.168.0.2");
+    assert_eq!(layers["ip"][1]["ip.ttl_raw"], serde_json::json!(["40", 28, 1, 0]));
+    assert_eq!(layers["ip"][1]["ip.flags_tree"]["ip.flags.df"], "Set");
+    assert_eq!(layers["udp"]["udp.dstport"], "53 (domain)");
+    assert_eq!(layers["udp"]["udp.length_raw"], serde_json::json!(["0008", 44, 2, 0]));
+}
+
+#[test]
+fn test_packet_summary() {
+    let mut syn = headers::TcpHeader::new(1234, 80);
+    syn.set_flag(headers::TcpFlags::Syn);
+    let p = headers::EthernetHeader::default()
+        / headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP)
+        / syn;
+    assert_eq!(p.summary(), "IP 10.0.0.1:1234 > 10.0.0.2:80 TCP [S] len=0");
+    assert_eq!(format!("{}", p), p.summary());
+
+    let udp = headers::IpHeader::new([0xfd00, 0, 0, 0, 0, 0, 0, 1], [0xfd00, 0, 0, 0, 0, 0, 0, 2], Protocol::UDP)
+        / headers::UdpHeader::new(5000, 53)
+        / "query";
+    assert_eq!(udp.summary(), "IP6 [fd00::1]:5000 > [fd00::2]:53 UDP len=5");
+
+    let arp = headers::EthernetHeader::default()
+        / headers::ArpHeader::new([0, 0x11, 0x22, 0x33, 0x44, 0x55], [10, 0, 0, 1], [0; 6], [10, 0, 0, 2]);
+    assert_eq!(arp.summary(), "ARP who-has 10.0.0.2 tell 10.0.0.1");
+}
+
+#[test]
+fn test_packet_show() {
+    let p = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::ICMP)
+        / headers::IcmpHeader::new(8, 0, 7, 1)
+        / "ping";
+    let shown = p.show();
+    let lines: Vec<&str> = shown.lines().collect();
+    assert_eq!(lines[0], "Internet Protocol Version 4, Src: 10.0.0.1, Dst: 10.0.0.2");
+    assert!(lines.contains(&"    Time to Live: 64"));
+    assert!(lines.contains(&"    Protocol: ICMP (1)"));
+    assert!(lines.contains(&"        Don't fragment: Set"));
+    assert!(lines.contains(&"Internet Control Message Protocol"));
+    assert!(lines.contains(&"    Type: 8 (Echo (ping) request)"));
+    assert_eq!(lines.last(), Some(&"    Data: 70696e67"));
+    assert!(format!("{:?}", p).starts_with("Packet { layers: [Ip(IpHeader {"));
+    assert_eq!(
+        format!("{}", headers::UdpHeader::new(5000, 53)),
+        "User Datagram Protocol, Src Port: 5000, Dst Port: 53"
+    );
+}
+
+#[test]
+fn test_hexdump() {
+    let data: Vec<u8> = (0x41..0x55).collect();
+    assert_eq!(
+        hexdump(&data),
+        "00000000  41 42 43 44 45 46 47 48  49 4a 4b 4c 4d 4e 4f 50  |ABCDEFGHIJKLMNOP|\n\
+         00000010  51 52 53 54                                       |QRST|\n\
+         00000014\n"
+    );
+    let p = headers::IcmpHeader::new(8, 0, 7, 1) / vec![0, 1];
+    assert!(p.hexdump().starts_with("00000000  08 00 "));
+    assert!(p.hexdump().ends_with("0000000a\n"));
+}
+
+#[test]
+fn test_import_hex_stream() {
+    assert_eq!(import::from_hex_stream("4500 00:1c,0x40 0X01\n-ff"), Ok(vec![0x45, 0, 0, 0x1c, 0x40, 1, 0xff]));
+    assert_eq!(import::from_hex_stream("450"), Err(ParseError::InvalidLength));
+    assert_eq!(import::from_hex_stream("45 zz"), Err(ParseError::InvalidCharacter));
+    let p = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP) / headers::UdpHeader::new(5000, 53) / "hi";
+    let stream: String = p.clone().into_vec().iter().map(|b| format!("{:02x}", b)).collect();
+    let parsed = Packet::from_hex(&stream).unwrap();
+    assert_eq!(parsed.get_protocols(), vec![Protocol::IP, Protocol::UDP]);
+    assert_eq!(parsed.get_payload(), &b"hi".to_vec());
+}
+
+#[test]
+fn test_import_hex_dumps() {
+    let p = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP) / headers::UdpHeader::new(5000, 53) / "hi";
+    let data = p.clone().into_vec();
+    let xxd = "00000000: 4500 001e 0000 4000 4011 26cd 0a00 0001  E.....@.@.&.....\n\
+               00000010: 0a00 0002 1388 0035 000a 6fb1 6869       .......5...,hi\n";
+    let tcpdump = "12:00:00.000000 IP 10.0.0.1.5000 > 10.0.0.2.53: UDP, length 2\n\
+                   \t0x0000:  4500 001e 0000 4000 4011 26cd 0a00 0001  E.....@.@.&.....\n\
+                   \t0x0010:  0a00 0002 1388 0035 000a 6fb1 6869       .......5...,hi\n";
+    let od = "000000 45 00 00 1e 00 00 40 00 40 11 26 cd 0a 00 00 01\n\
+              000010 0a 00 00 02 13 88 00 35 00 0a 6f b1 68 69\n\
+              00001e\n";
+    assert_eq!(import::from_text(&p.hexdump()), Ok(data.clone()));
+    assert_eq!(import::from_text(xxd), Ok(data.clone()));
+    assert_eq!(import::from_text(tcpdump), Ok(data.clone()));
+    assert_eq!(import::from_text(od), Ok(data.clone()));
+    assert_eq!(Packet::from_hex(tcpdump).unwrap().get_udp_header().unwrap().get_dst_port(), &53);
+
+    let two = format!("{}{}", p.hexdump(), hexdump(&[1, 2, 3]));
+    assert_eq!(import::from_hex_dump(&two), Ok(vec![data, vec![1, 2, 3]]));
+    assert_eq!(import::from_hex_dump("no offsets here"), Err(ParseError::InvalidFormat));
+}
+
+#[test]
+fn test_import_ambiguous_text() {
+    // a one byte first field is a byte of a hex stream, not an offset
+    assert_eq!(import::from_text("00 11 22 33"), Ok(vec![0, 0x11, 0x22, 0x33]));
+    // `>` is only a marker in od's output, which ends the line with `<`
+    assert_eq!(
+        import::from_text("00000000: 4142 4344 4546 4748  ABCD>FGH"),
+        Ok(vec![0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48])
+    );
+    assert_eq!(import::from_text("000000 41 42 43 >AB><\n000003\n"), Ok(vec![0x41, 0x42, 0x43]));
+    // the bytes from 0x0002 to 0x000f are missing
+    assert_eq!(import::from_text("0000  01 02\n0010  03 04"), Err(ParseError::InvalidLength));
+}
+
+#[test]
+fn test_generate_rust_code() {
+    let mut ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP);
+    ip.set_identification(0x1234);
+    ip.set_ttl(128);
+    let mut tcp = headers::TcpHeader::new(1234, 80);
+    tcp.set_sequence_number(1000);
+    tcp.set_acknowledgment_number(2000);
+    tcp.set_flags(0x18);
+    let captured = headers::EthernetHeader::new([0, 0x11, 0x22, 0x33, 0x44, 0x55], [0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb], 0)
+        / ip
+        / tcp
+        / "hello";
+    let parsed = Packet::parse(&captured.clone().into_vec()).unwrap();
+    assert_eq!(
+        parsed.to_rust(),
+        "let eth = headers::EthernetHeader::new([0x00, 0x11, 0x22, 0x33, 0x44, 0x55], [0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb], ethertype_numbers::ETHERTYPE_IPV4);\n\
+         let mut ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP);\n\
+         ip.set_identification(4660);\n\
+         ip.set_ttl(128);\n\
+         let mut tcp = headers::TcpHeader::new(1234, 80);\n\
+         tcp.set_sequence_number(1000);\n\
+         tcp.set_acknowledgment_number(2000);\n\
+         tcp.set_flags(0x18);\n\
+         let payload = vec![\n    \
+             0x68, 0x65, 0x6c, 0x6c, 0x6f,\n\
+         ];\n\
+         let packet = Packet::from_layers(vec![eth.into(), ip.into(), tcp.into()], payload);\n"
+    );
+
+    // the generated code, pasted in
+    let eth = headers::EthernetHeader::new([0x00, 0x11, 0x22, 0x33, 0x44, 0x55], [0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb], ethertype_numbers::ETHERTYPE_IPV4);
+    let mut ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP);
+    ip.set_identification(4660);
+    ip.set_ttl(128);
+    let mut tcp = headers::TcpHeader::new(1234, 80);
+    tcp.set_sequence_number(1000);
+    tcp.set_acknowledgment_number(2000);
+    tcp.set_flags(0x18);
+    let payload = vec![
+        0x68, 0x65, 0x6c, 0x6c, 0x6f,
+    ];
+    let packet = Packet::from_layers(vec![eth.into(), ip.into(), tcp.into()], payload);
+    assert_eq!(packet.into_vec(), captured.into_vec());
+}
+
+#[test]
+fn test_generate_rust_code_for_wrong_computed_fields() {
+    let mut captured = headers::EthernetHeader::new([0, 0x11, 0x22, 0x33, 0x44, 0x55], [0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb], 0)
+        / headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP)
+        / headers::UdpHeader::new(5000, 53)
+        / "hi";
+    captured.set_override(1, ComputedField::Length, 40);
+    captured.set_override(2, ComputedField::Checksum, 0xbeef);
+    captured.set_fcs_override(0x12345678);
+    // the IPv4 header checksum was calculated over the wrong total length, so it is right for it and needs no override
+    assert_eq!(
+        captured.to_rust(),
+        "let eth = headers::EthernetHeader::new([0x00, 0x11, 0x22, 0x33, 0x44, 0x55], [0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb], ethertype_numbers::ETHERTYPE_IPV4);\n\
+         let ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);\n\
+         let udp = headers::UdpHeader::new(5000, 53);\n\
+         let payload = vec![\n    \
+             0x68, 0x69,\n\
+         ];\n\
+         let mut packet = Packet::from_layers(vec![eth.into(), ip.into(), udp.into()], payload);\n\
+         packet.set_override(1, ComputedField::Length, 40);\n\
+         packet.set_override(2, ComputedField::Checksum, 0xbeef);\n\
+         packet.set_fcs_override(0x12345678);\n"
+    );
+
+    // the generated code, pasted in
+    let eth = headers::EthernetHeader::new([0x00, 0x11, 0x22, 0x33, 0x44, 0x55], [0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb], ethertype_numbers::ETHERTYPE_IPV4);
+    let ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);
+    let udp = headers::UdpHeader::new(5000, 53);
+    let payload = vec![
+        0x68, 0x69,
+    ];
+    let mut packet = Packet::from_layers(vec![eth.into(), ip.into(), udp.into()], payload);
+    packet.set_override(1, ComputedField::Length, 40);
+    packet.set_override(2, ComputedField::Checksum, 0xbeef);
+    packet.set_fcs_override(0x12345678);
+    assert_eq!(packet.into_vec(), captured.clone().into_vec());
+
+    // a packet with everything right needs no overrides, even if it has them
+    let mut exact = Packet::parse_exact(&(headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP) / headers::UdpHeader::new(1, 2)).into_vec()).unwrap();
+    exact.pin_computed_fields();
+    assert!(!exact.to_rust().contains("set_override"));
+}
+
+#[test]
+fn test_generate_rust_code_for_a_wrong_data_offset() {
+    let mut tcp = headers::TcpHeader::new(1234, 80);
+    tcp.set_options(vec![2, 4, 0x05, 0xb4]);
+    let mut captured = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP) / tcp / "hi";
+    // too short to cover the MSS option
+    captured.set_override(1, ComputedField::HeaderLength, 5);
+    assert_eq!(
+        captured.to_rust(),
+        "let ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP);\n\
+         let mut tcp = headers::TcpHeader::new(1234, 80);\n\
+         tcp.set_options(vec![0x02, 0x04, 0x05, 0xb4]);\n\
+         let payload = vec![\n    \
+             0x68, 0x69,\n\
+         ];\n\
+         let mut packet = Packet::from_layers(vec![ip.into(), tcp.into()], payload);\n\
+         packet.set_override(1, ComputedField::HeaderLength, 5);\n"
+    );
+
+    // the generated code, pasted in
+    let ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP);
+    let mut tcp = headers::TcpHeader::new(1234, 80);
+    tcp.set_options(vec![0x02, 0x04, 0x05, 0xb4]);
+    let payload = vec![
+        0x68, 0x69,
+    ];
+    let mut packet = Packet::from_layers(vec![ip.into(), tcp.into()], payload);
+    packet.set_override(1, ComputedField::HeaderLength, 5);
+    assert_eq!(packet.into_vec(), captured.into_vec());
+}
+
+#[test]
+fn test_generate_rust_code_for_unusual_fields() {
+    let mut ip = headers::IpHeader::new([0xfd00, 0, 0, 0, 0, 0, 0, 1], [0xfd00, 0, 0, 0, 0, 0, 0, 2], Protocol::UDP);
+    ip.set_next_protocol_number(protocol_numbers::IPPROTO_GRE);
+    let code = Packet::from_layers(vec![ip.into()], Vec::new()).to_rust();
+    assert_eq!(
+        code,
+        "let mut ip = headers::IpHeader::new([0xfd00, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x1], [0xfd00, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x2], Protocol::IP);\n\
+         ip.set_next_protocol_number(47);\n\
+         let packet = Packet::from_layers(vec![ip.into()], Vec::new());\n"
+    );
+}
+
+#[test]
+fn test_packet_diff() {
+    let build = |ttl: u8, id: u16, dst_port: u16, payload: &str| {
+        let mut ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP);
+        ip.set_ttl(ttl);
+        ip.set_identification(id);
+        ip / headers::TcpHeader::new(1, dst_port) / payload
+    };
+    let a = build(64, 1, 80, "abc");
+    assert!(a.diff(&a.clone()).is_empty());
+
+    let b = build(32, 1, 443, "abc");
+    let differences = a.diff(&b);
+    let fields: Vec<&str> = differences.iter().map(|d| match d {
+        diff::Difference::Field { field, .. } => *field,
+        diff::Difference::Layer { .. } => "layer",
+    }).collect();
+    assert_eq!(fields, vec!["ip.ttl", "ip.checksum", "tcp.dstport", "tcp.checksum"]);
+    assert_eq!(differences[0].to_string(), "layer 0 (ip), ip.ttl: 64 != 32");
+    assert_eq!(differences[2], diff::Difference::Field {
+        index: 1,
+        layer: "tcp",
+        field: "tcp.dstport",
+        left: Some(String::from("80 (http)")),
+        right: Some(String::from("443 (https)")),
+    });
+
+    let c = build(64, 1, 80, "");
+    let differences = a.diff(&c);
+    assert_eq!(differences.last(), Some(&diff::Difference::Layer { index: 2, left: Some("data"), right: None }));
+    assert!(differences.iter().any(|d| d.to_string() == "layer 0 (ip), ip.len: 43 != 40"));
+}
+
+#[test]
+fn test_assert_packet_eq_ignores_volatile_fields() {
+    let build = |id: u16| {
+        let mut ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);
+        ip.set_identification(id);
+        ip / headers::UdpHeader::new(5000, 53) / "query"
+    };
+    let (a, b) = (build(1), build(2));
+    assert_packet_eq!(a, b, diff::DiffOptions::new().ignore_volatile());
+    assert_packet_eq!(&a, &a.clone());
+    assert!(diff::DiffOptions::new().ignore("ip.flags").is_ignored("ip.flags.df"));
+    assert!(!diff::DiffOptions::new().ignore("ip.flags").is_ignored("ip.flagsx"));
+
+    let result = std::panic::catch_unwind(|| assert_packet_eq!(a, b));
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.contains("layer 0 (ip), ip.id: 0x0001 (1) != 0x0002 (2)"), "{}", message);
+}
+
+#[test]
+fn test_diff_ignores_tcp_timestamps() {
+    let build = |tsval: u32| {
+        let mut tcp = headers::TcpHeader::new(1234, 80);
+        let mut options = vec![2, 4, 0x05, 0xb4, 1, 1, 8, 10];
+        options.extend(tsval.to_be_bytes());
+        options.extend(7u32.to_be_bytes());
+        tcp.set_options(options);
+        headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP) / tcp
+    };
+    let (a, b) = (build(1000), build(2000));
+    let tcp = &a.dissect()[1];
+    assert_eq!(tcp.get_field("tcp.options.mss_val").unwrap().show, "1460");
+    assert_eq!(tcp.get_field("tcp.options.timestamp.tsval").unwrap().show, "1000");
+    assert_eq!(tcp.get_field("tcp.options.timestamp.tsecr").unwrap().show, "7");
+    assert_eq!(tcp.get_field("tcp.options.timestamp").unwrap().offset, 46);
+    assert!(a.show().contains("TCP Option: Timestamps: TSval 1000, TSecr 7"));
+
+    assert_eq!(
+        a.diff(&b).into_iter().map(|d| d.to_string()).collect::<Vec<String>>(),
+        vec!["layer 1 (tcp), tcp.checksum: 0x41fe != 0x3e16", "layer 1 (tcp), tcp.options.timestamp.tsval: 1000 != 2000"]
+    );
+    assert_packet_eq!(a, b, diff::DiffOptions::new().ignore_volatile());
+}
+
+#[test]
+fn test_template_product() {
+    use template::{Generator, Template};
+    let base = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP) / headers::TcpHeader::new(1234, 80) / "data";
+    let template = Template::new(base)
+        .set("ip.ttl", Generator::List(vec![1, 255]))
+        .set("tcp.dstport", Generator::Range { start: 20, end: 25, step: 2 })
+        .set("tcp.flags.syn", Generator::Fixed(1));
+    let packets: Vec<Packet> = template.product().collect();
+    assert_eq!(template.product().len(), 6);
+    let values: Vec<(u8, u16)> = packets.iter().map(|p| {
+        (*p.get_ip_header().unwrap().get_ttl(), *p.get_tcp_header().unwrap().get_dst_port())
+    }).collect();
+    assert_eq!(values, vec![(1, 20), (1, 22), (1, 24), (255, 20), (255, 22), (255, 24)]);
+    // every packet is finalized, so it matches the same packet built by hand
+    assert!(packets.iter().all(|p| *p.get_tcp_header().unwrap().get_flags() == 0x02));
+    let mut ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP);
+    ip.set_ttl(255);
+    let mut tcp = headers::TcpHeader::new(1234, 24);
+    tcp.set_flags(0x02);
+    assert_eq!(packets[5].clone().into_vec(), (ip / tcp / "data").into_vec());
+}
+
+#[test]
+fn test_template_zip_and_random() {
+    use template::{Generator, Template};
+    let base = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP) / headers::UdpHeader::new(5000, 53);
+    let template = Template::new(base)
+        .set("ip.id", Generator::Incrementing { start: 0xfffe, step: 1, count: 10 })
+        .set("udp.srcport", Generator::Random { seed: 7, count: 3, min: 1024, max: 2047 })
+        .set("ip.dst", Generator::Fixed(9));
+    let packets: Vec<Packet> = template.zip().collect();
+    assert_eq!(packets.len(), 3);
+    let ids: Vec<u16> = packets.iter().map(|p| *p.get_ip_header().unwrap().get_identification()).collect();
+    assert_eq!(ids, vec![0xfffe, 0xffff, 0]);
+    let ports: Vec<u16> = packets.iter().map(|p| *p.get_udp_header().unwrap().get_src_port()).collect();
+    assert!(ports.iter().all(|p| (1024..2048).contains(p)));
+    let again: Vec<u16> = template.zip().map(|p| *p.get_udp_header().unwrap().get_src_port()).collect();
+    assert_eq!(ports, again);
+    assert!(packets.iter().all(|p| p.get_ip_header().unwrap().get_dst_ip().to_string() == "0.0.0.9"));
+    assert_eq!(Generator::random(1, 5).len(), Some(5));
+    assert!(Generator::range(5, 4).is_empty());
+
+    // bounds the wrong way round are swapped
+    let swapped = Generator::Random { seed: 7, count: 100, min: 2047, max: 1024 };
+    let ordered = Generator::Random { seed: 7, count: 100, min: 1024, max: 2047 };
+    assert!((0..100).all(|i| swapped.get(i) == ordered.get(i)));
+}
+
+#[test]
+fn test_template_computed_fields() {
+    use template::{Generator, Template};
+    let base = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP) / headers::UdpHeader::new(5000, 53) / "query";
+    let template = Template::new(base)
+        .set("ip.checksum", Generator::Fixed(0x1234))
+        .set("udp.length", Generator::List(vec![99, 100]))
+        .set("ip.hdr_len", Generator::Fixed(6));
+    let packets: Vec<Vec<u8>> = template.product().map(|p| p.into_vec()).collect();
+    assert_eq!(packets.len(), 2);
+    for (data, udp_length) in packets.iter().zip([99u16, 100]) {
+        assert_eq!(data[0], 0x46);
+        assert_eq!(&data[10..12], &[0x12, 0x34]);
+        assert_eq!(&data[24..26], &udp_length.to_be_bytes());
+    }
+}
+
+#[test]
+#[should_panic(expected = "eth.fcs is in the padding or FCS of the packet, which can't be templated")]
+fn test_template_rejects_trailer_fields() {
+    use template::{Generator, Template};
+    let mut base = headers::EthernetHeader::default() / headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP) / headers::UdpHeader::new(5000, 53);
+    base.set_pad_to_minimum(true);
+    base.set_fcs(true);
+    let _ = Template::new(base).set("eth.fcs", Generator::Fixed(0));
+}
+
+#[test]
+fn test_fuzzer_leaves_the_trailer_alone() {
+    let mut base = headers::EthernetHeader::default() / headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP) / headers::UdpHeader::new(5000, 53);
+    base.set_pad_to_minimum(true);
+    base.set_fcs(true);
+    let mutants: Vec<fuzz::Mutant> = fuzz::Fuzzer::new(base, 1).take(500).collect();
+    assert!(mutants.iter().all(|m| !m.mutation.to_string().contains("eth.padding") && !m.mutation.to_string().contains("eth.fcs")));
+}
+
+#[test]
+fn test_fuzzer_is_reproducible() {
+    use fuzz::{Fuzzer, Mutation};
+    let base = headers::EthernetHeader::default()
+        / headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP)
+        / headers::UdpHeader::new(5000, 53)
+        / "query";
+    let first: Vec<fuzz::Mutant> = Fuzzer::new(base.clone(), 7).take(200).collect();
+    let second: Vec<fuzz::Mutant> = Fuzzer::new(base.clone(), 7).take(200).collect();
+    assert_eq!(first, second);
+    assert_eq!(Fuzzer::new(base.clone(), 7).mutant(123), first[123]);
+    assert_ne!(Fuzzer::new(base.clone(), 8).take(200).collect::<Vec<_>>(), first);
+
+    // every kind of mutation which applies to the packet turns up, and none of the mutants are the original packet
+    let original = base.clone().into_vec();
+    let kinds: std::collections::HashSet<std::mem::Discriminant<Mutation>> = first.iter().map(|m| std::mem::discriminant(&m.mutation)).collect();
+    assert_eq!(kinds.len(), 7);
+    assert!(first.iter().filter(|m| m.data == original).count() < 20);
+    assert!(first.iter().all(|m| !m.mutation.to_string().is_empty()));
+}
+
+#[test]
+fn test_mutations() {
+    use fuzz::Mutation;
+    let base = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP) / headers::UdpHeader::new(5000, 53) / "query";
+    let original = base.clone().into_vec();
+
+    // the mutated field is the only thing wrong, the checksum still matches it
+    let ttl = Mutation::BoundaryValue { layer: 0, field: "ip.ttl", value: 255 }.apply(&base);
+    assert_eq!(ttl[8], 255);
+    assert_eq!(checksum(&ttl[..20], 5), u16::from_be_bytes([ttl[10], ttl[11]]));
+
+    let bad = Mutation::BadChecksum { layer: 0, field: "ip.checksum", value: 0x1234 }.apply(&base);
+    assert_eq!(&bad[10..12], &[0x12, 0x34]);
+    assert_eq!(bad[12..], original[12..]);
+
+    let length = Mutation::WrongLength { layer: 1, field: "udp.length", value: 99 }.apply(&base);
+    assert_eq!(&length[24..26], &[0, 99]);
+
+    assert_eq!(Mutation::Truncate { length: 22 }.apply(&base), original[..22].to_vec());
+    assert_eq!(Mutation::RemoveLayer { layer: 0 }.apply(&base), original[20..].to_vec());
+    let swapped = Mutation::SwapLayers { first: 0, second: 1 }.apply(&base);
+    assert_eq!(swapped[..4], original[20..24]);
+    assert_eq!(swapped[8], 0x45);
+    assert_eq!(Mutation::DuplicateLayer { layer: 1 }.apply(&base).len(), original.len() + 8);
+}
+
+#[test]
+fn test_minimize_corpus() {
+    let corpus = vec![vec![1, 2, 3], vec![1], vec![2, 2], vec![2, 9, 9, 9], vec![3, 3, 3, 3]];
+    // pretend the system under test takes a different path for each first byte, and for long inputs
+    let kept = fuzz::minimize_corpus(corpus, |input| vec![input[0] as usize, 100 + input.len().min(4) / 4]);
+    assert_eq!(kept, vec![vec![1], vec![2, 2], vec![2, 9, 9, 9], vec![3, 3, 3, 3]]);
+}
+
+#[test]
+fn test_overrides_survive_finalize() {
+    let mut p = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP) / headers::UdpHeader::new(5000, 53) / "query";
+    let original = p.clone().into_vec();
+    p.set_override(0, ComputedField::Length, 1000);
+    p.set_override(0, ComputedField::HeaderLength, 6);
+    p.set_override(1, ComputedField::Checksum, 0);
+    p.extend_payload(b"more".iter().copied());
+    p.insert_header(0, headers::EthernetHeader::default());
+    let data = p.clone().into_vec();
+    let ip = &data[14..34];
+    assert_eq!(ip[0], 0x46);
+    assert_eq!(&ip[2..4], &1000u16.to_be_bytes());
+    // the header checksum is still calculated, over the wrong values
+    assert_eq!(checksum(ip, 5), u16::from_be_bytes([ip[10], ip[11]]));
+    assert_eq!(&data[40..42], &[0, 0]);
+    assert_eq!(&data[38..40], &[0, 17]);
+    assert_eq!(p.get_override(1, ComputedField::Length), Some(1000));
+    assert_eq!(p.get_override(2, ComputedField::Length), None);
+
+    p.remove_header(0);
+    p.set_payload(b"query".to_vec());
+    p.clear_overrides();
+    assert_eq!(p.into_vec(), original);
+}
+
+#[test]
+fn test_header_length_overrides_keep_the_headers_readable() {
+    let mut ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP);
+    ip.set_options(vec![1, 1, 1, 0]);
+    let mut p = headers::EthernetHeader::default() / ip / headers::TcpHeader::new(1, 2) / "data";
+    p.set_override(1, ComputedField::HeaderLength, 15);
+    p.set_override(2, ComputedField::HeaderLength, 0);
+    assert_eq!(p.clone().into_vec()[14], 0x4f);
+    assert_eq!(p.get_ip_header().unwrap().get_options(), &vec![1, 1, 1, 0]);
+    assert_eq!(p.get_tcp_header().unwrap().get_src_port(), &1);
+    assert_eq!(p.get_layers().len(), 3);
+    assert!(format!("{:?}", p).contains("options: [1, 1, 1, 0]"));
+    assert!(p.to_rust().contains("packet.set_override(1, ComputedField::HeaderLength, 15);\n"));
+    assert_eq!(p.get_payload(), &b"data".to_vec());
+    let _ = (p.show(), p.lint(), p.diff(&p.clone()));
+}
+
+#[test]
+fn test_override_checks_the_header_has_the_field() {
+    let mut p = headers::EthernetHeader::default()
+        / headers::IpHeader::new([0xfd00, 0, 0, 0, 0, 0, 0, 1], [0xfd00, 0, 0, 0, 0, 0, 0, 2], Protocol::TCP)
+        / headers::TcpHeader::new(1, 2);
+    assert!(!p.has_computed_field(0, ComputedField::Checksum));
+    assert!(!p.has_computed_field(1, ComputedField::Checksum));
+    assert!(p.has_computed_field(1, ComputedField::Length));
+    assert!(p.has_computed_field(2, ComputedField::HeaderLength));
+    assert!(!p.has_computed_field(3, ComputedField::Checksum));
+    p.set_override(2, ComputedField::HeaderLength, 15);
+    p.set_override(2, ComputedField::Checksum, 0xbeef);
+    let data = p.clone().into_vec();
+    assert_eq!(data[54 + 12] >> 4, 15);
+    assert_eq!(&data[54 + 16..54 + 18], &[0xbe, 0xef]);
+    p.clear_override(2, ComputedField::Checksum);
+    assert_ne!(&p.clone().into_vec()[54 + 16..54 + 18], &[0xbe, 0xef]);
+    assert!(std::panic::catch_unwind(move || p.set_override(0, ComputedField::Checksum, 0)).is_err());
+}
+
+#[test]
+fn test_too_much_data_does_not_panic() {
+    let ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);
+    let p = Packet::from_layers(vec![ip.into(), headers::UdpHeader::new(5000, 53).into()], vec![0u8; 65510]);
+    let data = p.into_vec();
+    assert_eq!(data.len(), 65538);
+    // the lengths wrap around rather than the packet refusing to be built
+    assert_eq!(&data[2..4], &[0, 2]);
+}
+
+/// an ethernet frame with IPv4 and TCP options, the NS flag set and a payload, with correct lengths and checksums
+fn captured_packet_with_options() -> Vec<u8> {
+    let mut ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP);
+    ip.set_flags(0);
+    ip.set_options(vec![0x94, 0x04, 0x00, 0x00]);
+    let mut tcp = headers::TcpHeader::new(40000, 443);
+    tcp.set_reserved(0x1);
+    tcp.set_options(vec![0x02, 0x04, 0x05, 0xb4, 0x01, 0x01, 0x08, 0x0a, 0, 0, 0, 1, 0, 0, 0, 2]);
+    (headers::EthernetHeader::default() / ip / tcp / "hello").into_vec()
+}
+
+#[test]
+fn test_parse_exact_round_trip() {
+    let captured = captured_packet_with_options();
+    assert_eq!(captured[14], 0x46);
+    assert_eq!(captured[14 + 24 + 12], 0x91);
+    let p = Packet::parse(&captured).unwrap();
+    assert_eq!(p.get_ip_header().unwrap().get_options(), &vec![0x94, 0x04, 0x00, 0x00]);
+    assert_eq!(*p.get_tcp_header().unwrap().get_reserved(), 0x1);
+    assert_eq!(p.get_tcp_header().unwrap().get_options().len(), 16);
+    assert_eq!(p.get_payload(), &b"hello".to_vec());
+    assert_eq!(p.into_vec(), captured);
+
+    // a bad checksum and total length are kept by parse_exact, and put right by parse
+    let mut bad = captured.clone();
+    bad[14 + 2..14 + 4].copy_from_slice(&999u16.to_be_bytes());
+    bad[14 + 10] ^= 0xff;
+    bad[38 + 16] ^= 0xff;
+    assert_eq!(Packet::parse_exact(&bad).unwrap().into_vec(), bad);
+    assert_eq!(Packet::parse(&bad).unwrap().into_vec(), captured);
+}
+
+#[test]
+fn test_parse_exact_only_changes_touched_fields() {
+    let mut bad = captured_packet_with_options();
+    bad[38 + 16] ^= 0xff;
+    let mut p = Packet::parse_exact(&bad).unwrap();
+    let mut ip = *p.get_ip_header().unwrap();
+    ip.set_ttl(1);
+    p.update_header(ip);
+    let data = p.clone().into_vec();
+    assert_eq!(data[14 + 8], 1);
+    assert_eq!(data[..22], bad[..22]);
+    assert_eq!(data[23..], bad[23..]);
+
+    // the dependent checksums are only recalculated when asked for
+    p.clear_override(1, ComputedField::Checksum);
+    let data = p.clone().into_vec();
+    assert_eq!(checksum(&data[14..38], 5), u16::from_be_bytes([data[24], data[25]]));
+    assert_eq!(data[38 + 16], bad[38 + 16]);
+    p.clear_overrides();
+    let data = p.into_vec();
+    assert_eq!(Packet::parse_exact(&data).unwrap().into_vec(), Packet::parse(&data).unwrap().into_vec());
+}
+
+#[test]
+fn test_option_mutations() {
+    use fuzz::Mutation;
+    let base = Packet::parse(&captured_packet_with_options()).unwrap();
+    let original = base.clone().into_vec();
+    // the maximum segment size option taken out of the tcp header
+    let removed = Mutation::RemoveOption { layer: 2, option: 0 }.apply(&base);
+    assert_eq!(removed.len(), original.len() - 4);
+    assert_eq!(removed[38 + 12] >> 4, 8);
+    assert_eq!(removed[38 + 20..38 + 22], [0x01, 0x01]);
+    let duplicated = Mutation::DuplicateOption { layer: 1, option: 0 }.apply(&base);
+    assert_eq!(duplicated[14], 0x47);
+    assert_eq!(duplicated[34..42], [0x94, 0x04, 0x00, 0x00, 0x94, 0x04, 0x00, 0x00]);
+    assert_eq!(checksum(&duplicated[14..42], 5), u16::from_be_bytes([duplicated[24], duplicated[25]]));
+}
+
+#[test]
+fn test_parse_truncated_header() {
+    let captured = captured_packet_with_options();
+    // cut off part way through the tcp options
+    let partial = Packet::parse_partial(&captured[..60]).unwrap();
+    assert_eq!(partial.packet.get_protocols(), vec![Protocol::ETH, Protocol::IP]);
+    assert_eq!(partial.packet.clone().into_vec(), captured[..38].to_vec());
+    assert_eq!(partial.truncated, Some(Truncated::Header { index: 2, proto: Protocol::TCP, data: captured[38..60].to_vec() }));
+    assert_eq!(partial.captured_length, 60);
+    assert_eq!(partial.original_length, captured.len());
+    assert_eq!(Packet::parse(&captured[..60]).err(), Some(ParseError::InvalidLength));
+
+    // cut off within the ip header, which still says how long the packet was
+    let partial = Packet::parse_partial(&captured[..20]).unwrap();
+    assert_eq!(partial.packet.layer_count(), 1);
+    assert_eq!(partial.truncated, Some(Truncated::Header { index: 1, proto: Protocol::IP, data: captured[14..20].to_vec() }));
+    assert_eq!(partial.original_length, captured.len());
+    assert_eq!(Packet::parse_partial(&[]).err(), Some(ParseError::InvalidLength));
+}
+
+#[test]
+fn test_parse_truncated_payload() {
+    let captured = captured_packet_with_options();
+    let partial = Packet::parse_partial(&captured[..captured.len() - 2]).unwrap();
+    assert_eq!(partial.truncated, Some(Truncated::Payload));
+    assert_eq!(partial.packet.get_payload(), &b"hel".to_vec());
+    assert_eq!(partial.original_length - partial.captured_length, 2);
+    // the length fields are left saying how long the packet really was
+    assert_eq!(partial.packet.get_ip_header().unwrap().get_packet_len(), &(captured.len() as u16 - 14));
+
+    let complete = Packet::parse_partial(&captured).unwrap();
+    assert!(!complete.is_truncated());
+    assert_eq!(complete.original_length, complete.captured_length);
+}
+
+#[test]
+fn test_strict_parsing() {
+    let captured = captured_packet_with_options();
+    let (packet, warnings) = Packet::parse_with(&captured, &ParseConfig::strict()).unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(packet.into_vec(), captured);
+
+    let mut bad = captured.clone();
+    bad[38 + 16] ^= 0xff;
+    assert_eq!(Packet::parse_with(&bad, &ParseConfig::strict()).err(), Some(ParseError::FailedCheck(ParseCheck::Checksums)));
+    assert!(Packet::parse_with(&bad, &ParseConfig::strict().skip(ParseCheck::Checksums)).is_ok());
+    assert_eq!(Packet::parse_with(&captured[..60], &ParseConfig::strict()).err(), Some(ParseError::InvalidLength));
+}
+
+#[test]
+fn test_lenient_parsing_warns() {
+    let mut bad = captured_packet_with_options();
+    bad[14 + 2..14 + 4].copy_from_slice(&999u16.to_be_bytes());
+    bad[14 + 6] |= 0x80;
+    bad[38 + 12] |= 0x02;
+    let (packet, warnings) = Packet::parse_with(&bad, &ParseConfig::lenient()).unwrap();
+    let found: Vec<(ParseCheck, usize)> = warnings.iter().map(|w| (w.check, w.layer)).collect();
+    assert_eq!(found, vec![
+        (ParseCheck::LengthFields, 1),
+        (ParseCheck::Checksums, 1),
+        (ParseCheck::ReservedBits, 1),
+        (ParseCheck::Checksums, 2),
+        (ParseCheck::ReservedBits, 2),
+    ]);
+    assert_eq!(warnings[0].to_string(), "layer 1: ip.len is 999, should be 65");
+    assert_eq!(packet.get_ip_header().unwrap().get_packet_len(), &65);
+    let (packet, _) = Packet::parse_with(&bad, &ParseConfig::lenient().exact()).unwrap();
+    assert_eq!(packet.into_vec(), bad);
+}
+
+#[test]
+fn test_lenient_parsing_keeps_what_it_cant_parse_as_payload() {
+    let mut ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);
+    ip.set_next_protocol_number(99);
+    let data = (ip / "unknown").into_vec();
+    assert_eq!(Packet::parse(&data).err(), Some(ParseError::InvalidFormat));
+    let (packet, warnings) = Packet::parse_with(&data, &ParseConfig::default()).unwrap();
+    assert_eq!(packet.get_protocols(), vec![Protocol::IP]);
+    assert_eq!(packet.get_payload(), &b"unknown".to_vec());
+    assert_eq!(warnings[0].to_string(), "layer 0: ip.proto 99 can't be parsed");
+    assert_eq!(Packet::parse_with(&data, &ParseConfig::strict()).err(), Some(ParseError::FailedCheck(ParseCheck::KnownProtocols)));
+
+    let captured = captured_packet_with_options();
+    let (packet, warnings) = Packet::parse_with(&captured[..60], &ParseConfig::lenient()).unwrap();
+    assert_eq!(packet.get_payload().len(), 22);
+    assert!(warnings.iter().any(|w| w.message == "the TCP header is cut off after 22 bytes"));
+}
+
+#[test]
+fn test_lint_clean_packet() {
+    let mut ip = headers::IpHeader::new([192, 168, 1, 10], [192, 168, 1, 1], Protocol::TCP);
+    ip.set_ttl(64);
+    let mut tcp = headers::TcpHeader::new(40000, 80);
+    tcp.set_flag(headers::TcpFlags::Syn);
+    let packet = headers::EthernetHeader::default() / ip / tcp;
+    assert_eq!(packet.lint(), vec![]);
+}
+
+#[test]
+fn test_lint_findings() {
+    use lint::Severity;
+    let eth = headers::EthernetHeader::new([0xff; 6], [0xff; 6], ethertype_numbers::ETHERTYPE_ARP);
+    let mut ip = headers::IpHeader::new([224, 0, 0, 1], [10, 0, 0, 1], Protocol::UDP);
+    ip.set_ttl(0);
+    ip.set_flags(0b100);
+    let mut packet = eth / ip / headers::UdpHeader::new(5000, 53) / "query";
+    packet.set_override(2, ComputedField::Length, 100);
+    let found: Vec<(Severity, usize, &str)> = packet.lint().iter().map(|f| (f.severity, f.layer, f.field)).collect();
+    assert_eq!(found, vec![
+        (Severity::Error, 0, "eth.src"),
+        (Severity::Error, 0, "eth.type"),
+        (Severity::Warning, 1, "ip.ttl"),
+        (Severity::Error, 1, "ip.src"),
+        (Severity::Warning, 1, "ip.flags.rb"),
+        (Severity::Error, 2, "udp.length"),
+    ]);
+    assert_eq!(packet.lint()[3].to_string(), "error: layer 1 (ip.src): the source address 224.0.0.1 is a multicast address");
+
+    let mut tcp = headers::TcpHeader::new(1, 2);
+    tcp.set_flags(0x03);
+    tcp.set_reserved(0x2);
+    let packet = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP) / tcp;
+    let found: Vec<&str> = packet.lint().iter().map(|f| f.field).collect();
+    assert_eq!(found, vec!["ip.proto", "tcp.flags", "tcp.flags.res"]);
+}
+
+#[test]
+fn test_lint_gratuitous_arp() {
+    use lint::Severity;
+    let mac = [2, 0, 0, 0, 0, 1];
+    let broadcast_reply = |sender_ip: [u8; 4], target_ip: [u8; 4]| {
+        let mut arp = headers::ArpHeader::new(mac, sender_ip, [0xff; 6], target_ip);
+        arp.set_opcode(headers::REPLY);
+        headers::EthernetHeader::new(mac, [0xff; 6], ethertype_numbers::ETHERTYPE_ARP) / arp
+    };
+    assert_eq!(broadcast_reply([10, 0, 0, 1], [10, 0, 0, 1]).lint(), vec![]);
+    let findings = broadcast_reply([10, 0, 0, 1], [10, 0, 0, 2]).lint();
+    assert_eq!(findings.iter().map(|f| (f.severity, f.layer, f.field)).collect::<Vec<_>>(), vec![(Severity::Warning, 1, "arp.src.proto_ipv4")]);
+    assert_eq!(
+        findings[0].message,
+        "the reply is broadcast like a gratuitous ARP, but its sender address 10.0.0.1 is not its target address 10.0.0.2"
+    );
+
+    // an ordinary reply to whoever asked, and a request, which is broadcast to find the target
+    let mut reply = headers::ArpHeader::new(mac, [10, 0, 0, 1], [2, 0, 0, 0, 0, 2], [10, 0, 0, 2]);
+    reply.set_opcode(headers::REPLY);
+    assert_eq!((headers::EthernetHeader::new(mac, [2, 0, 0, 0, 0, 2], ethertype_numbers::ETHERTYPE_ARP) / reply).lint(), vec![]);
+    let request = headers::ArpHeader::new(mac, [10, 0, 0, 1], [0; 6], [10, 0, 0, 2]);
+    assert_eq!((headers::EthernetHeader::new(mac, [0xff; 6], ethertype_numbers::ETHERTYPE_ARP) / request).lint(), vec![]);
+}
+
+#[test]
+fn test_vlan_tags() {
+    let eth = headers::EthernetHeader::new([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2], 0);
+    let ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);
+    let packet = eth / headers::VlanHeader::new(100) / headers::VlanHeader::new(200) / ip / headers::UdpHeader::new(5000, 53);
+    let data = packet.into_vec();
+    assert_eq!(&data[12..22], &[0x81, 0x00, 0x00, 100, 0x81, 0x00, 0x00, 200, 0x08, 0x00]);
+    assert_eq!(data.len(), 14 + 4 + 4 + 20 + 8);
+
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(parsed.get_protocols(), vec![Protocol::ETH, Protocol::VLAN, Protocol::VLAN, Protocol::IP, Protocol::UDP]);
+    // the innermost tag, as with any protocol which appears more than once
+    assert_eq!(parsed.get_vlan_header().unwrap().get_vlan_id(), &200);
+    assert_eq!(parsed.dissect()[2].get_field("vlan.id").unwrap().value(), Some(200));
+    assert_eq!(parsed.lint(), vec![]);
+    assert_eq!(parsed.into_vec(), data);
+}
+
+#[test]
+fn test_vlan_tag_fields() {
+    let mut tag = headers::VlanHeader::new(0xabc);
+    tag.set_priority(5);
+    tag.set_drop_eligible(true);
+    let eth = headers::EthernetHeader::new([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2], ethertype_numbers::ETHERTYPE_QINQ_LEGACY);
+    let data = (eth / tag / headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP) / headers::UdpHeader::new(1, 2)).into_vec();
+    assert_eq!(&data[12..16], &[0x91, 0x00, 0xba, 0xbc]);
+
+    let parsed = Packet::parse(&data).unwrap();
+    let tag = parsed.get_vlan_header().unwrap();
+    assert_eq!((tag.get_priority(), tag.get_drop_eligible(), tag.get_vlan_id()), (&5, &true, &0xabc));
+    assert_eq!(tag.get_eth_type(), &ethertype_numbers::ETHERTYPE_IPV4);
+    assert!(ethertype_numbers::is_vlan_tag(0x9100) && !ethertype_numbers::is_vlan_tag(0x0800));
+}
+
+#[test]
+fn test_mac_addr_parsing_and_formatting() {
+    let mac: MacAddr = "00:1B:21:0a:0b:0c".parse().unwrap();
+    assert_eq!(mac, [0x00, 0x1b, 0x21, 0x0a, 0x0b, 0x0c]);
+    assert_eq!("00-1b-21-0a-0b-0c".parse::<MacAddr>(), Ok(mac));
+    assert_eq!("001b.210a.0b0c".parse::<MacAddr>(), Ok(mac));
+    assert_eq!("0:1b:21:a:b:c".parse::<MacAddr>(), Ok(mac));
+    assert_eq!(mac.to_string(), "00:1b:21:0a:0b:0c");
+    assert_eq!(mac.format(MacFormat::Dash), "00-1b-21-0a-0b-0c");
+    assert_eq!(mac.format(MacFormat::Dot), "001b.210a.0b0c");
+    assert_eq!("00:1b:21:0a:0b".parse::<MacAddr>(), Err(ParseError::InvalidFormat));
+    assert_eq!("00:1b:21:0a:0b:0g".parse::<MacAddr>(), Err(ParseError::InvalidCharacter));
+    assert_eq!("001b.210a.b0c".parse::<MacAddr>(), Err(ParseError::InvalidFormat));
+
+    let eth = headers::EthernetHeader::new(mac, MacAddr::BROADCAST, ethertype_numbers::ETHERTYPE_ARP);
+    assert_eq!(eth.to_string(), "Ethernet II, Src: 00:1b:21:0a:0b:0c, Dst: ff:ff:ff:ff:ff:ff, Type: 0x0806");
+}
+
+#[test]
+fn test_mac_addr_classification() {
+    assert!(MacAddr::BROADCAST.is_broadcast() && MacAddr::BROADCAST.is_multicast());
+    assert!(MacAddr::ZERO.is_zero() && MacAddr::ZERO.is_unicast() && MacAddr::ZERO.is_universal());
+    assert!(MacAddr::new(0x02, 0x42, 0xac, 0x11, 0, 2).is_locally_administered());
+
+    let group = MacAddr::from_ipv4_multicast([239, 129, 1, 2].into()).unwrap();
+    assert_eq!(group.to_string(), "01:00:5e:01:01:02");
+    assert!(group.is_multicast());
+    assert_eq!(MacAddr::from_ipv4_multicast([10, 0, 0, 1].into()), None);
+    let group = MacAddr::from_ipv6_multicast("ff02::1:ff00:1234".parse().unwrap()).unwrap();
+    assert_eq!(group.to_string(), "33:33:ff:00:12:34");
+
+    let mac = MacAddr::new(0x00, 0x1b, 0x21, 0x0a, 0x0b, 0x0c);
+    assert_eq!(mac.to_eui64(), [0x02, 0x1b, 0x21, 0xff, 0xfe, 0x0a, 0x0b, 0x0c]);
+    assert_eq!(MacAddr::from_eui64(mac.to_eui64()), Some(mac));
+    assert_eq!(MacAddr::from_eui64([0; 8]), None);
+    assert_eq!(mac.to_ipv6_link_local().to_string(), "fe80::21b:21ff:fe0a:b0c");
+}
+
+#[cfg(feature = "oui")]
+#[test]
+fn test_mac_addr_vendor() {
+    assert_eq!(MacAddr::new(0x00, 0x50, 0x56, 1, 2, 3).vendor(), Some("VMware"));
+    assert_eq!(MacAddr::new(0x02, 0, 0, 0, 0, 1).vendor(), None);
+}
+
+#[test]
+fn test_ethernet_padding_and_fcs() {
+    assert_eq!(ethernet_fcs(b"123456789"), 0xcbf43926);
+    let ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);
+    let mut packet = headers::EthernetHeader::new([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2], 0) / ip / headers::UdpHeader::new(5000, 53) / "hi";
+    assert_eq!(packet.len(), 44);
+    packet.set_pad_to_minimum(true);
+    assert_eq!(packet.len(), 60);
+    assert_eq!(packet.get_padding(), &[0; 16][..]);
+    assert_eq!(packet.get_ip_header().unwrap().get_packet_len(), &30);
+    packet.set_fcs(true);
+    assert_eq!(packet.len(), 64);
+    assert_eq!(packet.verify_fcs(), Some(true));
+    let data = packet.clone().into_vec();
+    assert_eq!(packet.get_fcs(), Some(ethernet_fcs(&data[..60])));
+    assert_eq!(&data[60..], &ethernet_fcs(&data[..60]).to_le_bytes());
+    assert_eq!(packet.dissect()[0].get_field("eth.fcs").unwrap().offset, 60);
+
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(parsed.get_payload(), &b"hi".to_vec());
+    assert_eq!(parsed.get_padding(), &[0; 16][..]);
+    assert_eq!(parsed.get_fcs(), packet.get_fcs());
+    assert_eq!(parsed.into_vec(), data);
+
+    packet.set_fcs_override(0x12345678);
+    assert_eq!(packet.verify_fcs(), Some(false));
+    packet.clear_overrides();
+    assert_eq!(packet.verify_fcs(), Some(true));
+    let ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);
+    let mut unpadded = ip / headers::UdpHeader::new(5000, 53);
+    unpadded.set_pad_to_minimum(true);
+    unpadded.set_fcs(true);
+    assert_eq!(unpadded.len(), 28);
+}
+
+#[test]
+fn test_parse_bad_fcs() {
+    let ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);
+    let mut packet = headers::EthernetHeader::new([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2], 0) / ip / headers::UdpHeader::new(5000, 53);
+    packet.set_pad_to_minimum(true);
+    packet.set_fcs_override(0xdeadbeef);
+    let data = packet.into_vec();
+    let good = ethernet_fcs(&data[..60]);
+
+    let config = ParseConfig::lenient().fcs(true);
+    let (parsed, warnings) = Packet::parse_with(&data, &config).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].to_string(), format!("layer 0: eth.fcs is 0xdeadbeef, should be 0x{:08x}", good));
+    assert_eq!(parsed.get_fcs(), Some(good));
+    assert_eq!(parsed.get_padding().len(), 18);
+    assert!(parsed.get_payload().is_empty());
+    let (parsed, _) = Packet::parse_with(&data, &config.exact()).unwrap();
+    assert_eq!(parsed.into_vec(), data);
+    assert_eq!(Packet::parse_with(&data, &ParseConfig::strict().fcs(true)).err(), Some(ParseError::FailedCheck(ParseCheck::Checksums)));
+
+    // without being told, a bad FCS can't be told apart from the rest of the frame
+    assert_eq!(Packet::parse(&data).unwrap().get_payload().len(), 22);
+}
+
+#[test]
+fn test_8023_llc_frames() {
+    let stp_multicast = MacAddr::new(0x01, 0x80, 0xc2, 0, 0, 0);
+    let eth = headers::EthernetHeader::new(MacAddr::new(2, 0, 0, 0, 0, 1), stp_multicast, 0);
+    let llc = headers::LlcHeader::new(headers::SAP_STP, headers::SAP_STP, headers::LLC_UI);
+    let mut packet = eth / llc / vec![0u8; 35];
+    packet.set_pad_to_minimum(true);
+    let data = packet.into_vec();
+    assert_eq!(data.len(), 60);
+    assert_eq!(&data[12..17], &[0, 38, 0x42, 0x42, 0x03]);
+
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(parsed.get_protocols(), vec![Protocol::ETH, Protocol::LLC]);
+    assert_eq!(parsed.get_payload().len(), 35);
+    assert_eq!(parsed.get_padding().len(), 8);
+    assert_eq!(parsed.dissect()[0].get_field("eth.len").unwrap().value(), Some(38));
+    assert_eq!(parsed.summary(), "802.3 02:00:00:00:00:01 > 01:80:c2:00:00:00 len=35");
+    assert_eq!(parsed.into_vec(), data);
+
+    let mut bad = data.clone();
+    bad[13] = 100;
+    let (_, warnings) = Packet::parse_with(&bad, &ParseConfig::lenient()).unwrap();
+    assert_eq!(warnings[0].to_string(), "layer 0: eth.len is 100, should be 46");
+
+    let llc = headers::LlcHeader::new(headers::SAP_IPX, headers::SAP_IPX, 0x1234);
+    assert_eq!(llc.get_length(), 4);
+    let data = (headers::EthernetHeader::default() / llc / "ipx").into_vec();
+    assert_eq!(&data[14..18], &[0xe0, 0xe0, 0x34, 0x12]);
+    assert_eq!(Packet::parse(&data).unwrap().get_llc_header().unwrap().get_control(), &0x1234);
+}
+
+#[test]
+fn test_snap_frames() {
+    let ip = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);
+    let packet = headers::EthernetHeader::new([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2], 0)
+        / headers::LlcHeader::default()
+        / headers::SnapHeader::default()
+        / ip
+        / headers::UdpHeader::new(5000, 53);
+    assert_eq!(packet.lint(), vec![]);
+    let data = packet.into_vec();
+    assert_eq!(&data[12..22], &[0, 36, 0xaa, 0xaa, 0x03, 0, 0, 0, 0x08, 0x00]);
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(parsed.get_protocols(), vec![Protocol::ETH, Protocol::LLC, Protocol::SNAP, Protocol::IP, Protocol::UDP]);
+    assert_eq!(parsed.into_vec(), data);
+
+    let cdp = headers::EthernetHeader::default() / headers::LlcHeader::default() / headers::SnapHeader::new(headers::OUI_CISCO, 0x2000) / "cdp";
+    let parsed = Packet::parse(&cdp.into_vec()).unwrap();
+    assert_eq!(parsed.get_protocols(), vec![Protocol::ETH, Protocol::LLC, Protocol::SNAP]);
+    assert_eq!(parsed.get_payload(), &b"cdp".to_vec());
+    assert_eq!(parsed.dissect()[2].get_field("llc.pid").unwrap().show, "0x2000");
+}
+
+#[test]
+fn test_parse_arp_and_rarp() {
+    let sender = MacAddr::new(0, 0x11, 0x22, 0x33, 0x44, 0x55);
+    let mut packet = headers::EthernetHeader::new(sender, MacAddr::BROADCAST, 0)
+        / headers::ArpHeader::new(sender, [10, 0, 0, 1], MacAddr::ZERO, [10, 0, 0, 2]);
+    packet.set_pad_to_minimum(true);
+    let data = packet.into_vec();
+    assert_eq!(data.len(), 60);
+    assert_eq!(&data[12..14], &[0x08, 0x06]);
+
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(parsed.get_protocols(), vec![Protocol::ETH, Protocol::ARP]);
+    assert_eq!(parsed.get_padding().len(), 18);
+    assert!(parsed.get_payload().is_empty());
+    let arp = parsed.get_arp_header().unwrap();
+    assert_eq!(arp.get_sender_mac(), Some(sender));
+    assert_eq!(arp.get_destination_ip(), Some(std::net::Ipv4Addr::new(10, 0, 0, 2)));
+    assert_eq!(parsed.into_vec(), data);
+
+    let mut rarp = headers::ArpHeader::new(sender, [0; 4], sender, [0; 4]);
+    rarp.set_opcode(headers::REVERSE_REQUEST);
+    let data = (headers::EthernetHeader::new(sender, MacAddr::BROADCAST, 0) / rarp).into_vec();
+    assert_eq!(&data[12..14], &[0x80, 0x35]);
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(parsed.get_protocols(), vec![Protocol::ETH, Protocol::ARP]);
+    assert!(parsed.get_arp_header().unwrap().is_reverse());
+    assert_eq!(parsed.summary(), "RARP who-is 00:11:22:33:44:55 tell 00:11:22:33:44:55");
+    assert!(parsed.show().starts_with("Ethernet II"));
+    assert!(parsed.show().contains("Reverse Address Resolution Protocol (reverse request (3))"));
+}
+
+#[test]
+fn test_arp_address_sizes() {
+    // IPv4 over InfiniBand uses 20 byte hardware addresses
+    let sender: Vec<u8> = (1..=20).collect();
+    let arp = headers::ArpHeader::with_addresses(headers::HARDWARE_INFINIBAND, 0x0800, sender.clone(), vec![10, 0, 0, 1], vec![0; 20], vec![10, 0, 0, 2]);
+    assert_eq!((arp.get_hardware_size(), arp.get_protocol_size()), (&20, &4));
+    assert_eq!(arp.get_length(), 56);
+    assert_eq!(arp.get_sender_mac(), None);
+    assert_eq!(arp.get_sender_ip(), Some(std::net::Ipv4Addr::new(10, 0, 0, 1)));
+
+    let data = (headers::EthernetHeader::default() / arp).into_vec();
+    let parsed = Packet::parse(&data).unwrap();
+    let parsed_arp = parsed.get_arp_header().unwrap();
+    assert_eq!(parsed_arp.get_sender_hw_address(), &sender);
+    assert_eq!(parsed_arp.get_destination_proto_address(), &vec![10, 0, 0, 2]);
+    let dissected = &parsed.dissect()[1];
+    assert_eq!(dissected.get_field("arp.hw.type").unwrap().show, "InfiniBand (32)");
+    assert_eq!(dissected.get_field("arp.src.hw").unwrap().show.len(), 59);
+    assert_eq!(dissected.get_field("arp.dst.proto_ipv4").unwrap().show, "10.0.0.2");
+    assert_eq!(parsed.summary(), "ARP who-has 10.0.0.2 tell 10.0.0.1");
+    assert_eq!(parsed.into_vec(), data);
+
+    // the addresses in the header run past the end of the frame
+    let mut short = data.clone();
+    short[18] = 40;
+    assert_eq!(Packet::parse(&short).unwrap_err(), ParseError::InvalidLength);
+}
+
+#[test]
+fn test_arp_helpers() {
+    let (mine, theirs) = (MacAddr::new(2, 0, 0, 0, 0, 1), MacAddr::new(2, 0, 0, 0, 0, 2));
+    let request = neighbor::arp_request(mine, [10, 0, 0, 1], [10, 0, 0, 2]);
+    assert_eq!(request.summary(), "ARP who-has 10.0.0.2 tell 10.0.0.1");
+    assert_eq!(request.get_eth_header().unwrap().get_dst_mac(), &MacAddr::BROADCAST);
+    assert_eq!(request.lint(), vec![]);
+
+    let reply = neighbor::arp_reply(&request, theirs).unwrap();
+    assert_eq!(reply.summary(), "ARP 10.0.0.2 is-at 02:00:00:00:00:02");
+    let eth = reply.get_eth_header().unwrap();
+    assert_eq!((eth.get_src_mac(), eth.get_dst_mac()), (&theirs, &mine));
+    assert_eq!(reply.get_arp_header().unwrap().get_destination_mac(), Some(mine));
+    assert!(neighbor::arp_reply(&reply, theirs).is_none());
+
+    let probe = neighbor::arp_probe(mine, [169, 254, 1, 1]).get_arp_header().unwrap();
+    assert_eq!(probe.get_sender_ip(), Some(std::net::Ipv4Addr::UNSPECIFIED));
+    assert_eq!(probe.get_destination_ip(), Some(std::net::Ipv4Addr::new(169, 254, 1, 1)));
+    let announce = neighbor::arp_announce(mine, [169, 254, 1, 1]).get_arp_header().unwrap();
+    assert_eq!((announce.get_opcode(), announce.get_sender_ip()), (&headers::REQUEST, announce.get_destination_ip()));
+    let gratuitous = neighbor::gratuitous_arp(mine, [10, 0, 0, 1]);
+    assert_eq!(gratuitous.summary(), "ARP 10.0.0.1 is-at 02:00:00:00:00:01");
+    assert_eq!(gratuitous.get_arp_header().unwrap().get_destination_ip(), Some(std::net::Ipv4Addr::new(10, 0, 0, 1)));
+}
+
+#[test]
+fn test_ndp_helpers() {
+    let (mine, theirs) = (MacAddr::new(2, 0, 0, 0, 0, 1), MacAddr::new(2, 0, 0, 0, 0, 2));
+    let (my_ip, their_ip): (std::net::Ipv6Addr, std::net::Ipv6Addr) = ("fe80::1".parse().unwrap(), "fe80::12:3456".parse().unwrap());
+    let solicitation = neighbor::neighbor_solicitation(mine, my_ip, their_ip);
+    let ip = solicitation.get_ip_header().unwrap();
+    assert_eq!((ip.get_next_protocol(), ip.get_ttl()), (&protocol_numbers::IPPROTO_ICMPV6, &255));
+    assert_eq!(ip.get_dst_ip().to_string(), "ff02::1:ff12:3456");
+    assert_eq!(solicitation.get_eth_header().unwrap().get_dst_mac().to_string(), "33:33:ff:12:34:56");
+    let data = solicitation.into_vec();
+    assert_eq!(&data[(data.len() - 8)..], &[1, 1, 2, 0, 0, 0, 0, 1]);
+
+    // the ICMPv6 checksum covers the pseudo header of source, destination, length and next header
+    let mut sum: u32 = data[22..54].chunks(2).map(|w| u16::from_be_bytes([w[0], w[1]]) as u32).sum();
+    sum += (data.len() - 54) as u32 + 58;
+    sum += data[54..].chunks(2).map(|w| u16::from_be_bytes([w[0], w[1]]) as u32).sum::<u32>();
+    while sum >> 16 != 0 {
+        sum = (sum >> 16) + (sum & 0xffff);
+    }
+    assert_eq!(sum, 0xffff);
+
+    let solicitation = neighbor::neighbor_solicitation(mine, my_ip, their_ip);
+    let advertisement = neighbor::neighbor_advertisement(&solicitation, theirs).unwrap();
+    let icmp = advertisement.get_icmp_header().unwrap();
+    assert_eq!((icmp.get_msg_type(), icmp.get_identifier()), (&neighbor::NEIGHBOR_ADVERTISEMENT, &(neighbor::NA_SOLICITED | neighbor::NA_OVERRIDE)));
+    assert_eq!(advertisement.get_eth_header().unwrap().get_dst_mac(), &mine);
+    assert_eq!(advertisement.get_ip_header().unwrap().get_src_ip().to_string(), "fe80::12:3456");
+    assert_eq!(&advertisement.get_payload()[16..], &[2, 1, 2, 0, 0, 0, 0, 2]);
+
+    let probe = neighbor::duplicate_address_probe(mine, their_ip);
+    assert_eq!(probe.get_payload().len(), 16);
+    assert!(neighbor::neighbor_advertisement(&probe, theirs).is_none());
+    let unsolicited = neighbor::unsolicited_neighbor_advertisement(mine, my_ip);
+    assert_eq!(unsolicited.get_ip_header().unwrap().get_dst_ip().to_string(), "ff02::1");
+    assert_eq!(unsolicited.get_icmp_header().unwrap().get_identifier(), &neighbor::NA_OVERRIDE);
+}
+
+#[test]
+fn test_lldp() {
+    let chassis = MacAddr::new(0, 0x11, 0x22, 0x33, 0x44, 0x55);
+    let mut lldp = headers::LldpHeader::new(chassis, "ge-0/0/1", 120);
+    lldp.add_tlv(headers::LldpTlv::SystemName(String::from("switch1")))
+        .add_tlv(headers::LldpTlv::Capabilities { system: headers::CAPABILITY_BRIDGE, enabled: headers::CAPABILITY_BRIDGE })
+        .add_tlv(headers::LldpTlv::management_ip([10, 0, 0, 1], 3))
+        .add_tlv(headers::LldpTlv::PortVlanId(10))
+        .add_tlv(headers::LldpTlv::MaxFrameSize(1522));
+    let mut packet = headers::EthernetHeader::new(chassis, headers::LLDP_MULTICAST, 0) / lldp;
+    packet.set_pad_to_minimum(true);
+    let data = packet.into_vec();
+    assert_eq!(&data[12..14], &[0x88, 0xcc]);
+    // the chassis id TLV: type 1, length 7, the MAC address subtype and the address
+    assert_eq!(&data[14..23], &[0x02, 0x07, 0x04, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+    assert_eq!(&data[(data.len() - 2)..], &[0, 0]);
+
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(parsed.get_protocols(), vec![Protocol::ETH, Protocol::LLDP]);
+    assert!(parsed.get_padding().is_empty());
+    let lldp = parsed.get_lldp_header().unwrap();
+    assert_eq!(lldp.get_chassis_id(), Some((headers::CHASSIS_ID_MAC_ADDRESS, &chassis.octets()[..])));
+    assert_eq!(lldp.get_port_id(), Some((headers::PORT_ID_INTERFACE_NAME, &b"ge-0/0/1"[..])));
+    assert_eq!(lldp.get_ttl(), Some(120));
+    assert_eq!(lldp.get_system_name(), Some("switch1"));
+    assert_eq!(lldp.get_tlv(headers::TLV_ORGANIZATIONALLY_SPECIFIC), Some(&headers::LldpTlv::PortVlanId(10)));
+    assert_eq!(lldp.get_tlvs().last(), Some(&headers::LldpTlv::End));
+    assert_eq!(parsed.into_vec(), data);
+
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(parsed.summary(), "LLDP chassis 00:11:22:33:44:55 port ge-0/0/1 ttl=120");
+    let dissected = &parsed.dissect()[1];
+    assert_eq!(dissected.get_field("lldp.mgn.addr").unwrap().show, "10.0.0.1");
+    assert_eq!(dissected.get_field("lldp.ieee.802_1.port_vlan.id").unwrap().show, "10");
+    assert_eq!(dissected.get_field("lldp.ieee.802_3.max_frame_size").unwrap().show, "1522");
+    assert!(parsed.show().contains("Link Layer Discovery Protocol"));
+    assert!(parsed.lint().is_empty());
+    let code = parsed.to_rust();
+    assert!(code.contains("let lldp = headers::LldpHeader::from_tlvs(vec![\n    headers::LldpTlv::chassis_mac([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]),\n"));
+    assert!(code.contains("    headers::LldpTlv::SystemName(String::from(\"switch1\")),\n"));
+    assert!(code.contains("    headers::LldpTlv::MaxFrameSize(1522),\n    headers::LldpTlv::End,\n]);\n"));
+}
+
+#[test]
+fn test_lldp_unusual_tlvs() {
+    // a reserved TLV type, a TLV of an unknown organisation and a chassis id TLV too short for a MAC address
+    let tlvs = vec![
+        headers::LldpTlv::ChassisId { subtype: headers::CHASSIS_ID_MAC_ADDRESS, id: vec![1, 2, 3] },
+        headers::LldpTlv::port_name("eth0"),
+        headers::LldpTlv::Ttl(0),
+        headers::LldpTlv::Unknown { tlv_type: 9, value: vec![0xde, 0xad] },
+        headers::LldpTlv::OrganizationallySpecific { oui: [0x00, 0x0e, 0xcf], subtype: 1, info: vec![0x42] },
+        headers::LldpTlv::End,
+    ];
+    let data = (headers::EthernetHeader::new(MacAddr::new(0, 0x11, 0x22, 0x33, 0x44, 0x55), headers::LLDP_MULTICAST, 0)
+        / headers::LldpHeader::from_tlvs(tlvs.clone()))
+    .into_vec();
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(parsed.get_lldp_header().unwrap().get_tlvs(), &tlvs);
+    assert_eq!(parsed.into_vec(), data);
+
+    // an LLDPDU without the mandatory TLVs or an end TLV is still built, but lint finds it
+    let lldp = headers::LldpHeader::from_tlvs(vec![headers::LldpTlv::Ttl(120)]);
+    let packet = headers::EthernetHeader::new(MacAddr::new(0, 0x11, 0x22, 0x33, 0x44, 0x55), headers::LLDP_MULTICAST, 0) / lldp;
+    let findings = packet.lint();
+    assert_eq!(findings.len(), 2);
+    assert_eq!(findings[0].severity, lint::Severity::Error);
+    assert_eq!(findings[1].severity, lint::Severity::Warning);
+
+    // a TLV whose length runs past the end of the frame
+    let mut truncated = data[..14].to_vec();
+    truncated.extend_from_slice(&[0x02, 0x07, 0x04, 0x00, 0x11]);
+    assert!(Packet::parse(&truncated).is_err());
+}
+
+#[test]
+fn test_mpls_label_stack() {
+    let eth = headers::EthernetHeader::new([0, 0x11, 0x22, 0x33, 0x44, 0x55], [0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb], 0);
+    let mut outer = headers::MplsHeader::new(100);
+    outer.set_traffic_class(5);
+    let packet = eth
+        / outer
+        / headers::MplsHeader::new(200)
+        / headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP)
+        / headers::UdpHeader::new(1234, 53)
+        / "hello";
+    let data = packet.into_vec();
+    assert_eq!(&data[12..14], &[0x88, 0x47]);
+    // the outer label has the bottom of stack bit cleared, the inner one has it set
+    assert_eq!(&data[14..22], &[0x00, 0x06, 0x4a, 0x40, 0x00, 0x0c, 0x81, 0x40]);
+
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(parsed.get_protocols(), vec![Protocol::ETH, Protocol::MPLS, Protocol::MPLS, Protocol::IP, Protocol::UDP]);
+    let labels: Vec<(u32, bool)> = parsed.get_mpls_labels().iter().map(|l| (*l.get_label(), *l.get_bottom_of_stack())).collect();
+    assert_eq!(labels, vec![(100, false), (200, true)]);
+    assert_eq!(parsed.get_mpls_labels()[0].get_traffic_class(), &5);
+    assert_eq!(parsed.get_mpls_header().unwrap().get_label(), &200);
+    assert_eq!(parsed.summary(), "IP 10.0.0.1:1234 > 10.0.0.2:53 UDP len=5");
+    assert_eq!(parsed.dissect()[2].get_field("mpls.bottom").unwrap().show, "1");
+    assert!(parsed.lint().is_empty());
+    assert!(parsed.to_rust().contains("let mut mpls = headers::MplsHeader::new(100);\nmpls.set_traffic_class(5);\nmpls.set_bottom_of_stack(false);\n"));
+    assert_eq!(parsed.into_vec(), data);
+
+    // IPv6 is guessed from the version after the bottom label too
+    let v6 = headers::EthernetHeader::default()
+        / headers::MplsHeader::new(headers::LABEL_IPV6_EXPLICIT_NULL)
+        / headers::IpHeader::new([0xfd00, 0, 0, 0, 0, 0, 0, 1], [0xfd00, 0, 0, 0, 0, 0, 0, 2], Protocol::UDP)
+        / headers::UdpHeader::new(1234, 53);
+    let (parsed, warnings) = Packet::parse_with(&v6.into_vec(), &ParseConfig::strict()).unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(parsed.get_protocols(), vec![Protocol::ETH, Protocol::MPLS, Protocol::IP, Protocol::UDP]);
+    assert_eq!(parsed.dissect()[1].get_field("mpls.label").unwrap().show, "IPv6 Explicit-Null (2)");
+}
+
+#[test]
+fn test_mpls_pseudowire() {
+    let customer = headers::EthernetHeader::new([0x02, 0, 0, 0, 0, 0x01], [0x02, 0, 0, 0, 0, 0x02], 0)
+        / headers::IpHeader::new([192, 168, 0, 1], [192, 168, 0, 2], Protocol::TCP)
+        / headers::TcpHeader::new(1234, 80);
+    let mut packet = headers::EthernetHeader::default() / headers::MplsHeader::new(16) / headers::MplsHeader::new(1000) / headers::PwControlWordHeader::new(7);
+    for layer in customer.get_layers() {
+        packet.push_header(layer);
+    }
+    let data = packet.into_vec();
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(
+        parsed.get_protocols(),
+        vec![Protocol::ETH, Protocol::MPLS, Protocol::MPLS, Protocol::PWCW, Protocol::ETH, Protocol::IP, Protocol::TCP]
+    );
+    assert_eq!(parsed.get_pw_control_word_header().unwrap().get_sequence_number(), &7);
+    assert_eq!(parsed.summary(), "IP 192.168.0.1:1234 > 192.168.0.2:80 TCP [] len=0");
+    assert_eq!(parsed.into_vec(), data);
+
+    // anything else after the bottom label is payload
+    let data = (headers::EthernetHeader::default() / headers::MplsHeader::new(16) / vec![0x10, 0, 0, 0]).into_vec();
+    let parsed = Packet::parse(&data).unwrap();
+    assert_eq!(parsed.get_protocols(), vec![Protocol::ETH, Protocol::MPLS]);
+    assert_eq!(parsed.summary(), "MPLS labels=16 len=4");
+
+    // from_layers leaves the bottom of stack bits as they are, so a broken stack can be built and linted
+    let packet = Packet::from_layers(vec![headers::MplsHeader::new(16).into(), headers::MplsHeader::new(17).into()], Vec::new());
+    let findings: Vec<(usize, &str)> = packet.lint().iter().map(|f| (f.layer, f.field)).collect();
+    assert_eq!(findings, vec![(0, "mpls.bottom")]);
+}
+
+#[test]
+fn test_nested_headers_use_the_innermost() {
+    let outer = headers::IpHeader::new([192, 0, 2, 1], [192, 0, 2, 2], Protocol::IP);
+    let inner = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);
+    let mut packet = outer / inner / headers::UdpHeader::new(1234, 53);
+    assert_eq!(packet.get_layer_index(Protocol::IP), Some(1));
+    assert_eq!(packet.get_ip_header().unwrap().get_src_ip().to_string(), "10.0.0.1");
+
+    let mut replacement = headers::IpHeader::new([10, 0, 0, 3], [10, 0, 0, 2], Protocol::UDP);
+    replacement.set_ttl(5);
+    packet.update_header(replacement);
+    let layers = packet.get_layers();
+    match (&layers[0], &layers[1]) {
+        (headers::AnyHeader::Ip(outer), headers::AnyHeader::Ip(inner)) => {
+            assert_eq!(outer.get_src_ip().to_string(), "192.0.2.1");
+            assert_eq!((inner.get_src_ip().to_string(), *inner.get_ttl()), (String::from("10.0.0.3"), 5));
+        }
+        _ => panic!("expected two IP layers"),
+    }
+}
+
+#[test]
+fn test_dissect_icmpv6_type_names() {
+    let (mac, ip): (MacAddr, std::net::Ipv6Addr) = (MacAddr::new(2, 0, 0, 0, 0, 1), "fe80::1".parse().unwrap());
+    let solicitation = neighbor::neighbor_solicitation(mac, ip, "fe80::2".parse().unwrap());
+    assert_eq!(solicitation.dissect()[2].get_field("icmp.type").unwrap().show, "135 (Neighbor Solicitation)");
+    let advertisement = neighbor::unsolicited_neighbor_advertisement(mac, ip);
+    assert_eq!(advertisement.dissect()[2].get_field("icmp.type").unwrap().show, "136 (Neighbor Advertisement)");
+    let ping6 = headers::IpHeader::new(ip, "fe80::2".parse::<std::net::Ipv6Addr>().unwrap(), Protocol::ICMP) / headers::IcmpHeader::new(128, 0, 1, 1);
+    assert_eq!(ping6.dissect()[1].get_field("icmp.type").unwrap().show, "128 (Echo (ping) request)");
+
+    // the same type numbers under IPv4 are ICMP
+    let ping = headers::IpHeader::new([10, 0, 0, 1], [10, 0, 0, 2], Protocol::ICMP) / headers::IcmpHeader::new(8, 0, 1, 1);
+    assert_eq!(ping.dissect()[1].get_field("icmp.type").unwrap().show, "8 (Echo (ping) request)");
+    assert_eq!(dissect::icmpv6_type_name(135), Some("Neighbor Solicitation"));
+    assert_eq!(dissect::icmp_type_name(135), None);
+}
+
+#[test]
+fn test_answer_a_captured_neighbor_solicitation() {
+    let captured = [
+        0x33, 0x33, 0xff, 0x12, 0x34, 0x56, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x86, 0xdd, 0x60, 0x00, 0x00, 0x00, 0x00, 0x20, 0x3a, 0xff,
+        0xfe, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xff, 0x02, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xff, 0x12, 0x34, 0x56, 0x87, 0x00, 0x11, 0xcb, 0x00, 0x00, 0x00, 0x00, 0xfe, 0x80, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x12, 0x34, 0x56, 0x01, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01,
+    ];
+    let (solicitation, warnings) = Packet::parse_with(&captured, &ParseConfig::strict()).unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(solicitation.get_layers().iter().map(|h| h.get_proto()).collect::<Vec<_>>(), vec![Protocol::ETH, Protocol::IP, Protocol::ICMP]);
+    assert_eq!(solicitation.get_ip_header().unwrap().get_dst_ip().to_string(), "ff02::1:ff12:3456");
+
+    let theirs = MacAddr::new(2, 0, 0, 0, 0, 2);
+    let advertisement = neighbor::neighbor_advertisement(&solicitation, theirs).unwrap();
+    assert_eq!(advertisement.get_eth_header().unwrap().get_dst_mac(), &MacAddr::new(2, 0, 0, 0, 0, 1));
+    assert_eq!(advertisement.get_icmp_header().unwrap().get_msg_type(), &neighbor::NEIGHBOR_ADVERTISEMENT);
+    assert_eq!(advertisement.get_ip_header().unwrap().get_dst_ip().to_string(), "fe80::1");
+
+    // the advertisement parses back too, with its checksums right
+    assert!(Packet::parse_with(&advertisement.into_vec(), &ParseConfig::strict()).is_ok());
+}